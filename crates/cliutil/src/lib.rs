@@ -1,7 +1,16 @@
 // Copyright (c) 2026 rezky_nightky
 
-use std::io::{self, IsTerminal, Write};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ffi::OsStr;
+use std::fs;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::os::fd::{AsRawFd, RawFd};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
 
+use procscan::{mask_has_capability, CAP_DAC_READ_SEARCH, CAP_SYS_PTRACE};
 use serde::Serialize;
 
 /// Common error type for CLI tools.
@@ -13,6 +22,73 @@ pub enum AppError {
     Fatal(String),
 }
 
+/// Process exit codes shared by every tool.
+///
+/// `zenlixem doctor` reports failed checks with `Fatal` (2).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ExitCode {
+    /// Success; results may still be partial (see the JSON `partial` flag).
+    Success,
+    /// Invalid arguments or target (e.g. a path that does not exist).
+    InvalidInput,
+    /// The tool could not do its job (e.g. /proc unreadable).
+    Fatal,
+    /// The scan found nothing; only with `--fail-if-empty`.
+    Empty,
+    /// `zenlixem doctor` only: some checks warned, none failed. Shares 1
+    /// with `InvalidInput`, which doctor never returns.
+    Warnings,
+}
+
+impl ExitCode {
+    pub fn code(self) -> i32 {
+        match self {
+            ExitCode::Success => 0,
+            ExitCode::InvalidInput | ExitCode::Warnings => 1,
+            ExitCode::Fatal => 2,
+            ExitCode::Empty => 3,
+        }
+    }
+
+    /// Exit code of a completed scan: `Empty` when `fail_if_empty` was
+    /// requested and nothing was found, `Success` otherwise.
+    pub fn for_scan(found: bool, fail_if_empty: bool) -> ExitCode {
+        if fail_if_empty && !found {
+            ExitCode::Empty
+        } else {
+            ExitCode::Success
+        }
+    }
+
+    /// Terminate the process with this code.
+    pub fn exit(self) -> ! {
+        let _ = io::stdout().flush();
+        std::process::exit(self.code())
+    }
+}
+
+impl AppError {
+    pub fn exit_code(&self) -> ExitCode {
+        match self {
+            AppError::InvalidInput(_) => ExitCode::InvalidInput,
+            AppError::Fatal(_) => ExitCode::Fatal,
+        }
+    }
+}
+
+/// Report `err` (as JSON when `--json` was requested, otherwise on stderr)
+/// and exit with its code.
+pub fn exit_with_error(err: AppError, json: bool) -> ! {
+    let code = err.exit_code();
+    if json {
+        print_json_error(err);
+    } else {
+        let (AppError::InvalidInput(msg) | AppError::Fatal(msg)) = &err;
+        error(msg);
+    }
+    code.exit()
+}
+
 /// JSON error payload used by `print_json_error`.
 #[derive(Serialize)]
 pub struct JsonError {
@@ -43,18 +119,575 @@ pub fn print_json_payload<T: Serialize>(value: &T) {
     println!("{json}");
 }
 
-fn effective_uid() -> Option<u32> {
-    let s = std::fs::read_to_string("/proc/self/status").ok()?;
-    for line in s.lines() {
-        let Some(rest) = line.strip_prefix("Uid:") else {
-            continue;
+/// Identity of the machine a result came from (`--include-host`).
+///
+/// Fields are `None` when the procfs entry is unreadable.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct HostInfo {
+    pub hostname: Option<String>,
+    pub boot_id: Option<String>,
+}
+
+fn read_trimmed(path: &str) -> Option<String> {
+    let s = fs::read_to_string(path).ok()?;
+    let s = s.trim();
+    if s.is_empty() {
+        None
+    } else {
+        Some(s.to_string())
+    }
+}
+
+/// Read hostname and boot ID from /proc/sys/kernel.
+pub fn read_host_info() -> HostInfo {
+    HostInfo {
+        hostname: read_trimmed("/proc/sys/kernel/hostname"),
+        boot_id: read_trimmed("/proc/sys/kernel/random/boot_id"),
+    }
+}
+
+fn attach_host(payload: &mut serde_json::Value, host: &HostInfo) {
+    if let Some(obj) = payload.as_object_mut() {
+        obj.insert(
+            "host".to_string(),
+            serde_json::to_value(host).unwrap_or(serde_json::Value::Null),
+        );
+    }
+}
+
+/// Optional parts of the JSON result envelope, shared by all tools.
+///
+/// `privilege` is the canonical machine-readable mode field; `mode_message`
+/// is the same information as display text and is kept by default for
+/// compatibility.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct JsonEnvelope {
+    /// Add a top-level `host` object (`--include-host`).
+    pub include_host: bool,
+    /// Drop the human-oriented `mode_message` field (`--no-mode-message`).
+    pub omit_mode_message: bool,
+    /// Keep only these keys of each result (`--fields`); empty keeps all.
+    pub fields: Vec<String>,
+    /// List the PIDs behind `skipped`/`errored` (`--list-skipped`).
+    pub list_skipped: bool,
+    /// Add a top-level `scopes` array naming the evidence scanned for.
+    pub scopes: Option<ScanScopes>,
+}
+
+/// Reject `--fields` names that the current mode's results never carry.
+pub fn validate_fields(fields: &[String], known: &[&str]) -> Result<(), AppError> {
+    let unknown: Vec<&str> = fields
+        .iter()
+        .map(String::as_str)
+        .filter(|f| !known.contains(f))
+        .collect();
+    if unknown.is_empty() {
+        return Ok(());
+    }
+    Err(AppError::InvalidInput(format!(
+        "unknown field(s) for --fields: {} (available: {})",
+        unknown.join(", "),
+        known.join(", ")
+    )))
+}
+
+/// Project `results` (an array of objects, or a single object) onto `fields`,
+/// including the `results` of each per-target group under `targets`.
+fn project_results(payload: &mut serde_json::Value, fields: &[String]) {
+    if let Some(serde_json::Value::Array(groups)) = payload.get_mut("targets") {
+        for group in groups {
+            project_results(group, fields);
+        }
+    }
+
+    let keep = |obj: &mut serde_json::Map<String, serde_json::Value>| {
+        obj.retain(|k, _| fields.iter().any(|f| f == k));
+    };
+    match payload.get_mut("results") {
+        Some(serde_json::Value::Array(rows)) => {
+            for row in rows {
+                if let Some(obj) = row.as_object_mut() {
+                    keep(obj);
+                }
+            }
+        }
+        Some(serde_json::Value::Object(obj)) => keep(obj),
+        _ => {}
+    }
+}
+
+fn apply_envelope(payload: &mut serde_json::Value, envelope: &JsonEnvelope) {
+    if !envelope.fields.is_empty() {
+        project_results(payload, &envelope.fields);
+    }
+    if envelope.omit_mode_message {
+        if let Some(obj) = payload.as_object_mut() {
+            obj.remove("mode_message");
+        }
+    }
+    if envelope.include_host {
+        attach_host(payload, &read_host_info());
+    }
+}
+
+/// Print a tool's JSON result envelope, applying the optional parts.
+pub fn print_json_envelope(mut payload: serde_json::Value, envelope: &JsonEnvelope) {
+    apply_envelope(&mut payload, envelope);
+    print_json_payload(&payload);
+}
+
+/// Print a scanning tool's JSON result envelope; unlike
+/// [`print_json_envelope`] it can attach the PID lists of `stats`.
+pub fn print_scan_envelope(
+    mut payload: serde_json::Value,
+    stats: &ScanStats,
+    envelope: &JsonEnvelope,
+) {
+    if envelope.list_skipped {
+        stats.attach_pid_lists(&mut payload, PID_LIST_CAP);
+    }
+    if let (Some(scopes), Some(obj)) = (envelope.scopes, payload.as_object_mut()) {
+        obj.insert("scopes".to_string(), serde_json::json!(scopes.names()));
+    }
+    print_json_envelope(payload, envelope);
+}
+
+/// Number of distinct processes running a given command.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct CommandCount {
+    pub command: String,
+    pub count: usize,
+}
+
+/// Which evidence a path scan collects (`--no-fd`, `--no-mmap`). Skipping
+/// one kind saves reading it for every process.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ScanScopes {
+    pub fd: bool,
+    pub mmap: bool,
+}
+
+impl Default for ScanScopes {
+    fn default() -> Self {
+        ScanScopes {
+            fd: true,
+            mmap: true,
+        }
+    }
+}
+
+impl ScanScopes {
+    pub fn new(no_fd: bool, no_mmap: bool) -> Self {
+        ScanScopes {
+            fd: !no_fd,
+            mmap: !no_mmap,
+        }
+    }
+
+    /// Active scopes as used in JSON: `fd`, `mmap`.
+    pub fn names(&self) -> Vec<&'static str> {
+        let mut out = Vec::new();
+        if self.fd {
+            out.push("fd");
+        }
+        if self.mmap {
+            out.push("mmap");
+        }
+        out
+    }
+
+    /// One line for human output when a scan is narrowed, `None` by default.
+    pub fn notice(&self) -> Option<&'static str> {
+        match (self.fd, self.mmap) {
+            (true, false) => Some("Scope: open fds, cwd and root only (memory maps not scanned)"),
+            (false, true) => Some("Scope: memory maps only (open fds, cwd and root not scanned)"),
+            _ => None,
+        }
+    }
+
+    pub fn print_notice(&self) {
+        if let Some(msg) = self.notice() {
+            println!("{msg}");
+        }
+    }
+}
+
+/// A command-line target: a TCP/UDP port, a filesystem path, or a raw
+/// device/inode pair for files whose path is gone.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Target {
+    Port(u16),
+    Path(PathBuf),
+    Inode { major: u32, minor: u32, inode: u64 },
+}
+
+impl Target {
+    /// Anything that parses as a `u16` is a port; a file literally named
+    /// like a port can be given as `./8080`.
+    pub fn parse(arg: &str) -> Self {
+        match arg.parse::<u16>() {
+            Ok(port) => Target::Port(port),
+            Err(_) => Target::Path(PathBuf::from(arg)),
+        }
+    }
+
+    /// `"port"`, `"path"` or `"inode"`, as used for the JSON mode fields.
+    pub fn mode(&self) -> &'static str {
+        match self {
+            Target::Port(_) => "port",
+            Target::Path(_) => "path",
+            Target::Inode { .. } => "inode",
+        }
+    }
+}
+
+impl std::fmt::Display for Target {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Target::Port(port) => write!(f, "{port}"),
+            Target::Path(path) => write!(f, "{}", path.display()),
+            // The /proc/<pid>/maps spelling: hex major:minor, decimal inode.
+            Target::Inode {
+                major,
+                minor,
+                inode,
+            } => write!(f, "{major:02x}:{minor:02x}:{inode}"),
+        }
+    }
+}
+
+/// Whether `s` contains shell glob metacharacters (`*`, `?`, `[`).
+pub fn is_glob_pattern(s: &str) -> bool {
+    s.contains(['*', '?', '['])
+}
+
+/// Match one path component against a shell glob: `*`, `?` and bracket
+/// classes (`[abc]`, `[a-z]`, `[!x]`). `/` is never matched.
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let n: Vec<char> = name.chars().collect();
+    let (mut pi, mut ni) = (0, 0);
+    // Resume point for the most recent `*`: (pattern index after it, name index).
+    let mut star: Option<(usize, usize)> = None;
+
+    while ni < n.len() {
+        let step = match p.get(pi) {
+            Some('*') => {
+                star = Some((pi + 1, ni));
+                pi += 1;
+                continue;
+            }
+            Some('?') => Some(pi + 1),
+            Some('[') => match_class(&p[pi..], n[ni]).map(|len| pi + len),
+            Some(c) if *c == n[ni] => Some(pi + 1),
+            _ => None,
+        };
+        match (step, star) {
+            (Some(next), _) => {
+                pi = next;
+                ni += 1;
+            }
+            (None, Some((after, from))) => {
+                pi = after;
+                ni = from + 1;
+                star = Some((after, from + 1));
+            }
+            (None, None) => return false,
+        }
+    }
+    p[pi..].iter().all(|c| *c == '*')
+}
+
+/// Match `c` against the bracket class at the start of `p`, returning the
+/// class length on a match. An unterminated `[` matches itself literally.
+fn match_class(p: &[char], c: char) -> Option<usize> {
+    let Some(close) = p.iter().skip(2).position(|&x| x == ']').map(|i| i + 2) else {
+        return (c == '[').then_some(1);
+    };
+    let body = &p[1..close];
+    let (negate, body) = match body.first() {
+        Some('!') | Some('^') => (true, &body[1..]),
+        _ => (false, body),
+    };
+    let mut hit = false;
+    let mut i = 0;
+    while i < body.len() {
+        if i + 2 < body.len() && body[i + 1] == '-' {
+            hit |= body[i] <= c && c <= body[i + 2];
+            i += 3;
+        } else {
+            hit |= body[i] == c;
+            i += 1;
+        }
+    }
+    (hit != negate).then_some(close + 1)
+}
+
+/// Expand a shell glob into the existing paths it matches, sorted.
+///
+/// Wildcards are honoured in any component (not across `/`); like the shell,
+/// a wildcard does not match a leading `.` unless the pattern has one.
+/// Unreadable directories are skipped.
+pub fn expand_glob(pattern: &str) -> Vec<PathBuf> {
+    let mut current = vec![if pattern.starts_with('/') {
+        PathBuf::from("/")
+    } else {
+        PathBuf::new()
+    }];
+
+    for component in pattern.split('/').filter(|c| !c.is_empty()) {
+        let mut next = Vec::new();
+        for base in &current {
+            if !is_glob_pattern(component) {
+                next.push(base.join(component));
+                continue;
+            }
+            let dir: &Path = if base.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                base
+            };
+            let Ok(entries) = fs::read_dir(dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let name = entry.file_name();
+                let Some(name) = name.to_str() else {
+                    continue;
+                };
+                if name.starts_with('.') && !component.starts_with('.') {
+                    continue;
+                }
+                if glob_match(component, name) {
+                    next.push(base.join(name));
+                }
+            }
+        }
+        current = next;
+    }
+
+    let mut out: Vec<PathBuf> = current
+        .into_iter()
+        .filter(|p| !p.as_os_str().is_empty() && fs::symlink_metadata(p).is_ok())
+        .collect();
+    out.sort();
+    out
+}
+
+/// Kind of evidence tying a process to a target, in the canonical display
+/// order shared by every tool: open fd first, then exe (the running
+/// binary), memory mapped, cwd, root and socket.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum ReasonKind {
+    OpenFd,
+    Exe,
+    Mmap,
+    Cwd,
+    Root,
+    Socket,
+}
+
+/// Sort reasons into [`ReasonKind`] order; ties within a kind (e.g. several
+/// open fds) fall back to the reason's own ordering.
+pub fn sort_reasons<T: Ord>(reasons: &mut [T], kind: impl Fn(&T) -> ReasonKind) {
+    reasons.sort_by(|a, b| kind(a).cmp(&kind(b)).then_with(|| a.cmp(b)));
+}
+
+/// Aggregate `(pid, command)` pairs into per-command process counts.
+///
+/// A PID listed more than once (e.g. one row per port) is counted once.
+/// Sorted by count descending, then command name.
+pub fn distinct_commands<'a>(
+    holders: impl IntoIterator<Item = (i32, &'a str)>,
+) -> Vec<CommandCount> {
+    let mut seen: HashSet<i32> = HashSet::new();
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+
+    for (pid, command) in holders {
+        if seen.insert(pid) {
+            *counts.entry(command).or_default() += 1;
+        }
+    }
+
+    let mut out: Vec<CommandCount> = counts
+        .into_iter()
+        .map(|(command, count)| CommandCount {
+            command: command.to_string(),
+            count,
+        })
+        .collect();
+    out.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| a.command.cmp(&b.command))
+    });
+    out
+}
+
+/// User-requested holder exclusions (`--exclude-pid`, `--exclude-command`).
+#[derive(Clone, Debug, Default)]
+pub struct Exclusions {
+    pids: HashSet<i32>,
+    commands: HashSet<String>,
+}
+
+impl Exclusions {
+    pub fn new(pids: &[i32], commands: &[String]) -> Self {
+        Self {
+            pids: pids.iter().copied().collect(),
+            commands: commands.iter().cloned().collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pids.is_empty() && self.commands.is_empty()
+    }
+
+    /// Whether a holder should be dropped from the results.
+    pub fn excludes(&self, pid: i32, command: &str) -> bool {
+        self.pids.contains(&pid) || self.commands.contains(command)
+    }
+}
+
+/// One-line human summary, e.g. `3 (nginx x2, php-fpm, logrotate)`.
+pub fn format_distinct_commands(commands: &[CommandCount]) -> String {
+    let names = commands
+        .iter()
+        .map(|c| {
+            if c.count > 1 {
+                format!("{} x{}", c.command, c.count)
+            } else {
+                c.command.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("{} ({names})", commands.len())
+}
+
+/// Load uid -> user name from `/etc/passwd`.
+///
+/// Only the flat file is read; users provided through NSS modules
+/// (LDAP, SSSD, ...) are not included.
+pub fn load_passwd() -> io::Result<HashMap<u32, String>> {
+    let f = fs::File::open("/etc/passwd")?;
+    parse_passwd(io::BufReader::new(f))
+}
+
+/// Parse passwd(5) lines, skipping entries with an empty name or a
+/// malformed uid.
+pub fn parse_passwd<R: BufRead>(reader: R) -> io::Result<HashMap<u32, String>> {
+    let mut out = HashMap::new();
+
+    for line in reader.lines() {
+        let line = line?;
+        let mut parts = line.split(':');
+        let name = match parts.next() {
+            Some(s) if !s.is_empty() => s.to_string(),
+            _ => continue,
+        };
+        let _passwd = parts.next();
+        let uid = match parts.next() {
+            Some(s) => s.parse::<u32>().ok(),
+            None => None,
         };
-        let mut it = rest.split_whitespace();
-        let _real = it.next();
-        let effective = it.next()?;
-        return effective.parse::<u32>().ok();
+        if let Some(uid) = uid {
+            out.insert(uid, name);
+        }
+    }
+
+    Ok(out)
+}
+
+/// User name for `uid`, or the numeric uid when unknown.
+pub fn uid_to_user(uid: u32, passwd: &HashMap<u32, String>) -> String {
+    passwd.get(&uid).cloned().unwrap_or_else(|| uid.to_string())
+}
+
+/// Uid for a user `name`, the reverse of [`uid_to_user`]. A numeric name is
+/// taken as the uid itself, so unknown users can still be given by number.
+pub fn user_to_uid(name: &str, passwd: &HashMap<u32, String>) -> Option<u32> {
+    passwd
+        .iter()
+        .find(|(_, n)| n.as_str() == name)
+        .map(|(uid, _)| *uid)
+        .or_else(|| name.parse::<u32>().ok())
+}
+
+/// The identity the kernel checks file permissions against.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Credentials {
+    pub uid: u32,
+    pub gid: u32,
+    /// Supplementary groups.
+    pub groups: Vec<u32>,
+    /// Effective capability mask (`CapEff:`).
+    pub cap_eff: u64,
+}
+
+impl Credentials {
+    pub fn in_group(&self, gid: u32) -> bool {
+        self.gid == gid || self.groups.contains(&gid)
+    }
+
+    /// How much of the system these credentials can inspect: root, or
+    /// holding a capability that opens other users' `/proc/<pid>` entries.
+    pub fn privilege_mode(&self) -> PrivilegeMode {
+        if self.uid == 0 {
+            PrivilegeMode::Root
+        } else if [CAP_DAC_READ_SEARCH, CAP_SYS_PTRACE]
+            .into_iter()
+            .any(|cap| mask_has_capability(self.cap_eff, cap))
+        {
+            PrivilegeMode::Capable
+        } else {
+            PrivilegeMode::Unprivileged
+        }
     }
-    None
+}
+
+/// Effective uid/gid, supplementary groups and capabilities of this
+/// process, read from `/proc/self/status` once; `None` when that cannot
+/// be read.
+pub fn current_credentials() -> Option<&'static Credentials> {
+    static CREDS: OnceLock<Option<Credentials>> = OnceLock::new();
+    CREDS
+        .get_or_init(|| {
+            let s = std::fs::read_to_string("/proc/self/status").ok()?;
+            parse_status_credentials(&s)
+        })
+        .as_ref()
+}
+
+fn parse_status_credentials(status: &str) -> Option<Credentials> {
+    // `Uid:` / `Gid:` list real, effective, saved and filesystem ids.
+    let effective = |prefix: &str| {
+        status
+            .lines()
+            .find_map(|l| l.strip_prefix(prefix))
+            .and_then(|rest| rest.split_whitespace().nth(1))
+            .and_then(|v| v.parse::<u32>().ok())
+    };
+    let groups = status
+        .lines()
+        .find_map(|l| l.strip_prefix("Groups:"))
+        .map(|rest| {
+            rest.split_whitespace()
+                .filter_map(|g| g.parse::<u32>().ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    let cap_eff = status
+        .lines()
+        .find_map(|l| l.strip_prefix("CapEff:"))
+        .and_then(|v| u64::from_str_radix(v.trim(), 16).ok())
+        .unwrap_or(0);
+    Some(Credentials {
+        uid: effective("Uid:")?,
+        gid: effective("Gid:")?,
+        groups,
+        cap_eff,
+    })
 }
 
 const ANSI_DIM: &str = "\x1b[2m";
@@ -64,9 +697,53 @@ const ANSI_RESET: &str = "\x1b[0m";
 
 const SUITE_NAME: &str = "zenlixem";
 
+/// When warnings, errors and headers use ANSI color (`--color`).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum ColorChoice {
+    /// Color on a terminal unless `NO_COLOR` is set.
+    #[default]
+    Auto,
+    /// Color even when piped (e.g. into `less -R`).
+    Always,
+    Never,
+}
+
+static COLOR_CHOICE: AtomicU8 = AtomicU8::new(ColorChoice::Auto as u8);
+
+/// Set the process-wide color choice; call before producing output.
+pub fn set_color_choice(choice: ColorChoice) {
+    COLOR_CHOICE.store(choice as u8, Ordering::Relaxed);
+}
+
+pub fn color_choice() -> ColorChoice {
+    match COLOR_CHOICE.load(Ordering::Relaxed) {
+        1 => ColorChoice::Always,
+        2 => ColorChoice::Never,
+        _ => ColorChoice::Auto,
+    }
+}
+
+/// `NO_COLOR` only counts when set to a non-empty value (no-color.org).
+fn color_enabled(choice: ColorChoice, terminal: bool, no_color: Option<&OsStr>) -> bool {
+    match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => terminal && no_color.is_none_or(OsStr::is_empty),
+    }
+}
+
+fn use_color(stream: &impl IsTerminal) -> bool {
+    color_enabled(
+        color_choice(),
+        stream.is_terminal(),
+        std::env::var_os("NO_COLOR").as_deref(),
+    )
+}
+
 pub fn warn(message: &str) {
     let mut stderr = io::stderr();
-    if stderr.is_terminal() {
+    if use_color(&stderr) {
         let _ = writeln!(stderr, "{ANSI_YELLOW}Warning:{ANSI_RESET} {message}");
     } else {
         let _ = writeln!(stderr, "Warning: {message}");
@@ -75,22 +752,321 @@ pub fn warn(message: &str) {
 
 pub fn error(message: &str) {
     let mut stderr = io::stderr();
-    if stderr.is_terminal() {
+    if use_color(&stderr) {
         let _ = writeln!(stderr, "{ANSI_RED}Error:{ANSI_RESET} {message}");
     } else {
         let _ = writeln!(stderr, "Error: {message}");
     }
 }
 
+/// How human-facing results are rendered (`--format`).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum OutputFormat {
+    /// Tables with a privilege header and notices.
+    #[default]
+    Human,
+    /// Sorted, header-free, timestamp-free tab-separated lines for `diff`
+    /// and golden files.
+    Stable,
+}
+
+/// One `--format stable` line: tab-separated fields, with tabs and newlines
+/// inside a field turned into spaces so every record stays on one line.
+pub fn stable_line<S: AsRef<str>>(fields: impl IntoIterator<Item = S>) -> String {
+    fields
+        .into_iter()
+        .map(|f| f.as_ref().replace(['\t', '\n', '\r'], " "))
+        .collect::<Vec<_>>()
+        .join("\t")
+}
+
+/// Print `--format stable` lines sorted and deduplicated, so two runs over
+/// the same state produce identical output.
+pub fn print_stable(mut lines: Vec<String>) {
+    lines.sort();
+    lines.dedup();
+    let mut stdout = io::stdout().lock();
+    for line in lines {
+        let _ = writeln!(stdout, "{line}");
+    }
+}
+
 pub fn print_header(message: &str) {
     let mut stdout = io::stdout();
-    if stdout.is_terminal() {
+    if use_color(&stdout) {
         let _ = writeln!(stdout, "{ANSI_DIM}{message}{ANSI_RESET}");
     } else {
         let _ = writeln!(stdout, "{message}");
     }
 }
 
+/// Fallback when neither the terminal nor `COLUMNS` gives a width.
+pub const DEFAULT_TERMINAL_WIDTH: usize = 80;
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_interrupt(_signum: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+/// Turn Ctrl-C into a flag checked with [`interrupted`], so polling loops
+/// can finish the current round and exit cleanly instead of dying mid-line.
+pub fn install_interrupt_flag() {
+    // SAFETY: the handler only stores to an atomic, which is
+    // async-signal-safe.
+    unsafe {
+        libc::signal(
+            libc::SIGINT,
+            on_interrupt as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        );
+    }
+}
+
+/// Whether Ctrl-C arrived since [`install_interrupt_flag`].
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Sleep for `total`, waking early when [`interrupted`]. Returns whether
+/// the full interval elapsed.
+pub fn sleep_unless_interrupted(total: Duration) -> bool {
+    const STEP: Duration = Duration::from_millis(100);
+    let deadline = Instant::now() + total;
+    loop {
+        if interrupted() {
+            return false;
+        }
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return true;
+        }
+        std::thread::sleep(left.min(STEP));
+    }
+}
+
+/// Default minimum time between two terminal redraws.
+pub const DEFAULT_REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Coalesces terminal updates that arrive faster than the eye can follow
+/// (streamed rows, `--watch` frames), so fast scans do not flicker.
+///
+/// At most one redraw is allowed per interval; output produced in between
+/// is held by the caller and drawn with the next allowed redraw.
+#[derive(Clone, Debug)]
+pub struct RedrawThrottle {
+    interval: Duration,
+    last: Option<Instant>,
+}
+
+impl RedrawThrottle {
+    pub fn new(interval: Duration) -> Self {
+        RedrawThrottle {
+            interval,
+            last: None,
+        }
+    }
+
+    /// Whether a redraw at `now` is due; records it when it is.
+    pub fn should_redraw(&mut self, now: Instant) -> bool {
+        if !self.until_next(now).is_zero() {
+            return false;
+        }
+        self.last = Some(now);
+        true
+    }
+
+    /// Time left at `now` before the next redraw is allowed.
+    pub fn until_next(&self, now: Instant) -> Duration {
+        self.last.map_or(Duration::ZERO, |last| {
+            (last + self.interval).saturating_duration_since(now)
+        })
+    }
+}
+
+impl Default for RedrawThrottle {
+    fn default() -> Self {
+        RedrawThrottle::new(DEFAULT_REDRAW_INTERVAL)
+    }
+}
+
+/// Column count of the terminal on `fd`, via `ioctl(TIOCGWINSZ)`.
+fn tty_columns(fd: RawFd) -> Option<usize> {
+    let mut ws = libc::winsize {
+        ws_row: 0,
+        ws_col: 0,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    // SAFETY: TIOCGWINSZ only writes a `struct winsize` through the pointer,
+    // which points at a live value of that type.
+    let rc = unsafe { libc::ioctl(fd, libc::TIOCGWINSZ, &mut ws as *mut libc::winsize) };
+    (rc == 0 && ws.ws_col > 0).then_some(usize::from(ws.ws_col))
+}
+
+fn columns_from_env(value: Option<&str>) -> Option<usize> {
+    value?.trim().parse::<usize>().ok().filter(|w| *w > 0)
+}
+
+/// Width to fit table output to, or `None` when stdout is not a terminal:
+/// pipes and files always get full, untruncated lines.
+///
+/// The terminal is asked first because `COLUMNS` is a shell variable that
+/// is often not exported; then `COLUMNS`, then [`DEFAULT_TERMINAL_WIDTH`].
+pub fn terminal_width() -> Option<usize> {
+    let stdout = io::stdout();
+    if !stdout.is_terminal() {
+        return None;
+    }
+    let width = tty_columns(stdout.as_raw_fd())
+        .or_else(|| columns_from_env(std::env::var("COLUMNS").ok().as_deref()))
+        .unwrap_or(DEFAULT_TERMINAL_WIDTH);
+    Some(width)
+}
+
+/// Aligned human table: every column is as wide as its widest cell, so
+/// long commands and wide PIDs do not push later columns out of line.
+///
+/// Rows may carry detail lines (threads, caps, parents, reasons) printed
+/// verbatim below them; they do not count towards column widths.
+#[derive(Clone, Debug, Default)]
+pub struct Table {
+    headers: Vec<String>,
+    right: Vec<bool>,
+    fixed: Vec<bool>,
+    rows: Vec<(Vec<String>, Vec<String>)>,
+}
+
+impl Table {
+    pub fn new<S: Into<String>>(headers: impl IntoIterator<Item = S>) -> Self {
+        let headers: Vec<String> = headers.into_iter().map(Into::into).collect();
+        let right = vec![false; headers.len()];
+        let fixed = vec![false; headers.len()];
+        Self {
+            headers,
+            right,
+            fixed,
+            rows: Vec::new(),
+        }
+    }
+
+    /// Right-align `column` (sizes and other numbers read better that way).
+    pub fn align_right(mut self, column: usize) -> Self {
+        if let Some(r) = self.right.get_mut(column) {
+            *r = true;
+        }
+        self
+    }
+
+    /// Never narrow `column` to fit the terminal: a cut PID or port would
+    /// read as a different one.
+    pub fn fixed(mut self, column: usize) -> Self {
+        if let Some(f) = self.fixed.get_mut(column) {
+            *f = true;
+        }
+        self
+    }
+
+    /// Add a row; missing cells are blank and extra cells are dropped.
+    pub fn row<S: Into<String>>(&mut self, cells: impl IntoIterator<Item = S>) {
+        let mut cells: Vec<String> = cells.into_iter().map(Into::into).collect();
+        cells.resize(self.headers.len(), String::new());
+        self.rows.push((cells, Vec::new()));
+    }
+
+    /// Add a detail line under the last row.
+    pub fn detail(&mut self, line: impl Into<String>) {
+        if let Some((_, details)) = self.rows.last_mut() {
+            details.push(line.into());
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Column widths in characters. With a `limit`, the widest column not
+    /// marked [`fixed`](Self::fixed) is narrowed one character at a time
+    /// (never below its header) until a line fits.
+    pub fn column_widths(&self, limit: Option<usize>) -> Vec<usize> {
+        let mut widths: Vec<usize> = self.headers.iter().map(|h| h.chars().count()).collect();
+        for (cells, _) in &self.rows {
+            for (w, cell) in widths.iter_mut().zip(cells) {
+                *w = (*w).max(cell.chars().count());
+            }
+        }
+        let Some(limit) = limit else {
+            return widths;
+        };
+        let gaps = widths.len().saturating_sub(1);
+        while widths.iter().sum::<usize>() + gaps > limit {
+            let floor = |i: usize| self.headers[i].chars().count().max(1);
+            let Some(i) = (0..widths.len())
+                .filter(|&i| !self.fixed[i] && widths[i] > floor(i))
+                .max_by_key(|&i| (widths[i], std::cmp::Reverse(i)))
+            else {
+                break;
+            };
+            widths[i] -= 1;
+        }
+        widths
+    }
+
+    /// The header line followed by every row and its detail lines.
+    pub fn render(&self, limit: Option<usize>) -> Vec<String> {
+        let widths = self.column_widths(limit);
+        let mut lines = vec![self.render_line(&self.headers, &widths)];
+        for (cells, details) in &self.rows {
+            lines.push(self.render_line(cells, &widths));
+            lines.extend(details.iter().cloned());
+        }
+        lines
+    }
+
+    fn render_line(&self, cells: &[String], widths: &[usize]) -> String {
+        let last = cells.len().saturating_sub(1);
+        let mut line = String::new();
+        for (i, (cell, &width)) in cells.iter().zip(widths).enumerate() {
+            if i > 0 {
+                line.push(' ');
+            }
+            let cell = truncate_cell(cell, width);
+            if self.right[i] {
+                line.push_str(&format!("{cell:>width$}"));
+            } else if i == last {
+                line.push_str(&cell);
+            } else {
+                line.push_str(&format!("{cell:<width$}"));
+            }
+        }
+        line.truncate(line.trim_end().len());
+        line
+    }
+
+    /// Print to stdout (header dimmed like [`print_header`]), fitted to
+    /// [`terminal_width`] on a terminal and untruncated otherwise.
+    pub fn print(&self) {
+        let mut lines = self.render(terminal_width()).into_iter();
+        if let Some(header) = lines.next() {
+            print_header(&header);
+        }
+        let mut stdout = io::stdout().lock();
+        for line in lines {
+            let _ = writeln!(stdout, "{line}");
+        }
+    }
+}
+
+/// `cell` cut to `width` characters, ending in `…` when shortened.
+fn truncate_cell(cell: &str, width: usize) -> String {
+    if cell.chars().count() <= width {
+        return cell.to_string();
+    }
+    let mut out: String = cell.chars().take(width.saturating_sub(1)).collect();
+    out.push('…');
+    out
+}
+
 pub fn build_target() -> &'static str {
     option_env!("ZENLIXEM_BUILD_TARGET").unwrap_or("unknown")
 }
@@ -99,19 +1075,227 @@ pub fn git_sha() -> &'static str {
     option_env!("ZENLIXEM_GIT_SHA").unwrap_or("unknown")
 }
 
-pub fn privilege_mode() -> &'static str {
-    match effective_uid() {
-        Some(0) => "privileged",
-        _ => "unprivileged",
+/// How much of the system this process can inspect.
+///
+/// Serializes to the JSON `privilege` values; `privileged` and
+/// `unprivileged` predate `capable` and keep their spelling.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+pub enum PrivilegeMode {
+    /// Effective uid 0.
+    #[serde(rename = "privileged")]
+    Root,
+    /// Not root, but holding `cap_sys_ptrace` or `cap_dac_read_search`.
+    #[serde(rename = "capable")]
+    Capable,
+    #[serde(rename = "unprivileged")]
+    Unprivileged,
+}
+
+impl PrivilegeMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PrivilegeMode::Root => "privileged",
+            PrivilegeMode::Capable => "capable",
+            PrivilegeMode::Unprivileged => "unprivileged",
+        }
     }
+
+    pub fn message(self) -> &'static str {
+        match self {
+            PrivilegeMode::Root => "Mode: privileged (full scan)",
+            PrivilegeMode::Capable => {
+                "Mode: capable (not root, but scan capabilities held; other users' \
+                 processes are visible, root-only files may still be skipped)"
+            }
+            PrivilegeMode::Unprivileged => {
+                "Mode: unprivileged (only your own processes are fully visible; \
+                 partial results expected)"
+            }
+        }
+    }
+}
+
+pub fn privilege_mode() -> PrivilegeMode {
+    current_credentials().map_or(PrivilegeMode::Unprivileged, Credentials::privilege_mode)
 }
 
 pub fn privilege_mode_message() -> &'static str {
-    if privilege_mode() == "privileged" {
-        "Mode: privileged (full scan)"
+    privilege_mode().message()
+}
+
+/// Whether permission-denied skips are explained by running unprivileged.
+///
+/// `true` means re-running as root is expected to fill in the gaps; when
+/// already privileged, denials point at an LSM, hidepid, or similar policy.
+pub fn privilege_insufficient(skipped_permission_denied: usize) -> bool {
+    skipped_permission_denied > 0 && privilege_mode() != PrivilegeMode::Root
+}
+
+/// Processes a scan could not fully inspect.
+///
+/// Permission-denied PIDs are `skipped`; PIDs whose reads failed for any other
+/// reason (e.g. EIO on a dying mount) are `errored`. Either makes the result
+/// partial, but neither aborts the scan.
+#[derive(Clone, Debug, Default)]
+pub struct ScanStats {
+    skipped: HashSet<i32>,
+    errored: HashSet<i32>,
+    last_error: Option<String>,
+}
+
+impl ScanStats {
+    pub fn skip(&mut self, pid: i32) {
+        self.skipped.insert(pid);
+    }
+
+    pub fn error(&mut self, pid: i32, err: &io::Error) {
+        self.errored.insert(pid);
+        self.last_error = Some(format!("pid {pid}: {err}"));
+    }
+
+    /// Fold in the stats of another scan; a PID counts once across both.
+    pub fn merge(&mut self, other: &ScanStats) {
+        self.skipped.extend(&other.skipped);
+        self.errored.extend(&other.errored);
+        if other.last_error.is_some() {
+            self.last_error.clone_from(&other.last_error);
+        }
+    }
+
+    pub fn skipped(&self) -> usize {
+        self.skipped.len()
+    }
+
+    pub fn errored(&self) -> usize {
+        self.errored.len()
+    }
+
+    pub fn partial(&self) -> bool {
+        self.skipped() > 0 || self.errored() > 0
+    }
+
+    /// Add sorted `skipped_pids` and `errored_pids` arrays to `payload`, each
+    /// cut to `cap` entries; `pid_lists_truncated` says whether either was.
+    /// The `skipped`/`errored` counts stay exact.
+    pub fn attach_pid_lists(&self, payload: &mut serde_json::Value, cap: usize) {
+        let capped = |set: &HashSet<i32>| {
+            let mut pids: Vec<i32> = set.iter().copied().collect();
+            pids.sort_unstable();
+            pids.truncate(cap);
+            pids
+        };
+        let Some(obj) = payload.as_object_mut() else {
+            return;
+        };
+        let truncated = self.skipped() > cap || self.errored() > cap;
+        obj.insert("skipped_pids".to_string(), capped(&self.skipped).into());
+        obj.insert("errored_pids".to_string(), capped(&self.errored).into());
+        obj.insert("pid_lists_truncated".to_string(), truncated.into());
+    }
+
+    /// Human notice for errored PIDs, or `None` when there were none.
+    pub fn error_message(&self) -> Option<String> {
+        if self.errored.is_empty() {
+            return None;
+        }
+        let last = self.last_error.as_deref().unwrap_or("unknown error");
+        Some(format!(
+            "Partial result: {} processes could not be read (last error: {last})",
+            self.errored()
+        ))
+    }
+
+    /// Print the skip and error notices that apply, one per line.
+    pub fn print_notices(&self) {
+        if let Some(msg) = partial_result_message(self.skipped()) {
+            println!("{msg}");
+        }
+        if let Some(msg) = self.error_message() {
+            println!("{msg}");
+        }
+    }
+
+    /// Like [`ScanStats::print_notices`], but as warnings on stderr, for
+    /// output modes whose stdout must hold nothing but data.
+    pub fn warn_notices(&self) {
+        if let Some(msg) = partial_result_message(self.skipped()) {
+            warn(&msg);
+        }
+        if let Some(msg) = self.error_message() {
+            warn(&msg);
+        }
+    }
+}
+
+/// Longest `skipped_pids`/`errored_pids` list emitted by `--list-skipped`.
+pub const PID_LIST_CAP: usize = 1000;
+
+/// Human notice for permission-denied skips, or `None` when nothing was skipped.
+pub fn partial_result_message(skipped_permission_denied: usize) -> Option<String> {
+    if skipped_permission_denied == 0 {
+        return None;
+    }
+    let hint = if privilege_insufficient(skipped_permission_denied) {
+        "re-run as root for full results"
+    } else {
+        "denied despite privileges; check LSM/hidepid policy"
+    };
+    Some(format!(
+        "Partial result: {skipped_permission_denied} processes skipped (permission denied; {hint})"
+    ))
+}
+
+/// Quote an argument for display in a copy-pasteable shell command.
+fn shell_quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c));
+    if plain {
+        arg.to_string()
     } else {
-        "Mode: unprivileged (partial results expected)"
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+/// `sudo` argv re-running `argv` (program first) without the `--sudo` flag.
+///
+/// Dropping the flag means the elevated child can never re-exec again.
+pub fn sudo_argv(argv: &[String]) -> Vec<String> {
+    let mut out = vec!["sudo".to_string(), "--".to_string()];
+    out.extend(argv.iter().filter(|a| a.as_str() != "--sudo").cloned());
+    out
+}
+
+/// Re-exec the current invocation under `sudo` for `--sudo`.
+///
+/// Returns `Ok(())` without doing anything when already root; on success the
+/// process image is replaced and this never returns. If `sudo` cannot be
+/// executed, the error carries the exact command to run by hand.
+pub fn reexec_with_sudo() -> Result<(), AppError> {
+    use std::os::unix::process::CommandExt;
+
+    if current_credentials().is_some_and(|c| c.uid == 0) {
+        return Ok(());
+    }
+
+    let mut argv: Vec<String> = std::env::args().collect();
+    // Resolve the program so sudo's secure_path cannot pick a different binary.
+    if let Some(first) = argv.first_mut() {
+        if let Ok(exe) = std::env::current_exe() {
+            *first = exe.display().to_string();
+        }
     }
+    let cmd = sudo_argv(&argv);
+    let err = std::process::Command::new(&cmd[0]).args(&cmd[1..]).exec();
+    let printable = cmd
+        .iter()
+        .map(|a| shell_quote(a))
+        .collect::<Vec<_>>()
+        .join(" ");
+    Err(AppError::Fatal(format!(
+        "cannot exec sudo: {err}; run manually: {printable}"
+    )))
 }
 
 pub fn short_sha(sha: &str) -> &str {
@@ -134,6 +1318,368 @@ pub fn print_info() {
 mod tests {
     use super::*;
 
+    #[test]
+    fn redraw_throttle_coalesces_within_interval() {
+        let mut t = RedrawThrottle::new(Duration::from_millis(100));
+        let start = Instant::now();
+        assert!(t.should_redraw(start));
+        assert!(!t.should_redraw(start + Duration::from_millis(30)));
+        assert_eq!(
+            t.until_next(start + Duration::from_millis(30)),
+            Duration::from_millis(70)
+        );
+        assert!(!t.should_redraw(start + Duration::from_millis(99)));
+        assert!(t.should_redraw(start + Duration::from_millis(100)));
+        assert!(t.until_next(start + Duration::from_millis(500)).is_zero());
+
+        let mut zero = RedrawThrottle::new(Duration::ZERO);
+        assert!(zero.should_redraw(start));
+        assert!(zero.should_redraw(start));
+    }
+
+    #[test]
+    fn table_columns_fit_the_widest_cell() {
+        let mut table = Table::new(["PID", "COMMAND", "REASON"]);
+        table.row(["1", "systemd", "open_fd"]);
+        table.row(["123456", "a-very-long-command-name", "mmap"]);
+        table.row(["7", "idle", ""]);
+        table.detail("      threads: 1 x");
+        assert_eq!(table.column_widths(None), vec![6, 24, 7]);
+        assert_eq!(
+            table.render(None),
+            vec![
+                "PID    COMMAND                  REASON",
+                "1      systemd                  open_fd",
+                "123456 a-very-long-command-name mmap",
+                "7      idle",
+                "      threads: 1 x",
+            ]
+        );
+    }
+
+    #[test]
+    fn table_narrows_the_widest_column_to_fit() {
+        let mut table = Table::new(["PID", "COMMAND", "SIZE"])
+            .fixed(0)
+            .align_right(2);
+        table.row(["123456", "a-very-long-command-name", "4096"]);
+        assert_eq!(table.column_widths(Some(20)), vec![6, 8, 4]);
+        assert_eq!(table.render(Some(20))[1], "123456 a-very-… 4096");
+        // Headers are never cut; past that the line may overflow.
+        assert_eq!(table.column_widths(Some(5)), vec![6, 7, 4]);
+    }
+
+    #[test]
+    fn color_choice_respects_terminal_and_no_color() {
+        let set = Some(OsStr::new("1"));
+        let empty = Some(OsStr::new(""));
+        assert!(color_enabled(ColorChoice::Auto, true, None));
+        assert!(color_enabled(ColorChoice::Auto, true, empty));
+        assert!(!color_enabled(ColorChoice::Auto, true, set));
+        assert!(!color_enabled(ColorChoice::Auto, false, None));
+        assert!(color_enabled(ColorChoice::Always, false, set));
+        assert!(!color_enabled(ColorChoice::Never, true, None));
+    }
+
+    #[test]
+    fn exit_codes_are_stable() {
+        assert_eq!(ExitCode::Success.code(), 0);
+        assert_eq!(ExitCode::InvalidInput.code(), 1);
+        assert_eq!(ExitCode::Fatal.code(), 2);
+        assert_eq!(
+            AppError::InvalidInput(String::new()).exit_code(),
+            ExitCode::InvalidInput
+        );
+        assert_eq!(AppError::Fatal(String::new()).exit_code(), ExitCode::Fatal);
+    }
+
+    #[test]
+    fn parse_status_credentials_uses_effective_ids() {
+        let status = "Name:\tsh\nUid:\t1000\t1001\t1000\t1001\n\
+                      Gid:\t100\t101\t100\t101\nGroups:\t4 27 \n";
+        let creds = parse_status_credentials(status).unwrap();
+        assert_eq!(creds.uid, 1001);
+        assert_eq!(creds.gid, 101);
+        assert_eq!(creds.groups, vec![4, 27]);
+        assert_eq!(creds.cap_eff, 0);
+        assert!(creds.in_group(101) && creds.in_group(27) && !creds.in_group(100));
+        assert_eq!(parse_status_credentials("Name:\tsh\n"), None);
+    }
+
+    #[test]
+    fn scan_stats_counts_and_messages() {
+        let mut stats = ScanStats::default();
+        assert!(!stats.partial());
+        assert!(stats.error_message().is_none());
+
+        stats.skip(10);
+        stats.skip(10);
+        assert_eq!(stats.skipped(), 1);
+        assert!(stats.partial());
+
+        stats.error(20, &io::Error::from_raw_os_error(5));
+        assert_eq!(stats.errored(), 1);
+        let msg = stats.error_message().unwrap();
+        assert!(
+            msg.starts_with("Partial result: 1 processes could not be read (last error: pid 20: ")
+        );
+    }
+
+    #[test]
+    fn scan_stats_pid_lists_are_sorted_and_capped() {
+        let mut stats = ScanStats::default();
+        for pid in [30, 10, 20] {
+            stats.skip(pid);
+        }
+        stats.error(5, &io::Error::from_raw_os_error(5));
+
+        let mut payload = serde_json::json!({ "skipped": 3 });
+        stats.attach_pid_lists(&mut payload, 10);
+        assert_eq!(payload["skipped_pids"], serde_json::json!([10, 20, 30]));
+        assert_eq!(payload["errored_pids"], serde_json::json!([5]));
+        assert_eq!(payload["pid_lists_truncated"], false);
+
+        stats.attach_pid_lists(&mut payload, 2);
+        assert_eq!(payload["skipped_pids"], serde_json::json!([10, 20]));
+        assert_eq!(payload["pid_lists_truncated"], true);
+        assert_eq!(payload["skipped"], 3);
+    }
+
+    #[test]
+    fn columns_from_env_ignores_junk() {
+        assert_eq!(columns_from_env(Some("132")), Some(132));
+        assert_eq!(columns_from_env(Some(" 100\n")), Some(100));
+        assert_eq!(columns_from_env(Some("0")), None);
+        assert_eq!(columns_from_env(Some("wide")), None);
+        assert_eq!(columns_from_env(None), None);
+    }
+
+    #[test]
+    fn tty_columns_fails_on_non_terminal() {
+        assert_eq!(tty_columns(-1), None);
+        let file = fs::File::open("/dev/null").unwrap();
+        assert_eq!(tty_columns(file.as_raw_fd()), None);
+    }
+
+    #[test]
+    fn target_parse_classifies_ports_and_paths() {
+        assert_eq!(Target::parse("8080"), Target::Port(8080));
+        assert_eq!(Target::parse("0"), Target::Port(0));
+        assert_eq!(Target::parse("65536"), Target::Path(PathBuf::from("65536")));
+        assert_eq!(
+            Target::parse("./8080"),
+            Target::Path(PathBuf::from("./8080"))
+        );
+        assert_eq!(Target::parse("/etc/hosts").mode(), "path");
+        assert_eq!(Target::parse("443").to_string(), "443");
+
+        let inode = Target::Inode {
+            major: 8,
+            minor: 17,
+            inode: 1234,
+        };
+        assert_eq!(inode.mode(), "inode");
+        assert_eq!(inode.to_string(), "08:11:1234");
+    }
+
+    #[test]
+    fn scan_scopes_names_and_notice() {
+        let both = ScanScopes::default();
+        assert_eq!(both.names(), vec!["fd", "mmap"]);
+        assert_eq!(both.notice(), None);
+
+        let mmap_only = ScanScopes::new(true, false);
+        assert_eq!(mmap_only.names(), vec!["mmap"]);
+        assert!(mmap_only.notice().unwrap().contains("memory maps only"));
+        assert_eq!(ScanScopes::new(false, true).names(), vec!["fd"]);
+    }
+
+    #[test]
+    fn stable_line_is_tab_separated_and_single_line() {
+        assert_eq!(
+            stable_line(["42", "nginx", "open fd"]),
+            "42\tnginx\topen fd"
+        );
+        assert_eq!(stable_line(["a\tb", "c\nd"]), "a b\tc d");
+        assert_eq!(OutputFormat::default(), OutputFormat::Human);
+    }
+
+    #[test]
+    fn glob_match_wildcards_and_classes() {
+        assert!(glob_match("*.db", "app.db"));
+        assert!(glob_match("*.db", ".db"));
+        assert!(!glob_match("*.db", "app.db-wal"));
+        assert!(glob_match("app-?.log", "app-1.log"));
+        assert!(!glob_match("app-?.log", "app-12.log"));
+        assert!(glob_match("*a*b*", "xxaxxbxx"));
+        assert!(glob_match("[a-c]x", "bx"));
+        assert!(!glob_match("[!a-c]x", "bx"));
+        assert!(glob_match("[x", "[x"));
+        assert!(glob_match("*", ""));
+        assert!(!is_glob_pattern("/var/lib/app.db"));
+        assert!(is_glob_pattern("/var/lib/*.db"));
+    }
+
+    #[test]
+    fn expand_glob_lists_matching_paths() {
+        let dir = std::env::temp_dir().join(format!("zenlixem_glob_test_{}", std::process::id()));
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        for name in ["a.db", "b.db", "c.txt", ".hidden.db", "sub/d.db"] {
+            fs::write(dir.join(name), b"").unwrap();
+        }
+        let base = dir.display().to_string();
+
+        assert_eq!(
+            expand_glob(&format!("{base}/*.db")),
+            vec![dir.join("a.db"), dir.join("b.db")]
+        );
+        assert_eq!(
+            expand_glob(&format!("{base}/*/*.db")),
+            vec![dir.join("sub/d.db")]
+        );
+        assert!(expand_glob(&format!("{base}/*.none")).is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn scan_stats_merge_counts_pids_once() {
+        let mut a = ScanStats::default();
+        a.skip(1);
+        a.skip(2);
+        let mut b = ScanStats::default();
+        b.skip(2);
+        b.error(3, &io::Error::from_raw_os_error(5));
+        a.merge(&b);
+        assert_eq!(a.skipped(), 2);
+        assert_eq!(a.errored(), 1);
+        assert!(a.error_message().unwrap().contains("pid 3"));
+    }
+
+    #[test]
+    fn sort_reasons_uses_canonical_kind_order() {
+        let mut reasons = vec![
+            ("socket", ReasonKind::Socket),
+            ("mmap r-xp", ReasonKind::Mmap),
+            ("root", ReasonKind::Root),
+            ("open fd 9", ReasonKind::OpenFd),
+            ("cwd", ReasonKind::Cwd),
+            ("open fd 3", ReasonKind::OpenFd),
+        ];
+        sort_reasons(&mut reasons, |r| r.1);
+        let labels: Vec<&str> = reasons.iter().map(|r| r.0).collect();
+        assert_eq!(
+            labels,
+            vec![
+                "open fd 3",
+                "open fd 9",
+                "mmap r-xp",
+                "cwd",
+                "root",
+                "socket"
+            ]
+        );
+    }
+
+    #[test]
+    fn sudo_argv_drops_flag_and_keeps_order() {
+        let argv: Vec<String> = ["/usr/bin/whoholds", "--sudo", "--json", "/var/log/x"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(
+            sudo_argv(&argv),
+            vec!["sudo", "--", "/usr/bin/whoholds", "--json", "/var/log/x"]
+        );
+    }
+
+    #[test]
+    fn shell_quote_only_when_needed() {
+        assert_eq!(shell_quote("/var/log/x.log"), "/var/log/x.log");
+        assert_eq!(shell_quote("my file"), "'my file'");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+        assert_eq!(shell_quote(""), "''");
+    }
+
+    #[test]
+    fn apply_envelope_can_omit_mode_message() {
+        let mut payload = serde_json::json!({
+            "privilege": "unprivileged",
+            "mode_message": "Mode: unprivileged (partial results expected)",
+        });
+        apply_envelope(&mut payload, &JsonEnvelope::default());
+        assert!(payload.get("mode_message").is_some());
+
+        let envelope = JsonEnvelope {
+            omit_mode_message: true,
+            ..JsonEnvelope::default()
+        };
+        apply_envelope(&mut payload, &envelope);
+        assert!(payload.get("mode_message").is_none());
+        assert_eq!(payload["privilege"], "unprivileged");
+    }
+
+    #[test]
+    fn apply_envelope_projects_results() {
+        let envelope = JsonEnvelope {
+            fields: vec!["pid".to_string(), "command".to_string()],
+            ..JsonEnvelope::default()
+        };
+
+        let mut rows = serde_json::json!({
+            "mode": "path",
+            "results": [{ "pid": 1, "command": "init", "reason": "open fd" }],
+        });
+        apply_envelope(&mut rows, &envelope);
+        assert_eq!(
+            rows["results"],
+            serde_json::json!([{ "pid": 1, "command": "init" }])
+        );
+        assert_eq!(rows["mode"], "path");
+
+        let mut single = serde_json::json!({ "results": { "pid": 2, "user": "root" } });
+        apply_envelope(&mut single, &envelope);
+        assert_eq!(single["results"], serde_json::json!({ "pid": 2 }));
+
+        let mut multi = serde_json::json!({
+            "targets": [{ "target": "80", "results": [{ "pid": 3, "reason": "open fd" }] }],
+        });
+        apply_envelope(&mut multi, &envelope);
+        assert_eq!(
+            multi["targets"][0],
+            serde_json::json!({ "target": "80", "results": [{ "pid": 3 }] })
+        );
+    }
+
+    #[test]
+    fn validate_fields_rejects_unknown_names() {
+        let known = ["pid", "command"];
+        assert!(validate_fields(&[], &known).is_ok());
+        assert!(validate_fields(&["pid".to_string()], &known).is_ok());
+        match validate_fields(&["pid".to_string(), "uid".to_string()], &known) {
+            Err(AppError::InvalidInput(msg)) => {
+                assert!(msg.contains("uid"));
+                assert!(msg.contains("available: pid, command"));
+            }
+            other => panic!("unexpected: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn attach_host_adds_top_level_block() {
+        let mut payload = serde_json::json!({ "mode": "path", "results": [] });
+        let host = HostInfo {
+            hostname: Some("web-01".to_string()),
+            boot_id: None,
+        };
+        attach_host(&mut payload, &host);
+        assert_eq!(
+            payload["host"],
+            serde_json::json!({ "hostname": "web-01", "boot_id": null })
+        );
+        assert_eq!(payload["mode"], "path");
+    }
+
     #[test]
     fn short_sha_normal() {
         assert_eq!(
@@ -158,9 +1704,132 @@ mod tests {
     }
 
     #[test]
-    fn privilege_mode_returns_string() {
-        let mode = privilege_mode();
-        assert!(mode == "privileged" || mode == "unprivileged");
+    fn distinct_commands_counts_unique_pids() {
+        let holders = [
+            (10, "nginx"),
+            (11, "nginx"),
+            (11, "nginx"),
+            (20, "php-fpm"),
+            (30, "logrotate"),
+        ];
+        let counts = distinct_commands(holders);
+        assert_eq!(
+            counts,
+            vec![
+                CommandCount {
+                    command: "nginx".to_string(),
+                    count: 2
+                },
+                CommandCount {
+                    command: "logrotate".to_string(),
+                    count: 1
+                },
+                CommandCount {
+                    command: "php-fpm".to_string(),
+                    count: 1
+                },
+            ]
+        );
+        assert_eq!(
+            format_distinct_commands(&counts),
+            "3 (nginx x2, logrotate, php-fpm)"
+        );
+    }
+
+    #[test]
+    fn exclusions_match_pid_or_command() {
+        let ex = Exclusions::new(&[42], &["node_exporter".to_string()]);
+        assert!(!ex.is_empty());
+        assert!(ex.excludes(42, "bash"));
+        assert!(ex.excludes(7, "node_exporter"));
+        assert!(!ex.excludes(7, "bash"));
+        assert!(Exclusions::default().is_empty());
+    }
+
+    #[test]
+    fn distinct_commands_empty() {
+        assert!(distinct_commands(std::iter::empty()).is_empty());
+    }
+
+    #[test]
+    fn parse_passwd_from_buffer() {
+        let data = "root:x:0:0:root:/root:/bin/bash\n\
+                    :x:5:5::/:/bin/false\n\
+                    broken:x:notanumber:0::/:/bin/false\n\
+                    alice:x:1000:1000:Alice:/home/alice:/bin/zsh\n";
+        let map = parse_passwd(io::Cursor::new(data)).unwrap();
+        assert_eq!(map.len(), 2);
+        assert_eq!(uid_to_user(0, &map), "root");
+        assert_eq!(uid_to_user(1000, &map), "alice");
+        assert_eq!(uid_to_user(4242, &map), "4242");
+
+        assert_eq!(user_to_uid("alice", &map), Some(1000));
+        assert_eq!(user_to_uid("root", &map), Some(0));
+        assert_eq!(user_to_uid("4242", &map), Some(4242));
+        assert_eq!(user_to_uid("broken", &map), None);
+    }
+
+    #[test]
+    fn exit_code_for_scan_is_opt_in() {
+        assert_eq!(ExitCode::for_scan(true, false), ExitCode::Success);
+        assert_eq!(ExitCode::for_scan(false, false), ExitCode::Success);
+        assert_eq!(ExitCode::for_scan(true, true), ExitCode::Success);
+        assert_eq!(ExitCode::for_scan(false, true), ExitCode::Empty);
+        assert_eq!(ExitCode::Empty.code(), 3);
+    }
+
+    #[test]
+    fn privilege_mode_uses_euid_and_caps() {
+        let mode = |uid: &str, cap_eff: &str| {
+            let status = format!("Name:\tx\nUid:\t{uid}\nGid:\t0\t0\t0\t0\nCapEff:\t{cap_eff}\n");
+            parse_status_credentials(&status).unwrap().privilege_mode()
+        };
+        assert_eq!(
+            mode("1000\t0\t0\t0", "0000000000000000"),
+            PrivilegeMode::Root
+        );
+        assert_eq!(
+            mode("1000\t1000\t1000\t1000", "0000000000000004"),
+            PrivilegeMode::Capable
+        );
+        assert_eq!(
+            mode("1000\t1000\t1000\t1000", "0000000000080000"),
+            PrivilegeMode::Capable
+        );
+        // cap_net_admin alone does not help a scan.
+        assert_eq!(
+            mode("1000\t1000\t1000\t1000", "0000000000001000"),
+            PrivilegeMode::Unprivileged
+        );
+    }
+
+    #[test]
+    fn privilege_mode_serializes_to_legacy_strings() {
+        for mode in [
+            PrivilegeMode::Root,
+            PrivilegeMode::Capable,
+            PrivilegeMode::Unprivileged,
+        ] {
+            assert_eq!(serde_json::json!(mode), mode.as_str());
+        }
+        assert_eq!(PrivilegeMode::Root.as_str(), "privileged");
+        assert_eq!(PrivilegeMode::Unprivileged.as_str(), "unprivileged");
+    }
+
+    #[test]
+    fn partial_result_message_none_when_complete() {
+        assert!(!privilege_insufficient(0));
+        assert_eq!(partial_result_message(0), None);
+    }
+
+    #[test]
+    fn partial_result_message_mentions_count() {
+        let msg = partial_result_message(3).unwrap();
+        assert!(msg.starts_with("Partial result: 3 processes skipped"));
+        assert_eq!(
+            privilege_insufficient(3),
+            privilege_mode() != PrivilegeMode::Root
+        );
     }
 
     #[test]
@@ -12,6 +12,42 @@ pub struct FileId {
     pub inode: u64,
 }
 
+/// How a mapping's device and inode are compared against a [`FileId`].
+///
+/// /proc/<pid>/maps reports the device backing a mapping, which on
+/// overlayfs and some bind mounts differs from the `st_dev` that stat sees
+/// through the mount. `InodeOnly` falls back to the inode number when the
+/// devices disagree; inode numbers are only unique per filesystem, so it
+/// can match an unrelated file elsewhere.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum IdMatch {
+    #[default]
+    ExactDevInode,
+    InodeOnly,
+}
+
+impl FileId {
+    /// Whether `other` is the same file under `mode`.
+    pub fn matches(&self, other: &FileId, mode: IdMatch) -> bool {
+        match mode {
+            IdMatch::ExactDevInode => self == other,
+            IdMatch::InodeOnly => self.inode == other.inode,
+        }
+    }
+
+    /// Whether a `major:minor inode` triple as printed in /proc/<pid>/maps
+    /// is this file under `mode`.
+    pub fn matches_dev_inode(&self, major: u32, minor: u32, inode: u64, mode: IdMatch) -> bool {
+        self.matches(
+            &FileId {
+                dev: makedev(major, minor),
+                inode,
+            },
+            mode,
+        )
+    }
+}
+
 pub fn file_id_for_path(path: &Path) -> io::Result<FileId> {
     let md = fs::metadata(path)?;
     Ok(file_id_for_metadata(&md))
@@ -24,37 +60,200 @@ pub fn file_id_for_metadata(md: &fs::Metadata) -> FileId {
     }
 }
 
+/// Split a `st_dev` value into `(major, minor)` like glibc's
+/// `gnu_dev_major`/`gnu_dev_minor`. Below major 4096 and minor 2^20 this is
+/// the kernel's 32-bit `new_encode_dev` layout that /proc/<pid>/maps prints.
 pub fn dev_major_minor(dev: u64) -> (u32, u32) {
     let major = ((dev & 0x0000_0000_000f_ff00) >> 8) | ((dev & 0xffff_f000_0000_0000) >> 32);
-    let minor = (dev & 0x0000_0000_0000_00ff) | ((dev & 0x0000_0000_fff0_0000) >> 12);
+    let minor = (dev & 0x0000_0000_0000_00ff) | ((dev & 0x0000_0fff_fff0_0000) >> 12);
     (major as u32, minor as u32)
 }
 
+/// Encode `major:minor` as a `st_dev` value, the inverse of [`dev_major_minor`].
+pub fn makedev(major: u32, minor: u32) -> u64 {
+    let (major, minor) = (u64::from(major), u64::from(minor));
+    ((major & 0xffff_f000) << 32)
+        | ((major & 0x0000_0fff) << 8)
+        | ((minor & 0xffff_ff00) << 12)
+        | (minor & 0x0000_00ff)
+}
+
+/// Marker the kernel appends to readlink/maps paths of unlinked files.
+pub const DELETED_MARKER: &str = "(deleted)";
+
+/// Split a procfs-rendered path into the path proper and whether it carried
+/// the deleted marker.
+///
+/// Mainline kernels always render `" (deleted)"`; the separator whitespace
+/// is treated leniently so variants with extra or missing spaces still parse.
+pub fn strip_deleted_marker(s: &str) -> (String, bool) {
+    let trimmed = s.trim_end();
+    match trimmed.strip_suffix(DELETED_MARKER) {
+        Some(base) if !base.trim_end().is_empty() => (base.trim_end().to_string(), true),
+        _ => (s.to_string(), false),
+    }
+}
+
 pub fn format_duration_ago(d: Duration) -> String {
+    if d.as_secs() < 5 {
+        return "just now".to_string();
+    }
+    format!("{} ago", duration_amount(d))
+}
+
+/// Formats how far `d` lies ahead of now, e.g. `"3s ahead"`.
+pub fn format_duration_ahead(d: Duration) -> String {
+    if d.as_secs() < 5 {
+        return "just now".to_string();
+    }
+    format!("{} ahead", duration_amount(d))
+}
+
+/// The rolled-up magnitude of `d` in its largest whole unit: `"5s"`, `"2h"`,
+/// `"3w"`, `"1mo"` (30 days), `"1y"` (365 days).
+fn duration_amount(d: Duration) -> String {
     let secs = d.as_secs();
 
     if secs < 60 {
-        return format!("{}s ago", secs);
+        return format!("{}s", secs);
     }
 
     if secs < 60 * 60 {
-        return format!("{}m ago", secs / 60);
+        return format!("{}m", secs / 60);
     }
 
     if secs < 60 * 60 * 24 {
-        return format!("{}h ago", secs / (60 * 60));
+        return format!("{}h", secs / (60 * 60));
     }
 
-    format!("{}d ago", secs / (60 * 60 * 24))
+    let days = secs / (60 * 60 * 24);
+
+    if days < 7 {
+        return format!("{}d", days);
+    }
+
+    if days < 30 {
+        return format!("{}w", days / 7);
+    }
+
+    if days < 365 {
+        return format!("{}mo", days / 30);
+    }
+
+    format!("{}y", days / 365)
+}
+
+/// Formats a byte count with 1024-based units and one decimal, `ls -h`
+/// style: `"0B"`, `"1023B"`, `"4.0K"`, `"1.3M"`, `"2.0G"`. Values that
+/// would round up to 1024 of one unit roll over to the next.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [char; 6] = ['K', 'M', 'G', 'T', 'P', 'E'];
+    if bytes < 1024 {
+        return format!("{bytes}B");
+    }
+    let mut unit: u128 = 1024;
+    let mut idx = 0;
+    loop {
+        let tenths = (u128::from(bytes) * 10 + unit / 2) / unit;
+        if tenths < 10_240 || idx == UNITS.len() - 1 {
+            return format!("{}.{}{}", tenths / 10, tenths % 10, UNITS[idx]);
+        }
+        unit *= 1024;
+        idx += 1;
+    }
+}
+
+/// Formats unix seconds as a UTC wall-clock string, `"2024-05-01 13:22:07 UTC"`.
+pub fn format_unix_utc(secs: u64) -> String {
+    let days = secs / 86_400;
+    let rem = secs % 86_400;
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        rem / 3600,
+        (rem % 3600) / 60,
+        rem % 60
+    )
+}
+
+/// Parses a UTC wall-clock string back into unix seconds, the inverse of
+/// [`format_unix_utc`]: `"2024-05-01 13:22:07"`, with an optional `T`
+/// separator or trailing `UTC`. `None` for malformed or out-of-range parts
+/// and for dates before 1970.
+pub fn parse_unix_utc(s: &str) -> Option<u64> {
+    let s = s.trim();
+    let s = s.strip_suffix("UTC").map_or(s, str::trim_end);
+    let (date, time) = s.split_once([' ', 'T'])?;
+
+    let date: Vec<u64> = date
+        .split('-')
+        .map(str::parse)
+        .collect::<Result<_, _>>()
+        .ok()?;
+    let time: Vec<u64> = time
+        .split(':')
+        .map(str::parse)
+        .collect::<Result<_, _>>()
+        .ok()?;
+    let (&[year, month, day], &[hour, minute, second]) = (date.as_slice(), time.as_slice()) else {
+        return None;
+    };
+    if year < 1970
+        || !(1..=12).contains(&month)
+        || day == 0
+        || hour > 23
+        || minute > 59
+        || second > 59
+    {
+        return None;
+    }
+    let days = days_from_civil(year, month, day);
+    // Rejects days past the end of the month (2024-02-30).
+    if civil_from_days(days) != (year, month, day) {
+        return None;
+    }
+    Some(days * 86_400 + hour * 3600 + minute * 60 + second)
 }
 
+/// Converts a proleptic Gregorian date (year >= 1970) to days since
+/// 1970-01-01, the inverse of [`civil_from_days`].
+fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let yoe = year - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Converts days since 1970-01-01 to a proleptic Gregorian (year, month, day),
+/// counting in 400-year eras that start on March 1st.
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Formats `t` relative to now. A timestamp in the future (clock skew, NFS,
+/// a clock change) is reported as `"Ns ahead"` rather than folded into a
+/// misleading `"0s ago"`.
 pub fn format_systemtime_ago(t: SystemTime) -> String {
     let now = SystemTime::now();
-    let d = match now.duration_since(t) {
-        Ok(d) => d,
-        Err(_) => return "just now".to_string(), // clock skew: t is in the future
-    };
-    format_duration_ago(d)
+    match now.duration_since(t) {
+        Ok(d) => format_duration_ago(d),
+        Err(e) => format_duration_ahead(e.duration()),
+    }
 }
 
 #[cfg(test)]
@@ -84,6 +283,26 @@ mod tests {
         assert_eq!(format_duration_ago(Duration::from_secs(86400)), "1d ago");
     }
 
+    #[test]
+    fn format_duration_just_now() {
+        assert_eq!(format_duration_ago(Duration::from_secs(0)), "just now");
+        assert_eq!(format_duration_ago(Duration::from_secs(4)), "just now");
+    }
+
+    #[test]
+    fn format_duration_weeks_months_years() {
+        const DAY: u64 = 86400;
+        assert_eq!(format_duration_ago(Duration::from_secs(7 * DAY)), "1w ago");
+        assert_eq!(
+            format_duration_ago(Duration::from_secs(45 * DAY)),
+            "1mo ago"
+        );
+        assert_eq!(
+            format_duration_ago(Duration::from_secs(500 * DAY)),
+            "1y ago"
+        );
+    }
+
     #[test]
     fn dev_major_minor_smoke() {
         let (_maj, _min) = dev_major_minor(0);
@@ -100,8 +319,160 @@ mod tests {
     }
 
     #[test]
-    fn format_systemtime_future_returns_just_now() {
-        let future = SystemTime::now() + Duration::from_secs(3600);
-        assert_eq!(format_systemtime_ago(future), "just now");
+    fn dev_major_minor_matches_kernel_encoding() {
+        // (st_dev, major, minor); the first block is new_encode_dev:
+        // (minor & 0xff) | (major << 8) | ((minor & !0xff) << 12).
+        let table: &[(u64, u32, u32)] = &[
+            (0x0000_0000, 0, 0),
+            (0x0000_002d, 0, 45),       // btrfs/overlay anonymous dev 0:45
+            (0x0000_0801, 8, 1),        // sda1
+            (0x0000_fd00, 253, 0),      // dm-0
+            (0x0001_0303, 259, 3),      // nvme0n1p3 (blkext)
+            (0x0010_0800, 8, 256),      // sdq: minor above 255
+            (0xfff0_0fff, 15, 0xfffff), // largest kernel minor (MINORBITS = 20)
+            (0x000f_ff00, 4095, 0),     // largest 12-bit major
+            // glibc's 64-bit extension: major bits 12+ at 44, minor bits 20+ at 20..44.
+            (0x0000_1000_0000_0000, 4096, 0),
+            (0x0000_0001_0000_0000, 0, 1 << 20),
+            (0xffff_ffff_ffff_ffff, u32::MAX, u32::MAX),
+        ];
+        for &(dev, major, minor) in table {
+            assert_eq!(dev_major_minor(dev), (major, minor), "{dev:#x}");
+            assert_eq!(makedev(major, minor), dev, "{major}:{minor}");
+        }
+    }
+
+    #[test]
+    fn parse_unix_utc_inverts_format() {
+        assert_eq!(parse_unix_utc("1970-01-01 00:00:00"), Some(0));
+        assert_eq!(parse_unix_utc("2024-05-01 13:22:07"), Some(1_714_569_727));
+        assert_eq!(parse_unix_utc("2024-05-01T13:22:07"), Some(1_714_569_727));
+        assert_eq!(
+            parse_unix_utc("2024-05-01 13:22:07 UTC"),
+            Some(1_714_569_727)
+        );
+        assert_eq!(parse_unix_utc("2024-02-29 00:00:00"), Some(1_709_164_800));
+        for secs in [0, 951_782_400, 1_714_569_727, 4_102_444_799] {
+            assert_eq!(parse_unix_utc(&format_unix_utc(secs)), Some(secs));
+        }
+
+        for bad in [
+            "",
+            "2024-05-01",
+            "2024-05-01 13:22",
+            "1969-12-31 23:59:59",
+            "2023-02-29 00:00:00",
+            "2024-13-01 00:00:00",
+            "2024-05-00 00:00:00",
+            "2024-05-01 24:00:00",
+            "2024-05-01 13:60:00",
+            "2024/05/01 13:22:07",
+        ] {
+            assert_eq!(parse_unix_utc(bad), None, "{bad}");
+        }
+    }
+
+    #[test]
+    fn format_size_boundaries() {
+        assert_eq!(format_size(0), "0B");
+        assert_eq!(format_size(1023), "1023B");
+        assert_eq!(format_size(1024), "1.0K");
+        assert_eq!(format_size(1536), "1.5K");
+        assert_eq!(format_size(4096), "4.0K");
+        assert_eq!(format_size(1024 * 1024 - 1), "1.0M");
+        assert_eq!(format_size(1_363_149), "1.3M");
+        assert_eq!(format_size(2 * 1024 * 1024 * 1024), "2.0G");
+        assert_eq!(format_size(u64::MAX), "16.0E");
+    }
+
+    #[test]
+    fn id_match_modes() {
+        let id = FileId {
+            dev: makedev(8, 1),
+            inode: 42,
+        };
+        let overlay = FileId {
+            dev: makedev(0, 52),
+            inode: 42,
+        };
+        let other = FileId {
+            dev: makedev(8, 1),
+            inode: 43,
+        };
+
+        assert!(id.matches(&id, IdMatch::ExactDevInode));
+        assert!(!id.matches(&overlay, IdMatch::ExactDevInode));
+        assert!(id.matches(&overlay, IdMatch::InodeOnly));
+        assert!(!id.matches(&other, IdMatch::InodeOnly));
+
+        assert!(id.matches_dev_inode(8, 1, 42, IdMatch::ExactDevInode));
+        assert!(!id.matches_dev_inode(0, 52, 42, IdMatch::ExactDevInode));
+        assert!(id.matches_dev_inode(0, 52, 42, IdMatch::InodeOnly));
+        assert!(!id.matches_dev_inode(8, 1, 43, IdMatch::InodeOnly));
+    }
+
+    #[test]
+    fn makedev_round_trips() {
+        assert_eq!(makedev(8, 1), 0x0801);
+        for (major, minor) in [
+            (0, 0),
+            (8, 17),
+            (259, 3),
+            (4095, 255),
+            (70000, 1 << 19),
+            (1, 1 << 24),
+        ] {
+            assert_eq!(dev_major_minor(makedev(major, minor)), (major, minor));
+        }
+    }
+
+    #[test]
+    fn strip_deleted_marker_mainline() {
+        assert_eq!(
+            strip_deleted_marker("/var/log/app.log (deleted)"),
+            ("/var/log/app.log".to_string(), true)
+        );
+    }
+
+    #[test]
+    fn strip_deleted_marker_lenient_spacing() {
+        assert_eq!(
+            strip_deleted_marker("/tmp/x(deleted)"),
+            ("/tmp/x".to_string(), true)
+        );
+        assert_eq!(
+            strip_deleted_marker("/tmp/x  (deleted)\n"),
+            ("/tmp/x".to_string(), true)
+        );
+    }
+
+    #[test]
+    fn strip_deleted_marker_absent() {
+        assert_eq!(
+            strip_deleted_marker("/usr/lib/libc.so.6"),
+            ("/usr/lib/libc.so.6".to_string(), false)
+        );
+        assert_eq!(
+            strip_deleted_marker("(deleted)"),
+            ("(deleted)".to_string(), false)
+        );
+    }
+
+    #[test]
+    fn format_unix_utc_known_values() {
+        assert_eq!(format_unix_utc(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(format_unix_utc(951_782_400), "2000-02-29 00:00:00 UTC");
+        assert_eq!(format_unix_utc(1_714_569_727), "2024-05-01 13:22:07 UTC");
+        assert_eq!(format_unix_utc(4_102_444_799), "2099-12-31 23:59:59 UTC");
+    }
+
+    #[test]
+    fn format_systemtime_future_reports_ahead() {
+        let future = SystemTime::now() + Duration::from_secs(3630);
+        assert_eq!(format_systemtime_ago(future), "1h ahead");
+
+        let future = SystemTime::now() + Duration::from_secs(30);
+        let s = format_systemtime_ago(future);
+        assert!(s.ends_with("s ahead"), "{s}");
     }
 }
@@ -1,11 +1,20 @@
 // Copyright (c) 2026 rezky_nightky
 
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
 use std::fs;
 use std::io::{self, BufRead};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::ops::{ControlFlow, Deref};
+use std::os::unix::fs::MetadataExt;
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
-use fsmeta::{file_id_for_metadata, FileId};
+use serde::{Serialize, Serializer};
+
+use fsmeta::{file_id_for_metadata, file_id_for_path, makedev, strip_deleted_marker, FileId};
+
+pub use fsmeta::IdMatch;
 
 #[derive(Debug)]
 pub enum ProcAccess<T> {
@@ -15,6 +24,19 @@ pub enum ProcAccess<T> {
     Fatal(io::Error),
 }
 
+impl<T> ProcAccess<T> {
+    /// Stable name of the outcome for diagnostics: `ok`,
+    /// `permission_denied`, `gone` or `error`.
+    pub fn outcome(&self) -> &'static str {
+        match self {
+            ProcAccess::Ok(_) => "ok",
+            ProcAccess::PermissionDenied => "permission_denied",
+            ProcAccess::Gone => "gone",
+            ProcAccess::Fatal(_) => "error",
+        }
+    }
+}
+
 fn classify_proc_io_error<T>(e: io::Error) -> ProcAccess<T> {
     match e.kind() {
         io::ErrorKind::NotFound => ProcAccess::Gone,
@@ -25,16 +47,84 @@ fn classify_proc_io_error<T>(e: io::Error) -> ProcAccess<T> {
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct ProcMapEntry {
+    /// Permission column, e.g. `r-xp`.
+    pub perms: String,
     pub dev_major: u32,
     pub dev_minor: u32,
     pub inode: u64,
+    /// Mapped path with any deleted marker stripped.
     pub pathname: Option<String>,
+    /// The mapped file has been unlinked.
+    pub deleted: bool,
+}
+
+/// An open fd and the (marker-stripped) target of its magic symlink.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FdTarget {
+    pub fd: i32,
+    pub fd_path: PathBuf,
+    pub target: String,
+    /// The fd refers to an unlinked file.
+    pub deleted: bool,
+}
+
+// --- Proc root ---
+
+static PROC_ROOT: OnceLock<PathBuf> = OnceLock::new();
+
+/// Read from a captured proc tree instead of the live `/proc`.
+///
+/// Must be called before any scan; it can only be set once per process.
+pub fn set_proc_root(root: &Path) -> io::Result<()> {
+    if !root.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "{}: not a directory (extract captured archives first)",
+                root.display()
+            ),
+        ));
+    }
+    PROC_ROOT
+        .set(root.to_path_buf())
+        .map_err(|_| io::Error::new(io::ErrorKind::AlreadyExists, "proc root already set"))
+}
+
+/// Directory the readers treat as `/proc`.
+pub fn proc_root() -> &'static Path {
+    PROC_ROOT.get().map_or(Path::new("/proc"), |p| p.as_path())
+}
+
+/// Whether a captured proc tree is in use.
+///
+/// Offline, fd entries are plain symlinks and the device/inode numbers in
+/// maps belong to another machine, so targets are matched by path text.
+pub fn is_offline() -> bool {
+    PROC_ROOT.get().is_some()
+}
+
+static ID_MATCH: OnceLock<IdMatch> = OnceLock::new();
+
+/// Compare mappings against live targets with `mode` (`--inode-only`).
+///
+/// Must be called before any scan; it can only be set once per process.
+pub fn set_id_match(mode: IdMatch) {
+    let _ = ID_MATCH.set(mode);
+}
+
+/// How mappings are compared against live targets; exact by default.
+pub fn id_match() -> IdMatch {
+    ID_MATCH.get().copied().unwrap_or_default()
+}
+
+fn proc_path(rel: impl AsRef<Path>) -> PathBuf {
+    proc_root().join(rel)
 }
 
 pub fn list_pids() -> io::Result<Vec<i32>> {
     let mut pids = Vec::new();
 
-    for entry in fs::read_dir("/proc")? {
+    for entry in fs::read_dir(proc_root())? {
         let entry = entry?;
         let file_name = entry.file_name();
         let s = file_name.to_string_lossy();
@@ -47,24 +137,48 @@ pub fn list_pids() -> io::Result<Vec<i32>> {
     Ok(pids)
 }
 
+/// Shown for a comm that is empty or only whitespace, which some kernel
+/// tasks have, so command columns are never blank.
+pub const EMPTY_COMM_LABEL: &str = "<empty>";
+
+/// Contents of a `comm` file without the trailing newline; an empty or
+/// whitespace-only name becomes [`EMPTY_COMM_LABEL`].
+pub fn normalize_comm(raw: &str) -> String {
+    let name = raw.trim_end_matches(['\n', '\r']);
+    if name.trim().is_empty() {
+        return EMPTY_COMM_LABEL.to_string();
+    }
+    name.to_string()
+}
+
 #[deprecated(note = "use read_comm_access instead for proper permission handling")]
 pub fn read_comm(pid: i32) -> io::Result<String> {
-    let path = format!("/proc/{pid}/comm");
+    let path = proc_path(format!("{pid}/comm"));
     let contents = fs::read_to_string(path)?;
-    Ok(contents.trim_end_matches(['\n', '\r']).to_string())
+    Ok(normalize_comm(&contents))
 }
 
 pub fn read_comm_access(pid: i32) -> ProcAccess<String> {
-    let path = format!("/proc/{pid}/comm");
+    let path = proc_path(format!("{pid}/comm"));
     let contents = match fs::read_to_string(path) {
         Ok(c) => c,
         Err(e) => return classify_proc_io_error(e),
     };
-    ProcAccess::Ok(contents.trim_end_matches(['\n', '\r']).to_string())
+    ProcAccess::Ok(normalize_comm(&contents))
+}
+
+/// Owner uid of /proc/<pid> (the process's effective uid, or root for
+/// non-dumpable processes).
+pub fn proc_owner_uid(pid: i32) -> Option<u32> {
+    use std::os::unix::fs::MetadataExt;
+
+    fs::metadata(proc_path(pid.to_string()))
+        .ok()
+        .map(|md| md.uid())
 }
 
 pub fn fd_dir(pid: i32) -> PathBuf {
-    PathBuf::from(format!("/proc/{pid}/fd"))
+    proc_path(format!("{pid}/fd"))
 }
 
 #[deprecated(note = "use read_fd_links_access instead for proper permission handling")]
@@ -122,6 +236,116 @@ pub fn read_fd_links_access(pid: i32) -> ProcAccess<Vec<(i32, PathBuf, String)>>
     ProcAccess::Ok(out)
 }
 
+/// Size of the file behind `/proc/<pid>/fd/<fd>`.
+///
+/// Stats through the magic symlink, so this still works after the file has
+/// been unlinked. A closed fd or exited process reports `Gone`.
+pub fn fd_target_size(pid: i32, fd: i32) -> ProcAccess<u64> {
+    let path = fd_dir(pid).join(fd.to_string());
+    match fs::metadata(path) {
+        Ok(md) => ProcAccess::Ok(md.len()),
+        Err(e) => classify_proc_io_error(e),
+    }
+}
+
+/// The `flags:` field of `/proc/<pid>/fdinfo/<fd>` (octal `open(2)` flags,
+/// with `O_CLOEXEC` folded in by the kernel).
+pub fn read_fdinfo_flags(pid: i32, fd: i32) -> ProcAccess<u32> {
+    let path = proc_path(format!("{pid}/fdinfo/{fd}"));
+    let contents = match fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(e) => return classify_proc_io_error(e),
+    };
+    match parse_fdinfo_flags(&contents) {
+        Some(flags) => ProcAccess::Ok(flags),
+        None => ProcAccess::Fatal(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("no flags in /proc/{pid}/fdinfo/{fd}"),
+        )),
+    }
+}
+
+pub fn parse_fdinfo_flags(contents: &str) -> Option<u32> {
+    contents.lines().find_map(|line| {
+        let value = line.strip_prefix("flags:")?;
+        u32::from_str_radix(value.trim(), 8).ok()
+    })
+}
+
+const O_ACCMODE: u32 = 0o3;
+const O_WRONLY: u32 = 0o1;
+const O_RDWR: u32 = 0o2;
+
+/// Status flags worth naming, in display order. Values are the generic Linux
+/// ones shared by x86_64 and aarch64; `O_SYNC` includes the `O_DSYNC` bit, so
+/// it is listed first and consumes it.
+const OPEN_FLAG_NAMES: &[(u32, &str)] = &[
+    (0o2000, "append"),
+    (0o4000, "nonblock"),
+    (0o4010000, "sync"),
+    (0o10000, "dsync"),
+    (0o20000, "async"),
+    (0o1000000, "noatime"),
+    (0o2000000, "cloexec"),
+    (0o10000000, "path"),
+];
+
+/// Access mode of open `flags` from the `O_ACCMODE` bits: `ro`, `wo` or
+/// `rw`.
+pub fn access_mode(flags: u32) -> &'static str {
+    match flags & O_ACCMODE {
+        O_WRONLY => "wo",
+        O_RDWR => "rw",
+        _ => "ro",
+    }
+}
+
+/// [`access_mode`] followed by the named status flags set in `flags`,
+/// e.g. `["rw", "append", "cloexec"]`. Unnamed bits (such as
+/// `O_LARGEFILE`) are left out.
+pub fn open_flag_names(flags: u32) -> Vec<&'static str> {
+    let mut names = vec![access_mode(flags)];
+    let mut rest = flags;
+    for &(bits, name) in OPEN_FLAG_NAMES {
+        if rest & bits == bits {
+            names.push(name);
+            rest &= !bits;
+        }
+    }
+    names
+}
+
+/// [`open_flag_names`] joined for display: `rw, append, cloexec`.
+pub fn format_open_flags(flags: u32) -> String {
+    open_flag_names(flags).join(", ")
+}
+
+/// Like `read_fd_links_access`, but with deleted markers split off the
+/// link text so fd targets are handled the same way as map entries.
+pub fn read_fd_targets_access(pid: i32) -> ProcAccess<Vec<FdTarget>> {
+    let links = match read_fd_links_access(pid) {
+        ProcAccess::Ok(v) => v,
+        ProcAccess::PermissionDenied => return ProcAccess::PermissionDenied,
+        ProcAccess::Gone => return ProcAccess::Gone,
+        ProcAccess::Fatal(e) => return ProcAccess::Fatal(e),
+    };
+
+    let out = links
+        .into_iter()
+        .map(|(fd, fd_path, link)| {
+            let (target, deleted) = strip_deleted_marker(&link);
+            FdTarget {
+                fd,
+                fd_path,
+                target,
+                deleted,
+            }
+        })
+        .collect();
+
+    ProcAccess::Ok(out)
+}
+
 fn parse_hex_u32(s: &str) -> Option<u32> {
     u32::from_str_radix(s, 16).ok()
 }
@@ -141,9 +365,45 @@ pub fn parse_dev_hex(dev: &str) -> Option<(u32, u32)> {
     Some((major, minor))
 }
 
+/// Parse one /proc/<pid>/maps line.
+///
+/// The pathname is the remainder of the line after the inode column, so
+/// paths containing spaces and the trailing deleted marker are preserved.
+fn parse_proc_maps_line(line: &str) -> Option<ProcMapEntry> {
+    let mut rest = line;
+    let mut fields = [""; 5];
+    for field in fields.iter_mut() {
+        rest = rest.trim_start();
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        *field = &rest[..end];
+        rest = &rest[end..];
+    }
+    let [_addr, perms, _offset, dev, inode] = fields;
+
+    let (dev_major, dev_minor) = parse_dev_hex(dev)?;
+    let inode = inode.parse::<u64>().ok()?;
+
+    let rest = rest.trim();
+    let (pathname, deleted) = if rest.is_empty() {
+        (None, false)
+    } else {
+        let (p, deleted) = strip_deleted_marker(rest);
+        (Some(p), deleted)
+    };
+
+    Some(ProcMapEntry {
+        perms: perms.to_string(),
+        dev_major,
+        dev_minor,
+        inode,
+        pathname,
+        deleted,
+    })
+}
+
 #[deprecated(note = "use read_proc_maps_access instead for proper permission handling")]
 pub fn read_proc_maps(pid: i32) -> io::Result<Vec<ProcMapEntry>> {
-    let path = format!("/proc/{pid}/maps");
+    let path = proc_path(format!("{pid}/maps"));
     let f = fs::File::open(path)?;
     let reader = io::BufReader::new(f);
 
@@ -151,85 +411,57 @@ pub fn read_proc_maps(pid: i32) -> io::Result<Vec<ProcMapEntry>> {
 
     for line in reader.lines() {
         let line = line?;
-        let mut parts = line.split_whitespace();
-
-        let _addr = parts.next();
-        let _perms = parts.next();
-        let _offset = parts.next();
-        let dev = parts.next();
-        let inode = parts.next();
-
-        let (Some(dev), Some(inode)) = (dev, inode) else {
-            continue;
-        };
-
-        let Some((dev_major, dev_minor)) = parse_dev_hex(dev) else {
-            continue;
-        };
-
-        let Ok(inode) = inode.parse::<u64>() else {
-            continue;
-        };
-
-        let pathname = parts.next().map(|s| s.to_string());
-
-        out.push(ProcMapEntry {
-            dev_major,
-            dev_minor,
-            inode,
-            pathname,
-        });
+        if let Some(entry) = parse_proc_maps_line(&line) {
+            out.push(entry);
+        }
     }
 
     Ok(out)
 }
 
 pub fn read_proc_maps_access(pid: i32) -> ProcAccess<Vec<ProcMapEntry>> {
-    let path = format!("/proc/{pid}/maps");
-    let f = match fs::File::open(path) {
-        Ok(f) => f,
-        Err(e) => return classify_proc_io_error(e),
-    };
-    let reader = io::BufReader::new(f);
-
     let mut out = Vec::new();
+    match for_each_proc_map_entry(pid, |entry| {
+        out.push(entry.clone());
+        ControlFlow::Continue(())
+    }) {
+        ProcAccess::Ok(()) => ProcAccess::Ok(out),
+        ProcAccess::PermissionDenied => ProcAccess::PermissionDenied,
+        ProcAccess::Gone => ProcAccess::Gone,
+        ProcAccess::Fatal(e) => ProcAccess::Fatal(e),
+    }
+}
 
-    for line in reader.lines() {
-        let line = match line {
-            Ok(l) => l,
+/// Parse /proc/<pid>/maps one line at a time, handing each mapping to `f`
+/// until it returns `Break`. Nothing is collected, and one line buffer is
+/// reused, so existence checks stop at the first hit instead of parsing
+/// the whole (possibly huge) table.
+pub fn for_each_proc_map_entry(
+    pid: i32,
+    mut f: impl FnMut(&ProcMapEntry) -> ControlFlow<()>,
+) -> ProcAccess<()> {
+    let path = proc_path(format!("{pid}/maps"));
+    let file = match fs::File::open(path) {
+        Ok(file) => file,
+        Err(e) => return classify_proc_io_error(e),
+    };
+    let mut reader = io::BufReader::new(file);
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+        match reader.read_line(&mut line) {
+            Ok(0) => return ProcAccess::Ok(()),
+            Ok(_) => {}
             Err(e) => return classify_proc_io_error(e),
-        };
-        let mut parts = line.split_whitespace();
-
-        let _addr = parts.next();
-        let _perms = parts.next();
-        let _offset = parts.next();
-        let dev = parts.next();
-        let inode = parts.next();
-
-        let (Some(dev), Some(inode)) = (dev, inode) else {
-            continue;
-        };
-
-        let Some((dev_major, dev_minor)) = parse_dev_hex(dev) else {
-            continue;
-        };
-
-        let Ok(inode) = inode.parse::<u64>() else {
+        }
+        let Some(entry) = parse_proc_maps_line(line.trim_end_matches('\n')) else {
             continue;
         };
-
-        let pathname = parts.next().map(|s| s.to_string());
-
-        out.push(ProcMapEntry {
-            dev_major,
-            dev_minor,
-            inode,
-            pathname,
-        });
+        if f(&entry).is_break() {
+            return ProcAccess::Ok(());
+        }
     }
-
-    ProcAccess::Ok(out)
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -240,12 +472,107 @@ pub enum ProcNetProto {
     Udp6,
 }
 
+impl ProcNetProto {
+    /// Table name under /proc/net (`tcp`, `tcp6`, `udp`, `udp6`).
+    pub fn table_name(self) -> &'static str {
+        match self {
+            ProcNetProto::Tcp => "tcp",
+            ProcNetProto::Tcp6 => "tcp6",
+            ProcNetProto::Udp => "udp",
+            ProcNetProto::Udp6 => "udp6",
+        }
+    }
+}
+
+/// Socket tables scanned by `read_proc_net_sockets`, in scan order.
+pub const PROC_NET_TABLES: [ProcNetProto; 4] = [
+    ProcNetProto::Tcp,
+    ProcNetProto::Tcp6,
+    ProcNetProto::Udp,
+    ProcNetProto::Udp6,
+];
+
+/// Outcome of reading a single /proc/net socket table.
+#[derive(Debug)]
+pub struct ProcNetTable {
+    pub proto: ProcNetProto,
+    pub entries: io::Result<Vec<ProcNetSocketEntry>>,
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct ProcNetSocketEntry {
     pub proto: ProcNetProto,
+    pub local_addr: IpAddr,
     pub local_port: u16,
+    /// Peer address; unspecified (`0.0.0.0` / `::`) for unconnected sockets.
+    pub remote_addr: IpAddr,
+    pub remote_port: u16,
     pub inode: u64,
     pub state: u8,
+    /// Send queue (`tx_queue`); for a TCP listener, its accept backlog limit.
+    pub tx_queue: u32,
+    /// Receive queue (`rx_queue`); for a TCP listener, connections waiting
+    /// in the accept queue.
+    pub rx_queue: u32,
+    /// Pending timer code from the `tr` column (0 when none is armed).
+    pub timer: u8,
+    /// Time until that timer fires, in clock ticks (`tm->when`).
+    pub timer_ticks: u64,
+}
+
+/// Clock ticks per second used by the `tm->when` column (`USER_HZ`).
+pub const SOCKET_TIMER_TICKS_PER_SEC: u64 = 100;
+
+/// Name of a /proc/net `tr` timer code, `None` when no timer is armed or
+/// the code is not known.
+pub fn socket_timer_name(timer: u8) -> Option<&'static str> {
+    match timer {
+        1 => Some("retransmit"),
+        2 => Some("keepalive"),
+        3 => Some("time_wait"),
+        4 => Some("zero_window_probe"),
+        _ => None,
+    }
+}
+
+/// Decode an `ADDR:PORT` column such as `0100007F:1F90`.
+fn parse_hex_endpoint(field: &str) -> Option<(IpAddr, u16)> {
+    let (addr_hex, port_hex) = field.split_once(':')?;
+    Some((parse_hex_ip(addr_hex)?, parse_hex_u16(port_hex)?))
+}
+
+/// Split a `tx_queue:rx_queue` column such as `00000080:00000003`.
+fn parse_socket_queues(field: &str) -> Option<(u32, u32)> {
+    let (tx, rx) = field.split_once(':')?;
+    Some((parse_hex_u32(tx)?, parse_hex_u32(rx)?))
+}
+
+/// Split a `tr:tm->when` column such as `02:000AFC80`.
+fn parse_socket_timer(field: &str) -> Option<(u8, u64)> {
+    let (tr, when) = field.split_once(':')?;
+    Some((parse_hex_u8(tr)?, u64::from_str_radix(when, 16).ok()?))
+}
+
+/// Decode a /proc/net address column.
+///
+/// The kernel prints the address as native-endian 32-bit words: one word
+/// (8 hex digits) for IPv4, four words (32 hex digits) for IPv6.
+fn parse_hex_ip(hex: &str) -> Option<IpAddr> {
+    match hex.len() {
+        8 => {
+            let word = parse_hex_u32(hex)?;
+            Some(IpAddr::V4(Ipv4Addr::from(word.to_ne_bytes())))
+        }
+        32 => {
+            let mut bytes = [0u8; 16];
+            for (i, chunk) in bytes.chunks_exact_mut(4).enumerate() {
+                let word = parse_hex_u32(hex.get(i * 8..i * 8 + 8)?)?;
+                chunk.copy_from_slice(&word.to_ne_bytes());
+            }
+            Some(IpAddr::V6(Ipv6Addr::from(bytes)))
+        }
+        _ => None,
+    }
 }
 
 fn parse_proc_net_file(path: &Path, proto: ProcNetProto) -> io::Result<Vec<ProcNetSocketEntry>> {
@@ -265,13 +592,18 @@ fn parse_proc_net_file(path: &Path, proto: ProcNetProto) -> io::Result<Vec<ProcN
         let Some(local_address) = it.next() else {
             continue;
         };
-        let _rem_address = it.next();
+        let Some(rem_address) = it.next() else {
+            continue;
+        };
         let Some(state_hex) = it.next() else {
             continue;
         };
 
+        let (tx_queue, rx_queue) = it.next().and_then(parse_socket_queues).unwrap_or((0, 0));
+        let (timer, timer_ticks) = it.next().and_then(parse_socket_timer).unwrap_or((0, 0));
+
         let mut ok = true;
-        for _ in 0..5 {
+        for _ in 0..3 {
             if it.next().is_none() {
                 ok = false;
                 break;
@@ -285,7 +617,11 @@ fn parse_proc_net_file(path: &Path, proto: ProcNetProto) -> io::Result<Vec<ProcN
             continue;
         };
 
-        let Some((_addr_hex, port_hex)) = local_address.split_once(':') else {
+        let Some((addr_hex, port_hex)) = local_address.split_once(':') else {
+            continue;
+        };
+
+        let Some(local_addr) = parse_hex_ip(addr_hex) else {
             continue;
         };
 
@@ -293,6 +629,10 @@ fn parse_proc_net_file(path: &Path, proto: ProcNetProto) -> io::Result<Vec<ProcN
             continue;
         };
 
+        let Some((remote_addr, remote_port)) = parse_hex_endpoint(rem_address) else {
+            continue;
+        };
+
         let Some(state) = parse_hex_u8(state_hex) else {
             continue;
         };
@@ -303,184 +643,2288 @@ fn parse_proc_net_file(path: &Path, proto: ProcNetProto) -> io::Result<Vec<ProcN
 
         out.push(ProcNetSocketEntry {
             proto,
+            local_addr,
             local_port,
+            remote_addr,
+            remote_port,
             inode,
             state,
+            tx_queue,
+            rx_queue,
+            timer,
+            timer_ticks,
         });
     }
 
     Ok(out)
 }
 
-pub fn read_proc_net_sockets() -> io::Result<Vec<ProcNetSocketEntry>> {
-    let mut out = Vec::new();
+/// Read each /proc/net socket table, keeping per-table success or failure.
+///
+/// A missing table (e.g. tcp6 with IPv6 disabled) shows up as an `Err`
+/// entry rather than being folded into the others.
+pub fn read_proc_net_tables() -> Vec<ProcNetTable> {
+    PROC_NET_TABLES
+        .iter()
+        .map(|&proto| {
+            let path = proc_path("net").join(proto.table_name());
+            ProcNetTable {
+                proto,
+                entries: parse_proc_net_file(&path, proto),
+            }
+        })
+        .collect()
+}
+
+/// Sockets from every /proc/net table that could be read, plus the tables
+/// that could not.
+#[derive(Debug, Default)]
+pub struct ProcNetSockets {
+    pub entries: Vec<ProcNetSocketEntry>,
+    pub unreadable: Vec<(ProcNetProto, io::Error)>,
+}
 
-    if let Ok(v) = parse_proc_net_file(Path::new("/proc/net/tcp"), ProcNetProto::Tcp) {
-        out.extend(v);
+impl ProcNetSockets {
+    /// Keep the entries of the readable tables; note the others.
+    pub fn from_tables(tables: Vec<ProcNetTable>) -> Self {
+        let mut out = ProcNetSockets::default();
+        for table in tables {
+            match table.entries {
+                Ok(entries) => out.entries.extend(entries),
+                Err(e) => out.unreadable.push((table.proto, e)),
+            }
+        }
+        out
     }
-    if let Ok(v) = parse_proc_net_file(Path::new("/proc/net/tcp6"), ProcNetProto::Tcp6) {
-        out.extend(v);
+
+    /// One line per table that exists but failed to read, e.g.
+    /// `udp6 socket table unreadable: ...`. Absent tables are not reported.
+    pub fn warnings(&self) -> Vec<String> {
+        self.unreadable
+            .iter()
+            .filter(|(_, e)| e.kind() != io::ErrorKind::NotFound)
+            .map(|(proto, e)| format!("{} socket table unreadable: {e}", proto.table_name()))
+            .collect()
     }
-    if let Ok(v) = parse_proc_net_file(Path::new("/proc/net/udp"), ProcNetProto::Udp) {
-        out.extend(v);
+
+    /// Tables that do not exist: the protocol is disabled (tcp6 without
+    /// IPv6), which is not a failed read.
+    pub fn absent(&self) -> Vec<ProcNetProto> {
+        self.unreadable
+            .iter()
+            .filter(|(_, e)| e.kind() == io::ErrorKind::NotFound)
+            .map(|(proto, _)| *proto)
+            .collect()
     }
-    if let Ok(v) = parse_proc_net_file(Path::new("/proc/net/udp6"), ProcNetProto::Udp6) {
-        out.extend(v);
+
+    /// Entry count of `proto`'s table; `None` when it could not be read.
+    pub fn count(&self, proto: ProcNetProto) -> Option<usize> {
+        if self.unreadable.iter().any(|(p, _)| *p == proto) {
+            return None;
+        }
+        Some(self.entries.iter().filter(|e| e.proto == proto).count())
     }
+}
 
-    Ok(out)
+/// All sockets from the tables that could be read; see
+/// [`ProcNetSockets::warnings`] for the ones that could not.
+pub fn read_proc_net_sockets() -> ProcNetSockets {
+    ProcNetSockets::from_tables(read_proc_net_tables())
 }
 
-// --- Named constants for socket states ---
+/// One AF_UNIX socket from /proc/net/unix.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProcNetUnixEntry {
+    pub inode: u64,
+    /// Bound name: a filesystem path, or `@name` for an abstract socket
+    /// (kept verbatim). `None` for unbound sockets.
+    pub path: Option<String>,
+    /// Connection state (`St`): 1 unconnected, 2 connecting, 3 connected.
+    pub state: u8,
+    /// Socket type (`Type`): 1 stream, 2 dgram, 5 seqpacket.
+    pub kind: u8,
+}
 
-/// TCP socket states (Linux kernel)
-pub const TCP_ESTABLISHED: u8 = 0x01;
-pub const TCP_LISTEN: u8 = 0x0A;
+/// Every AF_UNIX socket from /proc/net/unix.
+pub fn read_proc_net_unix() -> io::Result<Vec<ProcNetUnixEntry>> {
+    let contents = fs::read_to_string(proc_path("net/unix"))?;
+    Ok(parse_proc_net_unix(&contents))
+}
 
-/// UDP socket states (Linux kernel)
-pub const UDP_LISTEN: u8 = 0x07;
+/// Parse /proc/net/unix:
+/// `Num RefCount Protocol Flags Type St Inode [Path]`.
+fn parse_proc_net_unix(contents: &str) -> Vec<ProcNetUnixEntry> {
+    let mut out = Vec::new();
+    for line in contents.lines().skip(1) {
+        let mut it = line.split_whitespace();
+        let (Some(_num), Some(_refs), Some(_proto), Some(_flags)) =
+            (it.next(), it.next(), it.next(), it.next())
+        else {
+            continue;
+        };
+        let (Some(kind), Some(state), Some(inode)) = (it.next(), it.next(), it.next()) else {
+            continue;
+        };
+        let (Ok(kind), Some(state), Ok(inode)) = (
+            u16::from_str_radix(kind, 16),
+            parse_hex_u8(state),
+            inode.parse::<u64>(),
+        ) else {
+            continue;
+        };
+        out.push(ProcNetUnixEntry {
+            inode,
+            path: unix_path_column(line),
+            state,
+            kind: kind as u8,
+        });
+    }
+    out
+}
 
-// --- Shared scanning functions ---
+/// Everything after the seventh column of a /proc/net/unix line; a path
+/// may itself contain spaces.
+fn unix_path_column(line: &str) -> Option<String> {
+    let mut rest = line.trim_start();
+    for _ in 0..7 {
+        let end = rest.find(char::is_whitespace)?;
+        rest = rest[end..].trim_start();
+    }
+    (!rest.is_empty()).then(|| rest.to_string())
+}
 
-pub fn scan_pid_open_fd_file(pid: i32, target: FileId) -> ProcAccess<bool> {
-    let links = match read_fd_links_access(pid) {
-        ProcAccess::Ok(v) => v,
-        ProcAccess::PermissionDenied => return ProcAccess::PermissionDenied,
-        ProcAccess::Gone => return ProcAccess::Gone,
-        ProcAccess::Fatal(e) => return ProcAccess::Fatal(e),
-    };
+/// The socket table, read from /proc/net at most once.
+///
+/// Standalone tools start with [`SocketTable::default`] and read on first
+/// use; a caller composing several scans reads once and hands the same
+/// table to each with [`SocketTable::prefetched`].
+#[derive(Clone, Debug, Default)]
+pub struct SocketTable {
+    entries: Option<Vec<ProcNetSocketEntry>>,
+    /// Unreadable-table warnings not yet handed out.
+    warnings: Vec<String>,
+}
 
-    for (_fd, fd_path, _link) in links {
-        let md = match fs::metadata(&fd_path) {
-            Ok(md) => md,
-            Err(_) => continue,
-        };
+impl SocketTable {
+    pub fn prefetched(entries: Vec<ProcNetSocketEntry>) -> Self {
+        SocketTable {
+            entries: Some(entries),
+            warnings: Vec::new(),
+        }
+    }
 
-        if file_id_for_metadata(&md) == target {
-            return ProcAccess::Ok(true);
+    /// The entries, reading /proc/net on the first call only. `report`
+    /// gets each [`ProcNetSockets::warnings`] line once, so a table shared
+    /// by several scans is reported a single time.
+    pub fn load(&mut self, mut report: impl FnMut(&str)) -> &[ProcNetSocketEntry] {
+        if self.entries.is_none() {
+            let sockets = read_proc_net_sockets();
+            self.warnings = sockets.warnings();
+            self.entries = Some(sockets.entries);
+        }
+        for warning in self.warnings.drain(..) {
+            report(&warning);
         }
+        self.entries.as_deref().unwrap_or_default()
     }
 
-    ProcAccess::Ok(false)
+    pub fn is_loaded(&self) -> bool {
+        self.entries.is_some()
+    }
 }
 
-pub fn scan_pid_mmap_file(
-    pid: i32,
-    target_major: u32,
-    target_minor: u32,
-    target_inode: u64,
-) -> ProcAccess<bool> {
-    let maps = match read_proc_maps_access(pid) {
-        ProcAccess::Ok(v) => v,
-        ProcAccess::PermissionDenied => return ProcAccess::PermissionDenied,
-        ProcAccess::Gone => return ProcAccess::Gone,
-        ProcAccess::Fatal(e) => return ProcAccess::Fatal(e),
-    };
+// --- Interface attribution for local addresses ---
+
+/// Maps local IP addresses to the network interface they belong to.
+///
+/// IPv6 addresses come from /proc/net/if_inet6 (exact matches). IPv4 has no
+/// procfs address list, so the most specific non-default route in
+/// /proc/net/route stands in; loopback is always `lo`.
+#[derive(Clone, Debug, Default)]
+pub struct InterfaceIndex {
+    v4_routes: Vec<(u32, u32, String)>,
+    v6_addrs: Vec<(Ipv6Addr, String)>,
+}
 
-    for entry in maps {
-        if entry.inode == 0 {
-            continue;
+impl InterfaceIndex {
+    /// Interface name owning `ip`, or `None` for wildcard and unknown addresses.
+    pub fn interface_for(&self, ip: IpAddr) -> Option<&str> {
+        if ip.is_unspecified() {
+            return None;
+        }
+        if ip.is_loopback() {
+            return Some("lo");
         }
 
-        if entry.inode == target_inode
-            && entry.dev_major == target_major
-            && entry.dev_minor == target_minor
-        {
-            return ProcAccess::Ok(true);
+        match ip {
+            IpAddr::V4(v4) => {
+                let addr = u32::from_be_bytes(v4.octets());
+                self.v4_routes
+                    .iter()
+                    .filter(|(net, mask, _)| *mask != 0 && addr & mask == *net)
+                    .max_by_key(|(_, mask, _)| mask.count_ones())
+                    .map(|(_, _, iface)| iface.as_str())
+            }
+            IpAddr::V6(v6) => {
+                if let Some(v4) = v6.to_ipv4_mapped() {
+                    return self.interface_for(IpAddr::V4(v4));
+                }
+                self.v6_addrs
+                    .iter()
+                    .find(|(a, _)| *a == v6)
+                    .map(|(_, iface)| iface.as_str())
+            }
         }
     }
-
-    ProcAccess::Ok(false)
 }
 
-pub fn parse_socket_inode(link: &str) -> Option<u64> {
-    let rest = link.strip_prefix("socket:[")?;
-    let rest = rest.strip_suffix(']')?;
-    rest.parse::<u64>().ok()
+fn parse_proc_net_route(contents: &str) -> Vec<(u32, u32, String)> {
+    let mut out = Vec::new();
+    for line in contents.lines().skip(1) {
+        let cols: Vec<&str> = line.split_whitespace().collect();
+        if cols.len() < 8 {
+            continue;
+        }
+        let (Some(dest), Some(mask)) = (parse_hex_u32(cols[1]), parse_hex_u32(cols[7])) else {
+            continue;
+        };
+        // Native-endian like the socket tables; normalize to big-endian math.
+        let dest = u32::from_be_bytes(dest.to_ne_bytes());
+        let mask = u32::from_be_bytes(mask.to_ne_bytes());
+        out.push((dest & mask, mask, cols[0].to_string()));
+    }
+    out
 }
 
-pub fn proto_label(proto: ProcNetProto) -> &'static str {
-    match proto {
-        ProcNetProto::Tcp | ProcNetProto::Tcp6 => "tcp",
-        ProcNetProto::Udp | ProcNetProto::Udp6 => "udp",
+fn parse_if_inet6(contents: &str) -> Vec<(Ipv6Addr, String)> {
+    let mut out = Vec::new();
+    for line in contents.lines() {
+        let cols: Vec<&str> = line.split_whitespace().collect();
+        if cols.len() < 6 || cols[0].len() != 32 {
+            continue;
+        }
+        // Unlike the socket tables, if_inet6 prints the address bytes in order.
+        let Ok(raw) = u128::from_str_radix(cols[0], 16) else {
+            continue;
+        };
+        out.push((Ipv6Addr::from(raw), cols[5].to_string()));
     }
+    out
 }
 
-pub fn proto_label_and_sort(proto: ProcNetProto) -> (&'static str, u8) {
-    match proto {
-        ProcNetProto::Tcp | ProcNetProto::Tcp6 => ("tcp", 0),
-        ProcNetProto::Udp | ProcNetProto::Udp6 => ("udp", 1),
+/// Best-effort read of the interface tables; missing files yield no entries.
+pub fn read_interface_index() -> InterfaceIndex {
+    InterfaceIndex {
+        v4_routes: fs::read_to_string(proc_path("net/route"))
+            .map(|s| parse_proc_net_route(&s))
+            .unwrap_or_default(),
+        v6_addrs: fs::read_to_string(proc_path("net/if_inet6"))
+            .map(|s| parse_if_inet6(&s))
+            .unwrap_or_default(),
     }
 }
 
-pub fn socket_state_label(proto: ProcNetProto, state: u8) -> String {
-    let label = match proto {
-        ProcNetProto::Tcp | ProcNetProto::Tcp6 => match state {
-            TCP_ESTABLISHED => "established",
-            TCP_LISTEN => "listening",
-            _ => "",
-        },
-        ProcNetProto::Udp | ProcNetProto::Udp6 => match state {
-            UDP_LISTEN => "listening",
-            _ => "",
-        },
-    };
+// --- Named constants for socket states ---
 
-    if label.is_empty() {
-        format!("0x{state:02X}")
-    } else {
-        label.to_string()
-    }
-}
+/// TCP socket states (Linux kernel)
+pub const TCP_ESTABLISHED: u8 = 0x01;
+pub const TCP_TIME_WAIT: u8 = 0x06;
+pub const TCP_CLOSE: u8 = 0x07;
+pub const TCP_LISTEN: u8 = 0x0A;
+
+/// UDP socket states (Linux kernel)
+///
+/// UDP reuses the TCP state numbering: a `connect(2)`ed UDP socket
+/// reports `TCP_ESTABLISHED`, an unconnected bound one `TCP_CLOSE`.
+pub const UDP_ESTABLISHED: u8 = 0x01;
+pub const UDP_LISTEN: u8 = 0x07;
+
+// --- Shared scanning functions ---
+
+pub fn scan_pid_open_fd_file(pid: i32, target: FileId) -> ProcAccess<bool> {
+    match find_pid_open_fd_file(pid, target) {
+        ProcAccess::Ok(fds) => ProcAccess::Ok(!fds.is_empty()),
+        ProcAccess::PermissionDenied => ProcAccess::PermissionDenied,
+        ProcAccess::Gone => ProcAccess::Gone,
+        ProcAccess::Fatal(e) => ProcAccess::Fatal(e),
+    }
+}
+
+/// Every fd of `pid` that refers to `target`, in ascending fd order.
+pub fn find_pid_open_fd_file(pid: i32, target: FileId) -> ProcAccess<Vec<i32>> {
+    find_pid_open_fd_ids(pid, |id| *id == target)
+}
+
+fn find_pid_open_fd_ids(pid: i32, matches: impl Fn(&FileId) -> bool) -> ProcAccess<Vec<i32>> {
+    match read_fd_file_ids(pid) {
+        ProcAccess::Ok(ids) => ProcAccess::Ok(
+            ids.into_iter()
+                .filter(|(_, id)| matches(id))
+                .map(|(fd, _)| fd)
+                .collect(),
+        ),
+        ProcAccess::PermissionDenied => ProcAccess::PermissionDenied,
+        ProcAccess::Gone => ProcAccess::Gone,
+        ProcAccess::Fatal(e) => ProcAccess::Fatal(e),
+    }
+}
+
+/// Identity of every open fd of `pid`, in ascending fd order, with one
+/// `stat` per fd and no link reads. Fds that close mid-scan are skipped.
+/// Callers matching several targets compare against this list instead of
+/// re-statting.
+pub fn read_fd_file_ids(pid: i32) -> ProcAccess<Vec<(i32, FileId)>> {
+    let entries = match fs::read_dir(fd_dir(pid)) {
+        Ok(e) => e,
+        Err(e) => return classify_proc_io_error(e),
+    };
+
+    let mut ids = Vec::new();
+    for entry in entries.flatten() {
+        let Some(fd) = entry
+            .file_name()
+            .to_str()
+            .and_then(|s| s.parse::<i32>().ok())
+        else {
+            continue;
+        };
+        if let Ok(md) = fs::metadata(entry.path()) {
+            ids.push((fd, file_id_for_metadata(&md)));
+        }
+    }
+    ids.sort_by_key(|(fd, _)| *fd);
+    ProcAccess::Ok(ids)
+}
+
+/// How fd links and mappings are matched against a target file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FileMatch {
+    /// Live scan: stat through the magic links and compare device/inode
+    /// against any of the ids (one per distinct view of the file).
+    Ids(Vec<FileId>),
+    /// Offline scan: compare the (marker-stripped) link and map path text.
+    Path(String),
+    /// Live scan of a directory subtree: every file and directory under it
+    /// on the directory's own device.
+    Tree(HashSet<FileId>),
+    /// Offline scan of a directory subtree: the directory's path text or
+    /// anything below it.
+    Under(String),
+}
+
+impl FileMatch {
+    /// Whether an fd target or cwd/root with identity `id` matches
+    /// (identity-based variants only).
+    pub fn matches_id(&self, id: &FileId) -> bool {
+        match self {
+            FileMatch::Ids(ids) => ids.contains(id),
+            FileMatch::Tree(ids) => ids.contains(id),
+            FileMatch::Path(_) | FileMatch::Under(_) => false,
+        }
+    }
+
+    /// Whether a (marker-stripped) link or map path matches (text-based
+    /// variants only).
+    pub fn matches_text(&self, text: &str) -> bool {
+        match self {
+            FileMatch::Path(path) => text == path,
+            FileMatch::Under(dir) => text
+                .strip_prefix(dir.trim_end_matches('/'))
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/')),
+            FileMatch::Ids(_) | FileMatch::Tree(_) => false,
+        }
+    }
+
+    fn by_identity(&self) -> bool {
+        matches!(self, FileMatch::Ids(_) | FileMatch::Tree(_))
+    }
+
+    fn matches_map(&self, e: &ProcMapEntry) -> bool {
+        self.matches_map_with(e, id_match())
+    }
+
+    /// Whether mapping `e` matches, comparing identities under `mode`.
+    /// Text-based variants ignore `mode`.
+    pub fn matches_map_with(&self, e: &ProcMapEntry, mode: IdMatch) -> bool {
+        let by_id = |id: &FileId| id.matches_dev_inode(e.dev_major, e.dev_minor, e.inode, mode);
+        match self {
+            FileMatch::Ids(ids) => e.inode != 0 && ids.iter().any(by_id),
+            FileMatch::Tree(ids) => {
+                e.inode != 0
+                    && match mode {
+                        IdMatch::ExactDevInode => ids.contains(&FileId {
+                            dev: makedev(e.dev_major, e.dev_minor),
+                            inode: e.inode,
+                        }),
+                        IdMatch::InodeOnly => ids.iter().any(by_id),
+                    }
+            }
+            FileMatch::Path(_) | FileMatch::Under(_) => {
+                e.pathname.as_deref().is_some_and(|p| self.matches_text(p))
+            }
+        }
+    }
+}
+
+/// Build the matcher for `path`: by identity on a live system, by path text
+/// when scanning a captured proc tree (the file need not exist locally).
+pub fn file_match_for_path(path: &Path) -> io::Result<FileMatch> {
+    if is_offline() {
+        return Ok(FileMatch::Path(path.display().to_string()));
+    }
+    file_id_for_path(path).map(|id| FileMatch::Ids(vec![id]))
+}
+
+/// Matcher for a file known only by device and inode (`major:minor` as in
+/// /proc/<pid>/maps), e.g. one already unlinked. Live scans only.
+pub fn file_match_for_inode(major: u32, minor: u32, inode: u64) -> FileMatch {
+    FileMatch::Ids(vec![FileId {
+        dev: makedev(major, minor),
+        inode,
+    }])
+}
+
+/// Build the subtree matcher for directory `dir` (`--under`), walking it
+/// once so each fd and mapping is a set lookup. Entries on another device
+/// (mounts below `dir`) are neither included nor descended into. Also
+/// returns how many subdirectories could not be read.
+pub fn file_match_under(dir: &Path) -> io::Result<(FileMatch, usize)> {
+    if is_offline() {
+        return Ok((FileMatch::Under(dir.display().to_string()), 0));
+    }
+    let md = fs::metadata(dir)?;
+    let root_dev = md.dev();
+    let mut ids = HashSet::new();
+    ids.insert(file_id_for_metadata(&md));
+    let mut unreadable = 0;
+    let mut pending = vec![dir.to_path_buf()];
+
+    while let Some(current) = pending.pop() {
+        let entries = match fs::read_dir(&current) {
+            Ok(entries) => entries,
+            Err(_) => {
+                unreadable += 1;
+                continue;
+            }
+        };
+        for entry in entries.flatten() {
+            let Ok(md) = entry.metadata() else {
+                continue;
+            };
+            if md.dev() != root_dev {
+                continue;
+            }
+            ids.insert(file_id_for_metadata(&md));
+            if md.is_dir() {
+                pending.push(entry.path());
+            }
+        }
+    }
+
+    Ok((FileMatch::Tree(ids), unreadable))
+}
+
+/// Fd identities of one pid, stat'ed on first use and shared by every
+/// target matched against that pid.
+pub struct FdIdCache {
+    pid: i32,
+    ids: Option<ProcAccess<Vec<(i32, FileId)>>>,
+}
+
+impl FdIdCache {
+    pub fn new(pid: i32) -> Self {
+        FdIdCache { pid, ids: None }
+    }
+
+    /// Same result as [`find_pid_open_fd_match`]; text (offline) targets
+    /// bypass the cache.
+    pub fn find_match(&mut self, target: &FileMatch) -> ProcAccess<Vec<i32>> {
+        if !target.by_identity() {
+            return find_pid_open_fd_match(self.pid, target);
+        }
+        let pid = self.pid;
+        match self.ids.get_or_insert_with(|| read_fd_file_ids(pid)) {
+            ProcAccess::Ok(ids) => ProcAccess::Ok(
+                ids.iter()
+                    .filter(|(_, id)| target.matches_id(id))
+                    .map(|(fd, _)| *fd)
+                    .collect(),
+            ),
+            ProcAccess::PermissionDenied => ProcAccess::PermissionDenied,
+            ProcAccess::Gone => ProcAccess::Gone,
+            ProcAccess::Fatal(e) => ProcAccess::Fatal(io::Error::new(e.kind(), e.to_string())),
+        }
+    }
+}
+
+/// Every fd of `pid` matching `target`, in ascending fd order.
+pub fn find_pid_open_fd_match(pid: i32, target: &FileMatch) -> ProcAccess<Vec<i32>> {
+    if target.by_identity() {
+        return find_pid_open_fd_ids(pid, |id| target.matches_id(id));
+    }
+    match read_fd_targets_access(pid) {
+        ProcAccess::Ok(v) => ProcAccess::Ok(
+            v.into_iter()
+                .filter(|t| target.matches_text(&t.target))
+                .map(|t| t.fd)
+                .collect(),
+        ),
+        ProcAccess::PermissionDenied => ProcAccess::PermissionDenied,
+        ProcAccess::Gone => ProcAccess::Gone,
+        ProcAccess::Fatal(e) => ProcAccess::Fatal(e),
+    }
+}
+
+/// Every mapping of `pid` matching `target`.
+pub fn find_pid_mmap_match(pid: i32, target: &FileMatch) -> ProcAccess<Vec<ProcMapEntry>> {
+    match read_proc_maps_access(pid) {
+        ProcAccess::Ok(v) => {
+            ProcAccess::Ok(v.into_iter().filter(|e| target.matches_map(e)).collect())
+        }
+        ProcAccess::PermissionDenied => ProcAccess::PermissionDenied,
+        ProcAccess::Gone => ProcAccess::Gone,
+        ProcAccess::Fatal(e) => ProcAccess::Fatal(e),
+    }
+}
+
+/// Per-process directory links that keep a directory busy without an open
+/// fd (why `umount` reports "target is busy").
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DirLink {
+    /// Working directory, /proc/<pid>/cwd.
+    Cwd,
+    /// Root directory (chroot), /proc/<pid>/root.
+    Root,
+}
+
+impl DirLink {
+    pub const ALL: [DirLink; 2] = [DirLink::Cwd, DirLink::Root];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DirLink::Cwd => "cwd",
+            DirLink::Root => "root",
+        }
+    }
+}
+
+/// Whether `pid`'s cwd or root is `target`: by identity through the magic
+/// link when live, by link text offline.
+pub fn pid_dir_link_matches(pid: i32, link: DirLink, target: &FileMatch) -> ProcAccess<bool> {
+    let path = proc_path(format!("{pid}/{}", link.as_str()));
+    if target.by_identity() {
+        return match fs::metadata(&path) {
+            Ok(md) => ProcAccess::Ok(target.matches_id(&file_id_for_metadata(&md))),
+            Err(e) => classify_proc_io_error(e),
+        };
+    }
+    match fs::read_link(&path) {
+        Ok(dest) => {
+            let (dest, _deleted) = strip_deleted_marker(&dest.to_string_lossy());
+            ProcAccess::Ok(target.matches_text(&dest))
+        }
+        Err(e) => classify_proc_io_error(e),
+    }
+}
+
+// --- Mounts ---
+
+/// One line of /proc/<pid>/mountinfo.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MountInfoEntry {
+    pub mount_id: u32,
+    pub parent_id: u32,
+    pub major: u32,
+    pub minor: u32,
+    /// Path inside the filesystem that is mounted (not `/` for bind mounts).
+    pub root: PathBuf,
+    pub mount_point: PathBuf,
+    pub fstype: String,
+    pub source: String,
+}
+
+/// Undo mountinfo's octal escaping of space, tab, newline and backslash.
+fn unescape_mountinfo(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(pos) = rest.find('\\') {
+        out.push_str(&rest[..pos]);
+        let esc = rest.get(pos + 1..pos + 4);
+        match esc.and_then(|e| u8::from_str_radix(e, 8).ok()) {
+            Some(b) => {
+                out.push(b as char);
+                rest = &rest[pos + 4..];
+            }
+            None => {
+                out.push('\\');
+                rest = &rest[pos + 1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+pub fn parse_mountinfo_line(line: &str) -> Option<MountInfoEntry> {
+    let (left, right) = line.split_once(" - ")?;
+    let mut it = left.split_whitespace();
+    let mount_id = it.next()?.parse::<u32>().ok()?;
+    let parent_id = it.next()?.parse::<u32>().ok()?;
+    let (major, minor) = it.next()?.split_once(':')?;
+    let root = unescape_mountinfo(it.next()?);
+    let mount_point = unescape_mountinfo(it.next()?);
+
+    let mut rt = right.split_whitespace();
+    let fstype = rt.next()?.to_string();
+    let source = unescape_mountinfo(rt.next().unwrap_or("none"));
+
+    Some(MountInfoEntry {
+        mount_id,
+        parent_id,
+        major: major.parse().ok()?,
+        minor: minor.parse().ok()?,
+        root: PathBuf::from(root),
+        mount_point: PathBuf::from(mount_point),
+        fstype,
+        source,
+    })
+}
+
+/// Mount table as seen by this process.
+pub fn read_mountinfo() -> io::Result<Vec<MountInfoEntry>> {
+    let contents = fs::read_to_string(proc_path("self/mountinfo"))?;
+    Ok(contents.lines().filter_map(parse_mountinfo_line).collect())
+}
+
+/// The mount `path` resolves through: deepest mount point, latest mount wins.
+fn containing_mount<'a>(path: &Path, mounts: &'a [MountInfoEntry]) -> Option<&'a MountInfoEntry> {
+    mounts
+        .iter()
+        .enumerate()
+        .filter(|(_, m)| path.starts_with(&m.mount_point))
+        .max_by_key(|(i, m)| (m.mount_point.components().count(), *i))
+        .map(|(_, m)| m)
+}
+
+/// Other paths through which the file at (absolute, canonical) `path` is
+/// reachable via bind mounts of the same filesystem.
+///
+/// Views hidden by a later mount on top are skipped.
+pub fn bind_views(path: &Path, mounts: &[MountInfoEntry]) -> Vec<PathBuf> {
+    let Some(home) = containing_mount(path, mounts) else {
+        return Vec::new();
+    };
+    let Ok(rel) = path.strip_prefix(&home.mount_point) else {
+        return Vec::new();
+    };
+    let fs_path = home.root.join(rel);
+
+    let mut out = Vec::new();
+    for m in mounts {
+        if m.mount_id == home.mount_id || (m.major, m.minor) != (home.major, home.minor) {
+            continue;
+        }
+        let Ok(inner) = fs_path.strip_prefix(&m.root) else {
+            continue;
+        };
+        let view = m.mount_point.join(inner);
+        if view == path || out.contains(&view) {
+            continue;
+        }
+        if containing_mount(&view, mounts).map(|c| c.mount_id) != Some(m.mount_id) {
+            continue;
+        }
+        out.push(view);
+    }
+    out
+}
+
+/// Widen a live matcher to every bind-mount view of `path`.
+///
+/// Returns the views found; ids that differ from the ones already matched
+/// (e.g. through an overlay) are added so holders via any view are found.
+pub fn add_bind_views(path: &Path, target: &mut FileMatch) -> Vec<PathBuf> {
+    let FileMatch::Ids(ids) = target else {
+        return Vec::new();
+    };
+    let Ok(canonical) = fs::canonicalize(path) else {
+        return Vec::new();
+    };
+    let mounts = read_mountinfo().unwrap_or_default();
+    let views = bind_views(&canonical, &mounts);
+    for view in &views {
+        if let Ok(id) = file_id_for_path(view) {
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+    }
+    views
+}
+
+/// Whether `pid` maps the given device and inode; stops reading at the
+/// first match.
+pub fn scan_pid_mmap_file(
+    pid: i32,
+    target_major: u32,
+    target_minor: u32,
+    target_inode: u64,
+) -> ProcAccess<bool> {
+    pid_has_mmap_where(pid, |entry| {
+        entry.inode != 0
+            && entry.inode == target_inode
+            && entry.dev_major == target_major
+            && entry.dev_minor == target_minor
+    })
+}
+
+/// Whether any mapping of `pid` matches `target`; stops reading at the
+/// first match.
+pub fn pid_has_mmap_match(pid: i32, target: &FileMatch) -> ProcAccess<bool> {
+    pid_has_mmap_where(pid, |entry| target.matches_map(entry))
+}
+
+fn pid_has_mmap_where(pid: i32, matches: impl Fn(&ProcMapEntry) -> bool) -> ProcAccess<bool> {
+    let mut found = false;
+    let walked = for_each_proc_map_entry(pid, |entry| {
+        if matches(entry) {
+            found = true;
+            return ControlFlow::Break(());
+        }
+        ControlFlow::Continue(())
+    });
+    match walked {
+        ProcAccess::Ok(()) => ProcAccess::Ok(found),
+        ProcAccess::PermissionDenied => ProcAccess::PermissionDenied,
+        ProcAccess::Gone => ProcAccess::Gone,
+        ProcAccess::Fatal(e) => ProcAccess::Fatal(e),
+    }
+}
+
+/// Every mapping of `pid` backed by the given device and inode.
+pub fn find_pid_mmap_file(
+    pid: i32,
+    target_major: u32,
+    target_minor: u32,
+    target_inode: u64,
+) -> ProcAccess<Vec<ProcMapEntry>> {
+    let maps = match read_proc_maps_access(pid) {
+        ProcAccess::Ok(v) => v,
+        ProcAccess::PermissionDenied => return ProcAccess::PermissionDenied,
+        ProcAccess::Gone => return ProcAccess::Gone,
+        ProcAccess::Fatal(e) => return ProcAccess::Fatal(e),
+    };
+
+    let matches = maps
+        .into_iter()
+        .filter(|entry| {
+            entry.inode != 0
+                && entry.inode == target_inode
+                && entry.dev_major == target_major
+                && entry.dev_minor == target_minor
+        })
+        .collect();
+
+    ProcAccess::Ok(matches)
+}
+
+/// What an fd symlink points at, from the shape of its link text.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FdKind {
+    /// A filesystem path (regular file, directory, device, ...).
+    File,
+    Socket(u64),
+    Pipe(u64),
+    /// `anon_inode:[eventfd]`, `anon_inode:inotify`, ...; holds the name.
+    AnonInode(String),
+    /// Any other pseudo-file link (e.g. `net:[...]`, `mnt:[...]`).
+    Other,
+}
+
+impl FdKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FdKind::File => "file",
+            FdKind::Socket(_) => "socket",
+            FdKind::Pipe(_) => "pipe",
+            FdKind::AnonInode(_) => "anon",
+            FdKind::Other => "other",
+        }
+    }
+}
+
+fn parse_bracketed_inode(link: &str, prefix: &str) -> Option<u64> {
+    let rest = link.strip_prefix(prefix)?;
+    let rest = rest.strip_prefix('[')?.strip_suffix(']')?;
+    rest.parse::<u64>().ok()
+}
+
+/// Classify a (marker-stripped) fd link target.
+pub fn classify_fd_link(link: &str) -> FdKind {
+    if link.starts_with('/') {
+        return FdKind::File;
+    }
+    if let Some(inode) = parse_bracketed_inode(link, "socket:") {
+        return FdKind::Socket(inode);
+    }
+    if let Some(inode) = parse_bracketed_inode(link, "pipe:") {
+        return FdKind::Pipe(inode);
+    }
+    if let Some(rest) = link.strip_prefix("anon_inode:") {
+        let name = rest.trim_start_matches('[').trim_end_matches(']');
+        return FdKind::AnonInode(name.to_string());
+    }
+    FdKind::Other
+}
+
+/// Result keys of an [`FdRow`], for `--fields` validation.
+pub const FD_ROW_FIELDS: &[&str] = &["fd", "type", "target", "deleted", "socket"];
+
+/// Where a socket fd is bound, as listed in /proc/net.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct SocketEndpoint {
+    pub proto: &'static str,
+    pub local: String,
+    pub state: String,
+}
+
+/// One open fd, categorized by its link text (see [`FdKind::as_str`]).
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct FdRow {
+    pub fd: i32,
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub target: String,
+    /// The fd refers to an unlinked file. Deletion is a flag rather than an
+    /// [`FdKind`]: the fd is still a `file`, just one without a name.
+    pub deleted: bool,
+    /// Endpoint of a `socket` fd found in /proc/net; a socket missing there
+    /// keeps only its `socket:[inode]` target.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub socket: Option<SocketEndpoint>,
+}
+
+impl FdRow {
+    /// The TARGET cell: the socket endpoint or the link text, marked
+    /// ` (deleted)` for an unlinked file.
+    pub fn detail(&self) -> String {
+        let mut out = match &self.socket {
+            Some(s) => format!("{} {} {}", s.proto, s.local, s.state),
+            None => self.target.clone(),
+        };
+        if self.deleted {
+            out.push_str(" (deleted)");
+        }
+        out
+    }
+}
+
+/// Rows for `targets` sorted by fd, with socket fds resolved against
+/// `sockets` by inode.
+pub fn fd_rows(targets: Vec<FdTarget>, sockets: &[ProcNetSocketEntry]) -> Vec<FdRow> {
+    let endpoints: HashMap<u64, &ProcNetSocketEntry> =
+        sockets.iter().map(|s| (s.inode, s)).collect();
+    let mut rows: Vec<FdRow> = targets
+        .into_iter()
+        .map(|t| {
+            let kind = classify_fd_link(&t.target);
+            let socket = match kind {
+                FdKind::Socket(inode) => endpoints.get(&inode).map(|s| SocketEndpoint {
+                    proto: s.proto.table_name(),
+                    local: SocketAddr::new(s.local_addr, s.local_port).to_string(),
+                    state: socket_state_label(s.proto, s.state),
+                }),
+                _ => None,
+            };
+            FdRow {
+                fd: t.fd,
+                kind: kind.as_str(),
+                target: t.target,
+                deleted: t.deleted,
+                socket,
+            }
+        })
+        .collect();
+    rows.sort_by_key(|r| r.fd);
+    rows
+}
+
+/// Inode of a `socket:[NNN]` fd link; `None` for any other link. Shared
+/// with [`classify_fd_link`] so every tool parses socket links alike.
+pub fn parse_socket_inode(link: &str) -> Option<u64> {
+    parse_bracketed_inode(link, "socket:")
+}
+
+pub fn proto_label(proto: ProcNetProto) -> &'static str {
+    match proto {
+        ProcNetProto::Tcp | ProcNetProto::Tcp6 => "tcp",
+        ProcNetProto::Udp | ProcNetProto::Udp6 => "udp",
+    }
+}
+
+pub fn proto_label_and_sort(proto: ProcNetProto) -> (&'static str, u8) {
+    match proto {
+        ProcNetProto::Tcp | ProcNetProto::Tcp6 => ("tcp", 0),
+        ProcNetProto::Udp | ProcNetProto::Udp6 => ("udp", 1),
+    }
+}
+
+/// Human label for a TCP state from the `st` column (include/net/tcp_states.h);
+/// `None` for states the kernel does not report there.
+pub fn tcp_state_label(state: u8) -> Option<&'static str> {
+    let label = match state {
+        TCP_ESTABLISHED => "established",
+        0x02 => "syn_sent",
+        0x03 => "syn_recv",
+        0x04 => "fin_wait1",
+        0x05 => "fin_wait2",
+        TCP_TIME_WAIT => "time_wait",
+        TCP_CLOSE => "close",
+        0x08 => "close_wait",
+        0x09 => "last_ack",
+        TCP_LISTEN => "listening",
+        0x0B => "closing",
+        _ => return None,
+    };
+    Some(label)
+}
+
+pub fn socket_state_label(proto: ProcNetProto, state: u8) -> String {
+    let label = match proto {
+        ProcNetProto::Tcp | ProcNetProto::Tcp6 => tcp_state_label(state),
+        ProcNetProto::Udp | ProcNetProto::Udp6 => match state {
+            UDP_ESTABLISHED => Some("connected"),
+            UDP_LISTEN => Some("listening"),
+            _ => None,
+        },
+    };
+
+    match label {
+        Some(label) => label.to_string(),
+        None => format!("0x{state:02X}"),
+    }
+}
 
 pub fn scan_pid_open_fd_socket(pid: i32, inodes: &HashSet<u64>) -> ProcAccess<bool> {
+    match read_socket_inodes(pid) {
+        ProcAccess::Ok(held) => ProcAccess::Ok(held.iter().any(|i| inodes.contains(i))),
+        ProcAccess::PermissionDenied => ProcAccess::PermissionDenied,
+        ProcAccess::Gone => ProcAccess::Gone,
+        ProcAccess::Fatal(e) => ProcAccess::Fatal(e),
+    }
+}
+
+/// Inodes of the sockets `pid` holds open (`socket:[NNN]` fd links),
+/// sorted, each once.
+pub fn read_socket_inodes(pid: i32) -> ProcAccess<Vec<u64>> {
     let links = match read_fd_links_access(pid) {
         ProcAccess::Ok(v) => v,
         ProcAccess::PermissionDenied => return ProcAccess::PermissionDenied,
         ProcAccess::Gone => return ProcAccess::Gone,
         ProcAccess::Fatal(e) => return ProcAccess::Fatal(e),
     };
+    let mut inodes: Vec<u64> = links
+        .iter()
+        .filter_map(|(_fd, _fd_path, link)| parse_socket_inode(link))
+        .collect();
+    inodes.sort_unstable();
+    inodes.dedup();
+    ProcAccess::Ok(inodes)
+}
+
+/// Which processes hold each socket, from one walk of every fd table.
+#[derive(Debug, Default)]
+pub struct SocketInodeIndex {
+    /// Socket inode to the PIDs holding it, sorted, each once.
+    pub pids_by_inode: BTreeMap<u64, Vec<i32>>,
+    /// PIDs whose fd table could not be read (permission denied).
+    pub denied: Vec<i32>,
+    /// PIDs whose fd table failed to read for any other reason.
+    pub errored: Vec<(i32, io::Error)>,
+}
+
+impl SocketInodeIndex {
+    /// PIDs holding `inode`; empty when none.
+    pub fn pids(&self, inode: u64) -> &[i32] {
+        self.pids_by_inode.get(&inode).map_or(&[], Vec::as_slice)
+    }
+}
+
+/// Build a [`SocketInodeIndex`] over every process. Callers intersect it
+/// with the inodes of the sockets they are interested in.
+pub fn build_socket_inode_index() -> io::Result<SocketInodeIndex> {
+    let mut index = SocketInodeIndex::default();
+    for pid in list_pids()? {
+        match read_socket_inodes(pid) {
+            ProcAccess::Ok(inodes) => {
+                for inode in inodes {
+                    index.pids_by_inode.entry(inode).or_default().push(pid);
+                }
+            }
+            ProcAccess::PermissionDenied => index.denied.push(pid),
+            ProcAccess::Gone => {}
+            ProcAccess::Fatal(e) => index.errored.push((pid, e)),
+        }
+    }
+    Ok(index)
+}
+
+/// Shown for a process name that could not be read, unless overridden.
+pub const DEFAULT_UNKNOWN_LABEL: &str = "<unknown>";
+
+static UNKNOWN_LABEL: OnceLock<String> = OnceLock::new();
+
+/// Override the text shown for unreadable process names (`--unknown-label`).
+///
+/// Must be called before any output; later calls are ignored.
+pub fn set_unknown_label(label: &str) {
+    let _ = UNKNOWN_LABEL.set(label.to_string());
+}
+
+pub fn unknown_label() -> &'static str {
+    UNKNOWN_LABEL
+        .get()
+        .map_or(DEFAULT_UNKNOWN_LABEL, |l| l.as_str())
+}
+
+/// Longest name the kernel keeps in /proc/<pid>/comm (`TASK_COMM_LEN` - 1).
+pub const COMM_MAX_LEN: usize = 15;
+
+/// Where the command name shown for a process comes from
+/// (`--command-source`).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum CommandSource {
+    /// comm, completed from the basename of argv[0] when comm was cut to
+    /// [`COMM_MAX_LEN`] (`systemd-journal` -> `systemd-journald`).
+    #[default]
+    Cmdline,
+    /// comm exactly as the kernel reports it.
+    Comm,
+    /// The whole argv joined by spaces (`--full-command`); comm for kernel
+    /// threads, which have none.
+    Argv,
+}
+
+static COMMAND_SOURCE: OnceLock<CommandSource> = OnceLock::new();
+
+/// Choose where command names come from. Must be called before any scan;
+/// later calls are ignored.
+pub fn set_command_source(source: CommandSource) {
+    let _ = COMMAND_SOURCE.set(source);
+}
+
+pub fn command_source() -> CommandSource {
+    COMMAND_SOURCE.get().copied().unwrap_or_default()
+}
+
+pub fn read_cmdline(pid: i32) -> io::Result<Vec<String>> {
+    let raw = fs::read(proc_path(format!("{pid}/cmdline")))?;
+    Ok(parse_cmdline(&raw))
+}
+
+/// argv of `pid` from /proc/<pid>/cmdline; empty for kernel threads and
+/// zombies.
+pub fn read_cmdline_access(pid: i32) -> ProcAccess<Vec<String>> {
+    match fs::read(proc_path(format!("{pid}/cmdline"))) {
+        Ok(raw) => ProcAccess::Ok(parse_cmdline(&raw)),
+        Err(e) => classify_proc_io_error(e),
+    }
+}
+
+/// Split NUL-separated argv, dropping only the terminating NUL so empty
+/// arguments (`prog ""`) are kept.
+fn parse_cmdline(raw: &[u8]) -> Vec<String> {
+    if raw.is_empty() {
+        return Vec::new();
+    }
+    let raw = raw.strip_suffix(&[0]).unwrap_or(raw);
+    raw.split(|b| *b == 0)
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect()
+}
+
+/// The untruncated name for `comm` when it was cut to [`COMM_MAX_LEN`] and
+/// the basename of `argv0` extends it. Processes that rewrite their argv
+/// (`sshd: user@pts/0`) do not match and keep their comm.
+pub fn full_command_name(comm: &str, argv0: &str) -> Option<String> {
+    if comm.len() < COMM_MAX_LEN {
+        return None;
+    }
+    let base = argv0.rsplit('/').next().unwrap_or(argv0);
+    (base.len() > comm.len() && base.starts_with(comm)).then(|| base.to_string())
+}
+
+/// The command name of `pid` per [`command_source`]: comm, completed from
+/// the cmdline when it looks truncated, or the full argv.
+pub fn read_command_access(pid: i32) -> ProcAccess<String> {
+    let comm = match read_comm_access(pid) {
+        ProcAccess::Ok(c) => c,
+        other => return other,
+    };
+    if command_source() == CommandSource::Argv {
+        return match read_cmdline_access(pid) {
+            ProcAccess::Ok(argv) if !argv.is_empty() => ProcAccess::Ok(argv.join(" ")),
+            ProcAccess::Gone => ProcAccess::Gone,
+            _ => ProcAccess::Ok(comm),
+        };
+    }
+    if command_source() == CommandSource::Comm || comm.len() < COMM_MAX_LEN {
+        return ProcAccess::Ok(comm);
+    }
+    let full = match read_cmdline_access(pid) {
+        ProcAccess::Ok(argv) => argv.first().and_then(|a| full_command_name(&comm, a)),
+        ProcAccess::PermissionDenied | ProcAccess::Gone | ProcAccess::Fatal(_) => None,
+    };
+    ProcAccess::Ok(full.unwrap_or(comm))
+}
+
+/// A process name (see [`read_command_access`]), or unknown when it could
+/// not be read.
+///
+/// Human output shows [`unknown_label`] for an unknown name; JSON gets
+/// `null`, so "could not read" never looks like a process that is literally
+/// named `<unknown>`.
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Comm(Option<String>);
+
+impl Comm {
+    pub fn known(name: impl Into<String>) -> Self {
+        Comm(Some(name.into()))
+    }
+
+    pub fn unknown() -> Self {
+        Comm(None)
+    }
+
+    /// The name as read, `None` when unknown.
+    pub fn get(&self) -> Option<&str> {
+        self.0.as_deref()
+    }
+
+    /// The name, or the unknown label.
+    pub fn as_str(&self) -> &str {
+        match &self.0 {
+            Some(name) => name,
+            None => unknown_label(),
+        }
+    }
+}
+
+impl Deref for Comm {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl fmt::Display for Comm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+impl Serialize for Comm {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+/// What a scan learns about a process when it first visits it, before
+/// reading its fds or maps.
+///
+/// The name is captured up front: a holder that exits right after matching
+/// would otherwise be reported under the unknown label, since its comm is
+/// gone by the time the match is recorded.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProcSnapshot {
+    pub pid: i32,
+    pub comm: Comm,
+}
+
+impl ProcSnapshot {
+    /// `Gone` when the process has already exited, so the walk can skip it
+    /// without further reads. An unreadable name is kept as unknown.
+    pub fn capture(pid: i32) -> ProcAccess<Self> {
+        let comm = match read_command_access(pid) {
+            ProcAccess::Ok(s) => Comm::known(s),
+            ProcAccess::Gone => return ProcAccess::Gone,
+            ProcAccess::PermissionDenied | ProcAccess::Fatal(_) => Comm::unknown(),
+        };
+        ProcAccess::Ok(ProcSnapshot { pid, comm })
+    }
+}
+
+/// Read the command name of `pid` (see [`read_command_access`]), giving
+/// [`Comm::unknown`] on any failure.
+pub fn read_comm_best_effort(pid: i32) -> Comm {
+    match read_command_access(pid) {
+        ProcAccess::Ok(s) => Comm::known(s),
+        ProcAccess::PermissionDenied | ProcAccess::Gone | ProcAccess::Fatal(_) => Comm::unknown(),
+    }
+}
+
+/// Executable of `pid` from the /proc/<pid>/exe link. Unlike comm it is
+/// neither truncated nor settable by the process. A replaced or removed
+/// binary keeps its ` (deleted)` suffix.
+pub fn read_exe(pid: i32) -> ProcAccess<PathBuf> {
+    match fs::read_link(proc_path(format!("{pid}/exe"))) {
+        Ok(p) => ProcAccess::Ok(p),
+        Err(e) => classify_proc_io_error(e),
+    }
+}
+
+/// [`read_exe`] as display text; `None` on any failure (kernel threads have
+/// no exe, other users' processes deny it without privileges).
+pub fn read_exe_best_effort(pid: i32) -> Option<String> {
+    match read_exe(pid) {
+        ProcAccess::Ok(p) => Some(p.display().to_string()),
+        ProcAccess::PermissionDenied | ProcAccess::Gone | ProcAccess::Fatal(_) => None,
+    }
+}
+
+/// Namespace identities of a process: the inode behind each
+/// /proc/<pid>/ns/<kind> link (e.g. `mnt:[4026531841]`). Two processes share
+/// a namespace exactly when these inodes are equal.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct NsIds {
+    pub mnt: u64,
+    pub net: u64,
+    pub pid: u64,
+}
+
+impl NsIds {
+    /// Whether `other` sees the same filesystem tree and network stack,
+    /// i.e. is not in a different container.
+    pub fn same_mnt_net(&self, other: &NsIds) -> bool {
+        self.mnt == other.mnt && self.net == other.net
+    }
+}
+
+/// Inode of a /proc/<pid>/ns link such as `net:[4026531840]`.
+pub fn parse_ns_link(link: &str, kind: &str) -> Option<u64> {
+    parse_bracketed_inode(link, &format!("{kind}:"))
+}
+
+/// Mount, network and PID namespace inodes of `pid`.
+pub fn read_ns_ids(pid: i32) -> ProcAccess<NsIds> {
+    let read = |kind: &str| -> ProcAccess<u64> {
+        match fs::read_link(proc_path(format!("{pid}/ns/{kind}"))) {
+            Ok(link) => match parse_ns_link(&link.to_string_lossy(), kind) {
+                Some(inode) => ProcAccess::Ok(inode),
+                None => ProcAccess::Fatal(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("unexpected ns link: {}", link.display()),
+                )),
+            },
+            Err(e) => classify_proc_io_error(e),
+        }
+    };
+    let mut ids = [0u64; 3];
+    for (slot, kind) in ids.iter_mut().zip(["mnt", "net", "pid"]) {
+        match read(kind) {
+            ProcAccess::Ok(inode) => *slot = inode,
+            ProcAccess::PermissionDenied => return ProcAccess::PermissionDenied,
+            ProcAccess::Gone => return ProcAccess::Gone,
+            ProcAccess::Fatal(e) => return ProcAccess::Fatal(e),
+        }
+    }
+    let [mnt, net, pid] = ids;
+    ProcAccess::Ok(NsIds { mnt, net, pid })
+}
+
+/// Whether `pid` is executing `target`: `Some(deleted)` when its exe is the
+/// target, with `deleted` set once the running binary has been replaced or
+/// removed. Compared by identity through the magic link when live (which
+/// still resolves a deleted binary), by [`read_exe`] text offline.
+pub fn pid_exe_match(pid: i32, target: &FileMatch) -> ProcAccess<Option<bool>> {
+    let exe = match read_exe(pid) {
+        ProcAccess::Ok(p) => p,
+        ProcAccess::PermissionDenied => return ProcAccess::PermissionDenied,
+        ProcAccess::Gone => return ProcAccess::Gone,
+        ProcAccess::Fatal(e) => return ProcAccess::Fatal(e),
+    };
+    let (path, deleted) = strip_deleted_marker(&exe.to_string_lossy());
+    let matches = if target.by_identity() {
+        match fs::metadata(proc_path(format!("{pid}/exe"))) {
+            Ok(md) => target.matches_id(&file_id_for_metadata(&md)),
+            Err(e) => return classify_proc_io_error(e),
+        }
+    } else {
+        target.matches_text(&path)
+    };
+    ProcAccess::Ok(matches.then_some(deleted))
+}
+
+/// Thread IDs of `pid` from /proc/<pid>/task, sorted; the main thread's
+/// TID equals `pid`.
+pub fn list_tasks(pid: i32) -> ProcAccess<Vec<i32>> {
+    match list_tids(pid) {
+        Ok(tids) => ProcAccess::Ok(tids),
+        Err(e) => classify_proc_io_error(e),
+    }
+}
+
+pub fn list_tids(pid: i32) -> io::Result<Vec<i32>> {
+    let mut tids = Vec::new();
+    for entry in fs::read_dir(proc_path(format!("{pid}/task")))? {
+        if let Ok(tid) = entry?.file_name().to_string_lossy().parse::<i32>() {
+            tids.push(tid);
+        }
+    }
+
+    tids.sort_unstable();
+    Ok(tids)
+}
+
+/// Per-thread name from /proc/<pid>/task/<tid>/comm; threads can rename
+/// themselves (`prctl(PR_SET_NAME)`), so it may differ from the process comm.
+pub fn read_task_comm(pid: i32, tid: i32) -> ProcAccess<String> {
+    let path = proc_path(format!("{pid}/task/{tid}/comm"));
+    match fs::read_to_string(path) {
+        Ok(c) => ProcAccess::Ok(normalize_comm(&c)),
+        Err(e) => classify_proc_io_error(e),
+    }
+}
+
+/// `(tid, comm)` for every thread of `pid`, skipping threads that exit or
+/// cannot be read mid-walk; empty when the task list itself is unreadable.
+pub fn task_comms_best_effort(pid: i32) -> Vec<(i32, String)> {
+    let tids = match list_tasks(pid) {
+        ProcAccess::Ok(t) => t,
+        ProcAccess::PermissionDenied | ProcAccess::Gone | ProcAccess::Fatal(_) => {
+            return Vec::new()
+        }
+    };
+    tids.into_iter()
+        .filter_map(|tid| match read_task_comm(pid, tid) {
+            ProcAccess::Ok(comm) => Some((tid, comm)),
+            ProcAccess::PermissionDenied | ProcAccess::Gone | ProcAccess::Fatal(_) => None,
+        })
+        .collect()
+}
+
+/// Fields of interest from /proc/<pid>/status.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ProcStatus {
+    pub ppid: i32,
+    /// State letter (`R`, `S`, `Z`, ...); `'\0'` when the line is missing.
+    pub state: char,
+    /// Effective capability mask (`CapEff`), bit N = capability N.
+    pub cap_eff: u64,
+}
+
+impl ProcStatus {
+    pub fn is_zombie(&self) -> bool {
+        self.state == 'Z'
+    }
+
+    pub fn has_capability(&self, cap: u32) -> bool {
+        mask_has_capability(self.cap_eff, cap)
+    }
+}
+
+/// Whether capability number `cap` is set in a `Cap*` mask from
+/// /proc/<pid>/status.
+pub fn mask_has_capability(mask: u64, cap: u32) -> bool {
+    cap < 64 && mask & (1 << cap) != 0
+}
+
+/// Bypass directory read/search checks (walk other users' /proc entries).
+pub const CAP_DAC_READ_SEARCH: u32 = 2;
+/// Read other users' fd links and maps (ptrace read access).
+pub const CAP_SYS_PTRACE: u32 = 19;
+pub const CAP_SYS_ADMIN: u32 = 21;
+
+/// Capability names by number, as in `<linux/capability.h>` (lowercase, like
+/// capsh(1) prints them).
+const CAPABILITY_NAMES: &[&str] = &[
+    "cap_chown",
+    "cap_dac_override",
+    "cap_dac_read_search",
+    "cap_fowner",
+    "cap_fsetid",
+    "cap_kill",
+    "cap_setgid",
+    "cap_setuid",
+    "cap_setpcap",
+    "cap_linux_immutable",
+    "cap_net_bind_service",
+    "cap_net_broadcast",
+    "cap_net_admin",
+    "cap_net_raw",
+    "cap_ipc_lock",
+    "cap_ipc_owner",
+    "cap_sys_module",
+    "cap_sys_rawio",
+    "cap_sys_chroot",
+    "cap_sys_ptrace",
+    "cap_sys_pacct",
+    "cap_sys_admin",
+    "cap_sys_boot",
+    "cap_sys_nice",
+    "cap_sys_resource",
+    "cap_sys_time",
+    "cap_sys_tty_config",
+    "cap_mknod",
+    "cap_lease",
+    "cap_audit_write",
+    "cap_audit_control",
+    "cap_setfcap",
+    "cap_mac_override",
+    "cap_mac_admin",
+    "cap_syslog",
+    "cap_wake_alarm",
+    "cap_block_suspend",
+    "cap_audit_read",
+    "cap_perfmon",
+    "cap_bpf",
+    "cap_checkpoint_restore",
+];
+
+/// Names of the capabilities set in `mask`, lowest bit first. Bits newer
+/// than this table are named by number (`cap_41`).
+pub fn capability_names(mask: u64) -> Vec<String> {
+    (0..64u32)
+        .filter(|bit| mask & (1 << bit) != 0)
+        .map(|bit| match CAPABILITY_NAMES.get(bit as usize) {
+            Some(name) => name.to_string(),
+            None => format!("cap_{bit}"),
+        })
+        .collect()
+}
+
+/// Whether `mask` holds every capability this build knows about (a root
+/// process, or one granted the full set).
+pub fn is_full_capability_set(mask: u64) -> bool {
+    let known = (1u64 << CAPABILITY_NAMES.len()) - 1;
+    mask & known == known
+}
+
+fn parse_proc_status(contents: &str) -> ProcStatus {
+    let mut status = ProcStatus::default();
+    for line in contents.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        match key {
+            "PPid" => status.ppid = value.trim().parse::<i32>().unwrap_or(0),
+            "State" => status.state = value.trim().chars().next().unwrap_or('\0'),
+            "CapEff" => status.cap_eff = u64::from_str_radix(value.trim(), 16).unwrap_or(0),
+            _ => {}
+        }
+    }
+    status
+}
+
+pub fn read_proc_status_access(pid: i32) -> ProcAccess<ProcStatus> {
+    let path = proc_path(format!("{pid}/status"));
+    match fs::read_to_string(path) {
+        Ok(c) => ProcAccess::Ok(parse_proc_status(&c)),
+        Err(e) => classify_proc_io_error(e),
+    }
+}
+
+/// Fields of interest from /proc/<pid>/stat.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ProcStat {
+    pub state: char,
+    pub ppid: i32,
+    /// Start time in clock ticks after boot (`USER_HZ`, see
+    /// [`SOCKET_TIMER_TICKS_PER_SEC`]).
+    pub starttime_ticks: u64,
+}
+
+/// Parse a /proc/<pid>/stat line. The comm field is parenthesised but may
+/// itself contain spaces and `)`, so the fixed fields start after the last
+/// `)` on the line.
+fn parse_proc_stat(contents: &str) -> Option<ProcStat> {
+    let (_, rest) = contents.rsplit_once(')')?;
+    // Fields 3.. of proc_pid_stat(5): state, ppid, ..., starttime (22).
+    let fields: Vec<&str> = rest.split_whitespace().collect();
+    let mut state = fields.first()?.chars();
+    let (Some(state), None) = (state.next(), state.next()) else {
+        return None;
+    };
+    Some(ProcStat {
+        state,
+        ppid: fields.get(1)?.parse().ok()?,
+        starttime_ticks: fields.get(19)?.parse().ok()?,
+    })
+}
+
+pub fn read_stat(pid: i32) -> ProcAccess<ProcStat> {
+    let path = proc_path(format!("{pid}/stat"));
+    match fs::read_to_string(&path) {
+        Ok(c) => match parse_proc_stat(&c) {
+            Some(stat) => ProcAccess::Ok(stat),
+            None => ProcAccess::Fatal(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: malformed stat line", path.display()),
+            )),
+        },
+        Err(e) => classify_proc_io_error(e),
+    }
+}
+
+/// Memory totals from /proc/<pid>/smaps_rollup, in kB as reported.
+///
+/// These are process-wide sums over all mappings, not per-file figures.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SmapsRollup {
+    pub rss_kb: u64,
+    pub pss_kb: u64,
+    pub shared_clean_kb: u64,
+    pub shared_dirty_kb: u64,
+}
+
+fn parse_smaps_rollup(contents: &str) -> SmapsRollup {
+    let mut out = SmapsRollup::default();
+    for line in contents.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let Some(kb) = value
+            .split_whitespace()
+            .next()
+            .and_then(|v| v.parse::<u64>().ok())
+        else {
+            continue;
+        };
+        match key {
+            "Rss" => out.rss_kb = kb,
+            "Pss" => out.pss_kb = kb,
+            "Shared_Clean" => out.shared_clean_kb = kb,
+            "Shared_Dirty" => out.shared_dirty_kb = kb,
+            _ => {}
+        }
+    }
+    out
+}
+
+/// Read /proc/<pid>/smaps_rollup (Linux 4.14+).
+///
+/// Kernels without the rollup file report `Gone`, same as an exited process.
+pub fn read_smaps_rollup(pid: i32) -> ProcAccess<SmapsRollup> {
+    let path = proc_path(format!("{pid}/smaps_rollup"));
+    match fs::read_to_string(path) {
+        Ok(c) => ProcAccess::Ok(parse_smaps_rollup(&c)),
+        Err(e) => classify_proc_io_error(e),
+    }
+}
+
+/// Upper bound on parent-chain length; real trees are far shallower.
+const MAX_PARENT_CHAIN: usize = 256;
+
+/// Walk PPid links from `pid` towards init.
+///
+/// Returns the ancestors nearest-first, excluding `pid` itself. The walk
+/// stops at PID 1, at a parent that is gone or unreadable, or on a cycle
+/// (possible when PIDs are reused mid-walk).
+pub fn parent_chain(pid: i32) -> Vec<i32> {
+    let mut chain = Vec::new();
+    let mut seen: HashSet<i32> = HashSet::new();
+    seen.insert(pid);
+
+    let mut current = pid;
+    while current > 1 && chain.len() < MAX_PARENT_CHAIN {
+        let ppid = match read_proc_status_access(current) {
+            ProcAccess::Ok(s) => s.ppid,
+            ProcAccess::PermissionDenied | ProcAccess::Gone | ProcAccess::Fatal(_) => break,
+        };
+        if ppid <= 0 || !seen.insert(ppid) {
+            break;
+        }
+        chain.push(ppid);
+        current = ppid;
+    }
+
+    chain
+}
+
+/// The cgroup path of a process from /proc/<pid>/cgroup.
+///
+/// Prefers the v2 (`0::`) entry, then the v1 `name=systemd` one, then any
+/// other controller, skipping entries that are just `/` (as on hybrid
+/// setups where the unified hierarchy is unused).
+pub fn parse_proc_cgroup(contents: &str) -> Option<String> {
+    let mut candidates: Vec<(u8, &str)> = Vec::new();
+    for line in contents.lines() {
+        let mut parts = line.splitn(3, ':');
+        let (Some(id), Some(controllers), Some(path)) = (parts.next(), parts.next(), parts.next())
+        else {
+            continue;
+        };
+        let rank = if id == "0" && controllers.is_empty() {
+            0
+        } else if controllers == "name=systemd" {
+            1
+        } else {
+            2
+        };
+        candidates.push((rank, path));
+    }
+    candidates.sort_by_key(|(rank, _)| *rank);
+    candidates
+        .iter()
+        .find(|(_, path)| *path != "/")
+        .or(candidates.first())
+        .map(|(_, path)| path.to_string())
+}
+
+pub fn read_cgroup_access(pid: i32) -> ProcAccess<Option<String>> {
+    let path = proc_path(format!("{pid}/cgroup"));
+    match fs::read_to_string(path) {
+        Ok(c) => ProcAccess::Ok(parse_proc_cgroup(&c)),
+        Err(e) => classify_proc_io_error(e),
+    }
+}
+
+/// Who a cgroup path belongs to, by naming convention only.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CgroupUnit {
+    /// A container; `id` is the 12-character short id.
+    Container {
+        runtime: &'static str,
+        id: String,
+    },
+    /// A Kubernetes pod, by pod UID.
+    Pod(String),
+    /// A systemd unit (`nginx.service`, `session-2.scope`, ...).
+    Systemd(String),
+    Unknown,
+}
+
+impl CgroupUnit {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            CgroupUnit::Container { runtime, .. } => runtime,
+            CgroupUnit::Pod(_) => "pod",
+            CgroupUnit::Systemd(_) => "systemd",
+            CgroupUnit::Unknown => "unknown",
+        }
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            CgroupUnit::Container { id, .. } => Some(id),
+            CgroupUnit::Pod(uid) => Some(uid),
+            CgroupUnit::Systemd(unit) => Some(unit),
+            CgroupUnit::Unknown => None,
+        }
+    }
+}
+
+const CONTAINER_SCOPE_PREFIXES: &[(&str, &str)] = &[
+    ("docker-", "docker"),
+    ("libpod-", "podman"),
+    ("cri-containerd-", "containerd"),
+    ("crio-", "cri-o"),
+];
+
+fn is_container_id(s: &str) -> bool {
+    s.len() == 64 && s.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Pod UID from `pod<uid>` (cgroupfs driver) or
+/// `kubepods-<qos>-pod<uid_with_underscores>.slice` (systemd driver).
+fn kube_pod_uid(part: &str) -> Option<String> {
+    let part = part.strip_suffix(".slice").unwrap_or(part);
+    let uid = &part[part.rfind("pod")? + 3..];
+    let uid_like = uid.len() == 36
+        && uid
+            .bytes()
+            .all(|b| b.is_ascii_hexdigit() || b == b'-' || b == b'_');
+    uid_like.then(|| uid.replace('_', "-"))
+}
+
+fn container_in(part: &str, parent: Option<&str>) -> Option<CgroupUnit> {
+    let short = |id: &str| id[..12].to_string();
+    for (prefix, runtime) in CONTAINER_SCOPE_PREFIXES {
+        if let Some(id) = part
+            .strip_prefix(prefix)
+            .and_then(|rest| rest.strip_suffix(".scope"))
+        {
+            if is_container_id(id) {
+                return Some(CgroupUnit::Container {
+                    runtime,
+                    id: short(id),
+                });
+            }
+        }
+    }
+    if is_container_id(part) {
+        // cgroupfs layout: /docker/<id>, /kubepods/.../<id>, ...
+        let runtime = if parent == Some("docker") {
+            "docker"
+        } else {
+            "container"
+        };
+        return Some(CgroupUnit::Container {
+            runtime,
+            id: short(part),
+        });
+    }
+    None
+}
+
+fn is_systemd_unit(part: &str) -> bool {
+    [".service", ".scope", ".socket", ".mount", ".swap"]
+        .iter()
+        .any(|suffix| part.len() > suffix.len() && part.ends_with(suffix))
+}
+
+/// Best-effort owner of a cgroup path: a Kubernetes pod wins over the
+/// container inside it, a container over its systemd scope, and otherwise
+/// the innermost systemd unit.
+pub fn classify_cgroup(path: &str) -> CgroupUnit {
+    let parts: Vec<&str> = path.split('/').filter(|p| !p.is_empty()).collect();
+
+    if parts.iter().any(|p| p.starts_with("kubepods")) {
+        if let Some(uid) = parts.iter().find_map(|p| kube_pod_uid(p)) {
+            return CgroupUnit::Pod(uid);
+        }
+    }
+
+    for (i, part) in parts.iter().enumerate().rev() {
+        let parent = i.checked_sub(1).map(|p| parts[p]);
+        if let Some(unit) = container_in(part, parent) {
+            return unit;
+        }
+    }
+
+    match parts.iter().rev().find(|p| is_systemd_unit(p)) {
+        Some(unit) => CgroupUnit::Systemd(unit.to_string()),
+        None => CgroupUnit::Unknown,
+    }
+}
+
+/// [`classify_cgroup`] for a PID; `Unknown` when its cgroup is unreadable.
+pub fn read_cgroup_unit_best_effort(pid: i32) -> CgroupUnit {
+    match read_cgroup_access(pid) {
+        ProcAccess::Ok(Some(path)) => classify_cgroup(&path),
+        ProcAccess::Ok(None)
+        | ProcAccess::PermissionDenied
+        | ProcAccess::Gone
+        | ProcAccess::Fatal(_) => CgroupUnit::Unknown,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fsmeta::dev_major_minor;
+
+    #[test]
+    fn parse_mountinfo_line_basic_and_escaped() {
+        let line =
+            "36 35 98:0 /mnt1 /mnt\\040two rw,noatime master:1 - ext3 /dev/root rw,errors=continue";
+        let m = parse_mountinfo_line(line).unwrap();
+        assert_eq!(m.mount_id, 36);
+        assert_eq!(m.parent_id, 35);
+        assert_eq!((m.major, m.minor), (98, 0));
+        assert_eq!(m.root, PathBuf::from("/mnt1"));
+        assert_eq!(m.mount_point, PathBuf::from("/mnt two"));
+        assert_eq!(m.fstype, "ext3");
+        assert_eq!(m.source, "/dev/root");
+
+        assert!(parse_mountinfo_line("garbage").is_none());
+    }
+
+    #[test]
+    fn bind_views_maps_paths_across_binds() {
+        let mounts: Vec<MountInfoEntry> = [
+            "1 0 8:1 / / rw - ext4 /dev/sda1 rw",
+            "2 1 8:2 / /srv rw - ext4 /dev/sda2 rw",
+            "3 1 8:2 /data/app /var/lib/app rw - ext4 /dev/sda2 rw",
+            "4 1 8:2 /other /opt/other rw - ext4 /dev/sda2 rw",
+            "5 1 8:2 /data /hidden rw - ext4 /dev/sda2 rw",
+            "6 5 0:40 / /hidden/app rw - tmpfs tmpfs rw",
+        ]
+        .iter()
+        .filter_map(|l| parse_mountinfo_line(l))
+        .collect();
+
+        let views = bind_views(Path::new("/srv/data/app/db.sqlite"), &mounts);
+        assert_eq!(views, vec![PathBuf::from("/var/lib/app/db.sqlite")]);
+
+        let back = bind_views(Path::new("/var/lib/app/db.sqlite"), &mounts);
+        assert_eq!(back, vec![PathBuf::from("/srv/data/app/db.sqlite")]);
+
+        assert!(bind_views(Path::new("/etc/hosts"), &mounts).is_empty());
+    }
+
+    #[test]
+    fn path_match_finds_own_fd_and_mapping() {
+        let pid = std::process::id() as i32;
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let path = std::env::temp_dir().join(format!("zenlixem_match_{pid}_{nanos}"));
+        let file = fs::File::create(&path).unwrap();
+
+        let by_text = FileMatch::Path(path.display().to_string());
+        let by_id = FileMatch::Ids(vec![file_id_for_path(&path).unwrap()]);
+        for target in [&by_text, &by_id] {
+            match find_pid_open_fd_match(pid, target) {
+                ProcAccess::Ok(fds) => assert!(!fds.is_empty(), "{target:?}"),
+                other => panic!("unexpected: {other:?}"),
+            }
+        }
+
+        let exe = std::env::current_exe().unwrap();
+        let exe_text = FileMatch::Path(exe.display().to_string());
+        match find_pid_mmap_match(pid, &exe_text) {
+            ProcAccess::Ok(entries) => assert!(!entries.is_empty()),
+            other => panic!("unexpected: {other:?}"),
+        }
+
+        drop(file);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn map_match_modes_over_differing_devices() {
+        // The file as stat sees it through an overlay mount (0:52) and the
+        // same inode as maps reports it on the backing device (8:1).
+        let id = FileId {
+            dev: makedev(0, 52),
+            inode: 1234,
+        };
+        let entry = |major, minor, inode| ProcMapEntry {
+            perms: "r-xp".to_string(),
+            dev_major: major,
+            dev_minor: minor,
+            inode,
+            pathname: Some("/usr/lib/libfoo.so".to_string()),
+            deleted: false,
+        };
+        let backing = entry(8, 1, 1234);
+        let same = entry(0, 52, 1234);
+        let other = entry(8, 1, 99);
+        let anon = entry(0, 0, 0);
+
+        for target in [
+            FileMatch::Ids(vec![id]),
+            FileMatch::Tree(HashSet::from([id])),
+        ] {
+            assert!(target.matches_map_with(&same, IdMatch::ExactDevInode));
+            assert!(!target.matches_map_with(&backing, IdMatch::ExactDevInode));
+            assert!(target.matches_map_with(&backing, IdMatch::InodeOnly));
+            assert!(!target.matches_map_with(&other, IdMatch::InodeOnly));
+            assert!(!target.matches_map_with(&anon, IdMatch::InodeOnly));
+        }
+
+        let by_text = FileMatch::Path("/usr/lib/libfoo.so".to_string());
+        assert!(by_text.matches_map_with(&other, IdMatch::ExactDevInode));
+        assert!(by_text.matches_map_with(&other, IdMatch::InodeOnly));
+    }
+
+    #[test]
+    fn subtree_match_finds_fd_and_mapping_below_dir() {
+        let pid = std::process::id() as i32;
+        let dir = std::env::temp_dir().join(format!("zenlixem_under_{pid}"));
+        fs::create_dir_all(dir.join("a/b")).unwrap();
+        let path = dir.join("a/b/held");
+        let file = fs::File::create(&path).unwrap();
+
+        let (tree, unreadable) = file_match_under(&dir).unwrap();
+        assert_eq!(unreadable, 0);
+        assert!(tree.matches_id(&file_id_for_path(&dir.join("a")).unwrap()));
+        match find_pid_open_fd_match(pid, &tree) {
+            ProcAccess::Ok(fds) => assert!(!fds.is_empty()),
+            other => panic!("unexpected: {other:?}"),
+        }
+
+        let exe = std::env::current_exe().unwrap();
+        let (exe_tree, _) = file_match_under(exe.parent().unwrap()).unwrap();
+        match find_pid_mmap_match(pid, &exe_tree) {
+            ProcAccess::Ok(entries) => assert!(!entries.is_empty()),
+            other => panic!("unexpected: {other:?}"),
+        }
+
+        drop(file);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn inode_match_finds_own_fd() {
+        let pid = std::process::id() as i32;
+        let path = std::env::temp_dir().join(format!("zenlixem_inode_{pid}"));
+        let file = fs::File::create(&path).unwrap();
+        let id = file_id_for_path(&path).unwrap();
+        let (major, minor) = dev_major_minor(id.dev);
+
+        let _ = fs::remove_file(&path);
+        match find_pid_open_fd_match(pid, &file_match_for_inode(major, minor, id.inode)) {
+            ProcAccess::Ok(fds) => assert!(!fds.is_empty()),
+            other => panic!("unexpected: {other:?}"),
+        }
+        drop(file);
+    }
+
+    #[test]
+    fn under_matches_dir_and_descendants_only() {
+        let under = FileMatch::Under("/mnt/data/".to_string());
+        assert!(under.matches_text("/mnt/data"));
+        assert!(under.matches_text("/mnt/data/x/y.db"));
+        assert!(!under.matches_text("/mnt/database"));
+        assert!(!under.matches_text("/mnt"));
+        assert!(!FileMatch::Path("/mnt/data".to_string()).matches_text("/mnt/data/x"));
+    }
+
+    #[test]
+    fn classify_fd_link_kinds() {
+        assert_eq!(classify_fd_link("/var/log/syslog"), FdKind::File);
+        assert_eq!(classify_fd_link("socket:[12345]"), FdKind::Socket(12345));
+        assert_eq!(classify_fd_link("pipe:[678]"), FdKind::Pipe(678));
+        assert_eq!(
+            classify_fd_link("anon_inode:[eventfd]"),
+            FdKind::AnonInode("eventfd".to_string())
+        );
+        assert_eq!(
+            classify_fd_link("anon_inode:inotify"),
+            FdKind::AnonInode("inotify".to_string())
+        );
+        assert_eq!(classify_fd_link("net:[4026531840]"), FdKind::Other);
+        assert_eq!(classify_fd_link("socket:[oops]"), FdKind::Other);
+    }
+
+    #[test]
+    fn fd_rows_sort_and_resolve_sockets() {
+        let target = |fd: i32, target: &str, deleted: bool| FdTarget {
+            fd,
+            fd_path: PathBuf::from(format!("/proc/1/fd/{fd}")),
+            target: target.to_string(),
+            deleted,
+        };
+        let listener = ProcNetSocketEntry {
+            proto: ProcNetProto::Tcp,
+            local_addr: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            local_port: 8080,
+            remote_addr: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            remote_port: 0,
+            inode: 77,
+            state: TCP_LISTEN,
+            tx_queue: 0,
+            rx_queue: 0,
+            timer: 0,
+            timer_ticks: 0,
+        };
+        let rows = fd_rows(
+            vec![
+                target(5, "socket:[99]", false),
+                target(3, "socket:[77]", false),
+                target(0, "/tmp/x", true),
+                target(4, "pipe:[12]", false),
+            ],
+            &[listener],
+        );
+        let details: Vec<(i32, &str, String)> =
+            rows.iter().map(|r| (r.fd, r.kind, r.detail())).collect();
+        assert_eq!(
+            details,
+            [
+                (0, "file", "/tmp/x (deleted)".to_string()),
+                (3, "socket", "tcp 127.0.0.1:8080 listening".to_string()),
+                (4, "pipe", "pipe:[12]".to_string()),
+                (5, "socket", "socket:[99]".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_dev_hex_ok() {
+        assert_eq!(parse_dev_hex("08:01"), Some((8, 1)));
+    }
+
+    #[test]
+    fn parse_dev_hex_bad() {
+        assert_eq!(parse_dev_hex("zz:01"), None);
+    }
+
+    #[test]
+    fn parse_dev_hex_missing_minor() {
+        assert_eq!(parse_dev_hex("08"), None);
+    }
+
+    #[test]
+    fn parse_proc_maps_line_regular() {
+        let line = "7f2c1a200000-7f2c1a228000 r--p 00000000 08:01 1835017                    /usr/lib/x86_64-linux-gnu/libc.so.6";
+        let e = parse_proc_maps_line(line).unwrap();
+        assert_eq!(e.perms, "r--p");
+        assert_eq!((e.dev_major, e.dev_minor, e.inode), (8, 1, 1835017));
+        assert_eq!(
+            e.pathname.as_deref(),
+            Some("/usr/lib/x86_64-linux-gnu/libc.so.6")
+        );
+        assert!(!e.deleted);
+    }
+
+    #[test]
+    fn parse_proc_maps_line_deleted_with_spaces() {
+        let line = "7f2c1a200000-7f2c1a228000 rw-s 00000000 fd:00 42 /tmp/my file.db (deleted)";
+        let e = parse_proc_maps_line(line).unwrap();
+        assert_eq!(e.pathname.as_deref(), Some("/tmp/my file.db"));
+        assert!(e.deleted);
+    }
+
+    #[test]
+    fn parse_proc_maps_line_anonymous() {
+        let line = "7ffd5c9e1000-7ffd5ca02000 rw-p 00000000 00:00 0";
+        let e = parse_proc_maps_line(line).unwrap();
+        assert_eq!(e.inode, 0);
+        assert_eq!(e.pathname, None);
+        assert!(!e.deleted);
+    }
+
+    #[test]
+    fn parse_proc_maps_line_truncated() {
+        assert_eq!(parse_proc_maps_line("7ffd5c9e1000-7ffd5ca02000 rw-p"), None);
+    }
+
+    #[test]
+    fn read_fd_targets_marks_deleted_files() {
+        let path = std::env::temp_dir().join(format!(
+            "zenlixem_fd_deleted_test_{}_{}.txt",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let f = fs::File::create(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let targets = match read_fd_targets_access(std::process::id() as i32) {
+            ProcAccess::Ok(v) => v,
+            other => panic!("unexpected result: {other:?}"),
+        };
+        let expected = path.to_string_lossy().to_string();
+        assert!(targets.iter().any(|t| t.deleted && t.target == expected));
+
+        drop(f);
+    }
+
+    #[test]
+    fn fd_target_size_of_unlinked_file() {
+        use std::io::Write;
+        use std::os::fd::AsRawFd;
+
+        let path = std::env::temp_dir().join(format!(
+            "zenlixem_fd_size_test_{}_{}.txt",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let mut f = fs::File::create(&path).unwrap();
+        f.write_all(&[0u8; 4096]).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let pid = std::process::id() as i32;
+        match fd_target_size(pid, f.as_raw_fd()) {
+            ProcAccess::Ok(size) => assert_eq!(size, 4096),
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
 
-    for (_fd, _fd_path, link) in links {
-        let Some(inode) = parse_socket_inode(&link) else {
-            continue;
+    #[test]
+    fn fd_target_size_closed_fd_is_gone() {
+        let pid = std::process::id() as i32;
+        assert!(matches!(fd_target_size(pid, i32::MAX), ProcAccess::Gone));
+        assert!(matches!(fd_target_size(-1, 0), ProcAccess::Gone));
+    }
+
+    #[test]
+    fn fd_file_ids_are_sorted_and_cached_matches_agree() {
+        use std::os::fd::AsRawFd;
+
+        let path =
+            std::env::temp_dir().join(format!("zenlixem_fd_ids_test_{}", std::process::id()));
+        let f = fs::File::create(&path).unwrap();
+        let target = file_match_for_path(&path).unwrap();
+        let pid = std::process::id() as i32;
+
+        let ProcAccess::Ok(ids) = read_fd_file_ids(pid) else {
+            panic!("own fds unreadable");
         };
+        assert!(ids.windows(2).all(|w| w[0].0 < w[1].0));
+        assert!(ids.iter().any(|(fd, _)| *fd == f.as_raw_fd()));
 
-        if inodes.contains(&inode) {
-            return ProcAccess::Ok(true);
+        let mut cache = FdIdCache::new(pid);
+        let ProcAccess::Ok(cached) = cache.find_match(&target) else {
+            panic!("cached match failed");
+        };
+        assert!(cached.contains(&f.as_raw_fd()));
+        match find_pid_open_fd_match(pid, &target) {
+            ProcAccess::Ok(direct) => assert_eq!(cached, direct),
+            other => panic!("unexpected result: {other:?}"),
         }
+        assert!(matches!(read_fd_file_ids(-1), ProcAccess::Gone));
+        drop(f);
+        fs::remove_file(&path).unwrap();
     }
 
-    ProcAccess::Ok(false)
-}
+    #[test]
+    fn proc_map_entries_stream_and_short_circuit() {
+        let pid = std::process::id() as i32;
+        let mut seen = 0usize;
+        let res = for_each_proc_map_entry(pid, |_| {
+            seen += 1;
+            ControlFlow::Break(())
+        });
+        assert!(matches!(res, ProcAccess::Ok(())));
+        assert_eq!(seen, 1);
+
+        let exe = fs::read_link("/proc/self/exe").unwrap();
+        let target = file_match_for_path(&exe).unwrap();
+        assert!(matches!(
+            pid_has_mmap_match(pid, &target),
+            ProcAccess::Ok(true)
+        ));
+        assert!(matches!(pid_has_mmap_match(-1, &target), ProcAccess::Gone));
+    }
 
-/// Read /proc/<pid>/comm, returning "<unknown>" on any failure.
-pub fn read_comm_best_effort(pid: i32) -> String {
-    match read_comm_access(pid) {
-        ProcAccess::Ok(s) => s,
-        ProcAccess::PermissionDenied | ProcAccess::Gone | ProcAccess::Fatal(_) => {
-            "<unknown>".to_string()
-        }
+    #[test]
+    fn proc_access_outcome_names() {
+        assert_eq!(ProcAccess::Ok(1).outcome(), "ok");
+        assert_eq!(
+            ProcAccess::<()>::PermissionDenied.outcome(),
+            "permission_denied"
+        );
+        assert_eq!(ProcAccess::<()>::Gone.outcome(), "gone");
+        assert_eq!(
+            ProcAccess::<()>::Fatal(io::Error::from_raw_os_error(5)).outcome(),
+            "error"
+        );
+        assert!(proc_owner_uid(std::process::id() as i32).is_some());
+        assert_eq!(proc_owner_uid(-1), None);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn socket_table_reads_once() {
+        let mut reported = Vec::new();
+        let mut table = SocketTable::prefetched(Vec::new());
+        assert!(table.is_loaded());
+        assert!(table.load(|w| reported.push(w.to_string())).is_empty());
+        assert!(reported.is_empty());
+
+        let mut lazy = SocketTable::default();
+        assert!(!lazy.is_loaded());
+        let first = lazy.load(|w| reported.push(w.to_string())).len();
+        assert!(lazy.is_loaded());
+        let reported_once = reported.len();
+        assert_eq!(lazy.load(|w| reported.push(w.to_string())).len(), first);
+        assert_eq!(reported.len(), reported_once);
+    }
 
     #[test]
-    fn parse_dev_hex_ok() {
-        assert_eq!(parse_dev_hex("08:01"), Some((8, 1)));
+    fn proc_net_sockets_keep_readable_tables() {
+        let dir = std::env::temp_dir().join(format!("zenlixem_net_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let tcp = dir.join("tcp");
+        fs::write(
+            &tcp,
+            "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode\n   \
+             0: 00000000:1F90 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 999 1 0000000000000000 100 0 0 10 0\n",
+        )
+        .unwrap();
+        // udp6 exists but cannot be read as a file; tcp6 is absent.
+        fs::create_dir_all(dir.join("udp6")).unwrap();
+
+        let tables = [
+            (ProcNetProto::Tcp, "tcp"),
+            (ProcNetProto::Tcp6, "tcp6"),
+            (ProcNetProto::Udp6, "udp6"),
+        ]
+        .into_iter()
+        .map(|(proto, name)| ProcNetTable {
+            proto,
+            entries: parse_proc_net_file(&dir.join(name), proto),
+        })
+        .collect();
+        let sockets = ProcNetSockets::from_tables(tables);
+
+        assert_eq!(sockets.entries.len(), 1);
+        assert_eq!(sockets.entries[0].inode, 999);
+        let failed: Vec<ProcNetProto> = sockets.unreadable.iter().map(|(p, _)| *p).collect();
+        assert_eq!(failed, vec![ProcNetProto::Tcp6, ProcNetProto::Udp6]);
+        assert_eq!(sockets.absent(), vec![ProcNetProto::Tcp6]);
+        assert_eq!(sockets.count(ProcNetProto::Tcp), Some(1));
+        assert_eq!(sockets.count(ProcNetProto::Tcp6), None);
+        let warnings = sockets.warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(
+            warnings[0].starts_with("udp6 socket table unreadable: "),
+            "{warnings:?}"
+        );
+        let _ = fs::remove_dir_all(&dir);
     }
 
     #[test]
-    fn parse_dev_hex_bad() {
-        assert_eq!(parse_dev_hex("zz:01"), None);
+    fn dir_links_match_own_cwd() {
+        let pid = std::process::id() as i32;
+        let cwd = std::env::current_dir().unwrap();
+        let here = FileMatch::Ids(vec![file_id_for_path(&cwd).unwrap()]);
+        assert!(matches!(
+            pid_dir_link_matches(pid, DirLink::Cwd, &here),
+            ProcAccess::Ok(true)
+        ));
+
+        let root = FileMatch::Ids(vec![file_id_for_path(Path::new("/")).unwrap()]);
+        assert!(matches!(
+            pid_dir_link_matches(pid, DirLink::Root, &root),
+            ProcAccess::Ok(true)
+        ));
+        assert!(matches!(
+            pid_dir_link_matches(-1, DirLink::Cwd, &root),
+            ProcAccess::Gone
+        ));
     }
 
     #[test]
-    fn parse_dev_hex_missing_minor() {
-        assert_eq!(parse_dev_hex("08"), None);
+    fn parse_fdinfo_flags_reads_octal() {
+        let contents = "pos:\t0\nflags:\t02102002\nmnt_id:\t29\nino:\t1234\n";
+        assert_eq!(parse_fdinfo_flags(contents), Some(0o2102002));
+        assert_eq!(parse_fdinfo_flags("pos:\t0\n"), None);
+    }
+
+    #[test]
+    fn open_flag_names_decodes_mode_and_status() {
+        assert_eq!(format_open_flags(0o0), "ro");
+        assert_eq!(format_open_flags(0o100001), "wo");
+        assert_eq!(open_flag_names(0o2102002), vec!["rw", "append", "cloexec"]);
+        assert_eq!(format_open_flags(0o4010000), "ro, sync");
+        assert_eq!(format_open_flags(0o14000), "ro, nonblock, dsync");
+        assert_eq!(access_mode(0o2), "rw");
+    }
+
+    #[test]
+    fn read_fdinfo_flags_sees_append() {
+        use std::os::fd::AsRawFd;
+
+        let path =
+            std::env::temp_dir().join(format!("zenlixem_fdinfo_test_{}.log", std::process::id()));
+        let f = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .unwrap();
+        let pid = std::process::id() as i32;
+        let flags = match read_fdinfo_flags(pid, f.as_raw_fd()) {
+            ProcAccess::Ok(flags) => flags,
+            other => panic!("unexpected result: {other:?}"),
+        };
+        fs::remove_file(&path).unwrap();
+        let names = open_flag_names(flags);
+        assert_eq!(names[0], "wo");
+        assert!(names.contains(&"append"));
+        assert!(names.contains(&"cloexec"));
+        assert!(matches!(read_fdinfo_flags(pid, i32::MAX), ProcAccess::Gone));
     }
 
     #[test]
@@ -499,9 +2943,320 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_proc_status_reads_ppid() {
+        let contents = "Name:\tbash\nState:\tS (sleeping)\nTgid:\t4242\nPid:\t4242\nPPid:\t4100\nTracerPid:\t0\n";
+        assert_eq!(parse_proc_status(contents).ppid, 4100);
+    }
+
+    #[test]
+    fn parse_proc_stat_splits_on_last_paren() {
+        let line = "4242 ((weird )name)) S 4100 4242 4100 34816 4242 4194560 1200 0 3 0 \
+                    15 7 0 0 20 0 1 0 987654 12345678 900 18446744073709551615\n";
+        assert_eq!(
+            parse_proc_stat(line),
+            Some(ProcStat {
+                state: 'S',
+                ppid: 4100,
+                starttime_ticks: 987654,
+            })
+        );
+
+        let zombie = "7 (a) b) Z 1 7 7 0 -1 4228108 0 0 0 0 0 0 0 0 20 0 1 0 42 0 0";
+        assert_eq!(
+            parse_proc_stat(zombie).map(|s| (s.state, s.ppid)),
+            Some(('Z', 1))
+        );
+
+        assert_eq!(parse_proc_stat("4242 (bash S 1"), None);
+        assert_eq!(parse_proc_stat("4242 (bash) S 1 2 3"), None);
+        assert_eq!(parse_proc_stat(""), None);
+    }
+
+    #[test]
+    fn read_stat_of_self() {
+        let me = std::process::id() as i32;
+        match read_stat(me) {
+            ProcAccess::Ok(stat) => {
+                let ProcAccess::Ok(status) = read_proc_status_access(me) else {
+                    panic!("status unreadable");
+                };
+                assert_eq!(stat.ppid, status.ppid);
+                assert!(stat.starttime_ticks > 0);
+            }
+            other => panic!("unexpected: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parse_proc_status_missing_ppid() {
+        assert_eq!(parse_proc_status("Name:\tbash\n").ppid, 0);
+    }
+
+    #[test]
+    fn parse_proc_cgroup_prefers_unified_then_systemd() {
+        assert_eq!(
+            parse_proc_cgroup("0::/system.slice/nginx.service\n").as_deref(),
+            Some("/system.slice/nginx.service")
+        );
+        let hybrid =
+            "12:cpu,cpuacct:/docker/abc\n1:name=systemd:/system.slice/docker-abc.scope\n0::/\n";
+        assert_eq!(
+            parse_proc_cgroup(hybrid).as_deref(),
+            Some("/system.slice/docker-abc.scope")
+        );
+        assert_eq!(parse_proc_cgroup("0::/\n").as_deref(), Some("/"));
+        assert_eq!(parse_proc_cgroup(""), None);
+    }
+
+    #[test]
+    fn classify_cgroup_names_containers_pods_and_units() {
+        let id = "4f2a9c1e7b3d".repeat(5) + "0a1b";
+        assert_eq!(
+            classify_cgroup(&format!("/system.slice/docker-{id}.scope")),
+            CgroupUnit::Container {
+                runtime: "docker",
+                id: "4f2a9c1e7b3d".to_string()
+            }
+        );
+        assert_eq!(classify_cgroup(&format!("/docker/{id}")).kind(), "docker");
+        assert_eq!(
+            classify_cgroup(&format!("/machine.slice/libpod-{id}.scope")).kind(),
+            "podman"
+        );
+
+        let uid = "0f1e2d3c-4b5a-6978-8a9b-0c1d2e3f4a5b";
+        let systemd_driver = format!(
+            "/kubepods.slice/kubepods-burstable.slice/kubepods-burstable-pod{}.slice/cri-containerd-{id}.scope",
+            uid.replace('-', "_")
+        );
+        assert_eq!(
+            classify_cgroup(&systemd_driver),
+            CgroupUnit::Pod(uid.to_string())
+        );
+        assert_eq!(
+            classify_cgroup(&format!("/kubepods/besteffort/pod{uid}/{id}")),
+            CgroupUnit::Pod(uid.to_string())
+        );
+
+        assert_eq!(
+            classify_cgroup("/system.slice/nginx.service"),
+            CgroupUnit::Systemd("nginx.service".to_string())
+        );
+        assert_eq!(
+            classify_cgroup("/user.slice/user-1000.slice/session-2.scope").name(),
+            Some("session-2.scope")
+        );
+        assert_eq!(classify_cgroup("/"), CgroupUnit::Unknown);
+        assert_eq!(classify_cgroup("/user.slice").name(), None);
+    }
+
+    #[test]
+    fn parse_proc_status_reads_zombie_state() {
+        let status = parse_proc_status("Name:\tdefunct\nState:\tZ (zombie)\nPPid:\t1\n");
+        assert_eq!(status.state, 'Z');
+        assert!(status.is_zombie());
+        assert!(!parse_proc_status("State:\tS (sleeping)\n").is_zombie());
+    }
+
+    #[test]
+    fn parse_proc_status_reads_cap_eff() {
+        let status = parse_proc_status("CapPrm:\t0000000000000000\nCapEff:\t0000000000200400\n");
+        assert_eq!(status.cap_eff, 0x200400);
+        assert!(status.has_capability(CAP_SYS_ADMIN));
+        assert_eq!(
+            capability_names(status.cap_eff),
+            vec!["cap_net_bind_service", "cap_sys_admin"]
+        );
+        assert_eq!(parse_proc_status("PPid:\t1\n").cap_eff, 0);
+    }
+
+    #[test]
+    fn mask_has_capability_tests_single_bits() {
+        let mask = (1 << CAP_SYS_PTRACE) | (1 << CAP_DAC_READ_SEARCH);
+        assert!(mask_has_capability(mask, CAP_SYS_PTRACE));
+        assert!(mask_has_capability(mask, CAP_DAC_READ_SEARCH));
+        assert!(!mask_has_capability(mask, CAP_SYS_ADMIN));
+        assert!(mask_has_capability(u64::MAX, 63));
+        assert!(!mask_has_capability(u64::MAX, 64));
+        assert!(!mask_has_capability(0, CAP_SYS_PTRACE));
+    }
+
+    #[test]
+    fn capability_names_full_and_unknown_bits() {
+        assert!(is_full_capability_set(0x000001ffffffffff));
+        assert!(!is_full_capability_set(0x200000));
+        assert_eq!(capability_names(1 << 41), vec!["cap_41"]);
+        assert!(capability_names(0).is_empty());
+    }
+
+    #[test]
+    fn parse_smaps_rollup_fields() {
+        let contents = "55d0c6a00000-7ffd5ca02000 ---p 00000000 00:00 0                          [rollup]\nRss:               12288 kB\nPss:                3072 kB\nPss_Anon:           1024 kB\nShared_Clean:       8192 kB\nShared_Dirty:         64 kB\nPrivate_Clean:      2048 kB\n";
+        assert_eq!(
+            parse_smaps_rollup(contents),
+            SmapsRollup {
+                rss_kb: 12288,
+                pss_kb: 3072,
+                shared_clean_kb: 8192,
+                shared_dirty_kb: 64,
+            }
+        );
+    }
+
+    #[test]
+    fn read_smaps_rollup_nonexistent_pid_is_gone() {
+        assert!(matches!(read_smaps_rollup(-1), ProcAccess::Gone));
+    }
+
+    #[test]
+    fn parent_chain_of_self_reaches_init() {
+        let chain = parent_chain(std::process::id() as i32);
+        assert!(!chain.is_empty());
+        assert!(!chain.contains(&(std::process::id() as i32)));
+    }
+
+    #[test]
+    fn parent_chain_of_nonexistent_pid_is_empty() {
+        assert!(parent_chain(-1).is_empty());
+    }
+
     #[test]
     fn read_comm_best_effort_on_nonexistent_pid() {
-        assert_eq!(read_comm_best_effort(-1), "<unknown>");
+        assert_eq!(read_comm_best_effort(-1), Comm::unknown());
+    }
+
+    #[test]
+    fn normalize_comm_fills_empty_names() {
+        assert_eq!(normalize_comm("bash\n"), "bash");
+        assert_eq!(normalize_comm("kworker/0:1 \n"), "kworker/0:1 ");
+        assert_eq!(normalize_comm(""), EMPTY_COMM_LABEL);
+        assert_eq!(normalize_comm("\n"), EMPTY_COMM_LABEL);
+        assert_eq!(normalize_comm("  \t\n"), EMPTY_COMM_LABEL);
+    }
+
+    #[test]
+    fn proc_snapshot_captures_comm_or_gone() {
+        let pid = std::process::id() as i32;
+        match ProcSnapshot::capture(pid) {
+            ProcAccess::Ok(snap) => {
+                assert_eq!(snap.pid, pid);
+                assert!(snap.comm.get().is_some());
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+        assert!(matches!(ProcSnapshot::capture(-1), ProcAccess::Gone));
+    }
+
+    #[test]
+    fn full_command_name_only_completes_truncated_comm() {
+        assert_eq!(
+            full_command_name("systemd-journal", "/usr/lib/systemd/systemd-journald").as_deref(),
+            Some("systemd-journald")
+        );
+        assert_eq!(full_command_name("bash", "/usr/bin/bash-static"), None);
+        assert_eq!(
+            full_command_name("sshd-session-xy", "sshd: alice@pts/0"),
+            None
+        );
+        assert_eq!(
+            full_command_name("systemd-journal", "systemd-journal"),
+            None
+        );
+    }
+
+    #[test]
+    fn parse_cmdline_keeps_empty_arguments() {
+        assert_eq!(parse_cmdline(b""), Vec::<String>::new());
+        assert_eq!(
+            parse_cmdline(b"nginx\0-g\0daemon off;\0"),
+            ["nginx", "-g", "daemon off;"]
+        );
+        assert_eq!(parse_cmdline(b"prog\0\0x\0"), ["prog", "", "x"]);
+        // Processes that rewrite argv may drop the terminating NUL.
+        assert_eq!(parse_cmdline(b"sshd: alice"), ["sshd: alice"]);
+    }
+
+    #[test]
+    fn read_cmdline_of_self_has_argv0() {
+        let pid = std::process::id() as i32;
+        match read_cmdline_access(pid) {
+            ProcAccess::Ok(argv) => assert!(!argv.is_empty()),
+            other => panic!("unexpected result: {other:?}"),
+        }
+        assert!(matches!(read_cmdline_access(-1), ProcAccess::Gone));
+        assert!(!read_cmdline(pid).unwrap().is_empty());
+    }
+
+    #[test]
+    fn comm_displays_label_but_serializes_null() {
+        let unknown = Comm::unknown();
+        assert_eq!(unknown.as_str(), DEFAULT_UNKNOWN_LABEL);
+        assert_eq!(format!("[{unknown:<10}]"), "[<unknown> ]");
+
+        let literal = Comm::known("<unknown>");
+        assert_ne!(literal, unknown);
+        assert_eq!(literal.get(), Some("<unknown>"));
+        assert_eq!(&*Comm::known("nginx"), "nginx");
+    }
+
+    #[test]
+    fn read_exe_is_own_binary() {
+        let pid = std::process::id() as i32;
+        let exe = std::env::current_exe().unwrap();
+        assert!(matches!(read_exe(pid), ProcAccess::Ok(p) if p == exe));
+        assert_eq!(read_exe_best_effort(pid), Some(exe.display().to_string()));
+        assert!(matches!(read_exe(-1), ProcAccess::Gone));
+        assert_eq!(read_exe_best_effort(-1), None);
+    }
+
+    #[test]
+    fn ns_links_parse_and_match_own_process() {
+        assert_eq!(parse_ns_link("mnt:[4026531841]", "mnt"), Some(4026531841));
+        assert_eq!(parse_ns_link("net:[4026531840]", "mnt"), None);
+        assert_eq!(parse_ns_link("pid:[oops]", "pid"), None);
+
+        let pid = std::process::id() as i32;
+        let ProcAccess::Ok(own) = read_ns_ids(pid) else {
+            panic!("cannot read own namespaces");
+        };
+        assert!(own.mnt != 0 && own.net != 0 && own.pid != 0);
+        assert!(own.same_mnt_net(&own));
+        let elsewhere = NsIds {
+            net: own.net + 1,
+            ..own
+        };
+        assert!(!own.same_mnt_net(&elsewhere));
+        assert!(matches!(read_ns_ids(-1), ProcAccess::Gone));
+    }
+
+    #[test]
+    fn exe_match_finds_own_binary() {
+        let pid = std::process::id() as i32;
+        let exe = std::env::current_exe().unwrap();
+        let by_id = FileMatch::Ids(vec![file_id_for_path(&exe).unwrap()]);
+        assert!(matches!(
+            pid_exe_match(pid, &by_id),
+            ProcAccess::Ok(Some(false))
+        ));
+
+        let other = FileMatch::Ids(vec![file_id_for_path(Path::new("/")).unwrap()]);
+        assert!(matches!(pid_exe_match(pid, &other), ProcAccess::Ok(None)));
+        assert!(matches!(pid_exe_match(-1, &by_id), ProcAccess::Gone));
+    }
+
+    #[test]
+    fn task_comms_include_the_main_thread() {
+        let pid = std::process::id() as i32;
+        let tasks = task_comms_best_effort(pid);
+        let main = tasks.iter().find(|(tid, _)| *tid == pid).unwrap();
+        assert_eq!(
+            list_tids(pid).unwrap(),
+            tasks.iter().map(|(tid, _)| *tid).collect::<Vec<_>>()
+        );
+        assert!(list_tids(-1).is_err());
+        assert!(matches!(read_comm_access(pid), ProcAccess::Ok(c) if c == main.1));
+        assert!(task_comms_best_effort(-1).is_empty());
     }
 
     #[test]
@@ -526,11 +3281,42 @@ mod tests {
         assert_eq!(parse_socket_inode("socket:[abc]"), None);
     }
 
+    #[test]
+    fn parse_socket_inode_other_links() {
+        assert_eq!(parse_socket_inode("anon_inode:[eventfd]"), None);
+        assert_eq!(parse_socket_inode("pipe:[12345]"), None);
+        assert_eq!(parse_socket_inode("/tmp/socket:[12345]"), None);
+        assert_eq!(
+            classify_fd_link("socket:[12345]"),
+            FdKind::Socket(parse_socket_inode("socket:[12345]").unwrap())
+        );
+    }
+
     #[test]
     fn parse_socket_inode_empty() {
         assert_eq!(parse_socket_inode(""), None);
     }
 
+    #[test]
+    fn socket_inode_index_finds_own_listener() {
+        use std::os::fd::AsRawFd;
+        use std::os::unix::fs::MetadataExt;
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let pid = std::process::id() as i32;
+        let link = format!("/proc/self/fd/{}", listener.as_raw_fd());
+        let inode = fs::metadata(link).unwrap().ino();
+
+        match read_socket_inodes(pid) {
+            ProcAccess::Ok(inodes) => assert!(inodes.contains(&inode), "{inodes:?}"),
+            other => panic!("unexpected: {other:?}"),
+        }
+        let index = build_socket_inode_index().unwrap();
+        assert!(index.pids(inode).contains(&pid));
+        assert!(index.pids(u64::MAX).is_empty());
+        assert!(!index.denied.contains(&pid));
+    }
+
     #[test]
     fn proto_label_tcp() {
         assert_eq!(proto_label(ProcNetProto::Tcp), "tcp");
@@ -571,9 +3357,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn socket_state_label_tcp_closing_states() {
+        assert_eq!(
+            socket_state_label(ProcNetProto::Tcp, TCP_TIME_WAIT),
+            "time_wait"
+        );
+        assert_eq!(socket_state_label(ProcNetProto::Tcp6, 0x08), "close_wait");
+        assert_eq!(tcp_state_label(0x02), Some("syn_sent"));
+        assert_eq!(tcp_state_label(0x0B), Some("closing"));
+    }
+
     #[test]
     fn socket_state_label_tcp_unknown() {
-        assert_eq!(socket_state_label(ProcNetProto::Tcp, 0x06), "0x06");
+        assert_eq!(socket_state_label(ProcNetProto::Tcp, 0x20), "0x20");
+        assert_eq!(tcp_state_label(0x00), None);
     }
 
     #[test]
@@ -584,9 +3382,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn socket_state_label_udp_connected() {
+        assert_eq!(
+            socket_state_label(ProcNetProto::Udp, UDP_ESTABLISHED),
+            "connected"
+        );
+        assert_eq!(
+            socket_state_label(ProcNetProto::Udp6, UDP_ESTABLISHED),
+            "connected"
+        );
+    }
+
     #[test]
     fn socket_state_label_udp_unknown() {
-        assert_eq!(socket_state_label(ProcNetProto::Udp, 0x01), "0x01");
+        assert_eq!(socket_state_label(ProcNetProto::Udp, 0x02), "0x02");
     }
 
     #[test]
@@ -605,13 +3415,174 @@ mod tests {
 
         let v = parse_proc_net_file(&path, ProcNetProto::Tcp).unwrap();
         assert_eq!(v.len(), 1);
+        assert_eq!(v[0].local_addr, IpAddr::V4(Ipv4Addr::LOCALHOST));
         assert_eq!(v[0].local_port, 53);
         assert_eq!(v[0].inode, 46743);
         assert_eq!(v[0].state, 0x0A);
+        assert_eq!(v[0].timer, 0);
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn parse_socket_timer_column() {
+        assert_eq!(parse_socket_timer("02:000AFC80"), Some((2, 0xAFC80)));
+        assert_eq!(parse_socket_timer("00:00000000"), Some((0, 0)));
+        assert_eq!(parse_socket_timer("zz"), None);
+        assert_eq!(parse_socket_queues("00000080:00000003"), Some((128, 3)));
+        assert_eq!(socket_timer_name(2), Some("keepalive"));
+        assert_eq!(socket_timer_name(0), None);
+    }
+
+    #[test]
+    fn parse_proc_net_file_reads_connected_udp() {
+        let path = std::env::temp_dir().join(format!(
+            "zenlixem_proc_net_udp_test_{}_{}.txt",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+
+        // A resolver client: 127.0.0.1:40000 connected to 127.0.0.53:53.
+        let contents = "   sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode ref pointer drops\n  1234: 0100007F:9C40 3500007F:0035 01 00000000:00000000 00:00000000 00000000  1000        0 51234 2 0000000000000000 0\n";
+        fs::write(&path, contents).unwrap();
+
+        let v = parse_proc_net_file(&path, ProcNetProto::Udp).unwrap();
+        assert_eq!(v.len(), 1);
+        assert_eq!(v[0].local_port, 40000);
+        assert_eq!(v[0].remote_addr, IpAddr::V4(Ipv4Addr::new(127, 0, 0, 53)));
+        assert_eq!(v[0].remote_port, 53);
+        assert_eq!(v[0].inode, 51234);
+        assert_eq!(v[0].state, UDP_ESTABLISHED);
+        assert_eq!(socket_state_label(v[0].proto, v[0].state), "connected");
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn parse_proc_net_file_reads_tcp6_peer() {
+        let path = std::env::temp_dir().join(format!(
+            "zenlixem_proc_net_tcp6_test_{}_{}.txt",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+
+        // [::1]:8443 connected to [2001:db8::1]:51000, words in native order.
+        let words = |addr: Ipv6Addr| {
+            addr.octets()
+                .chunks_exact(4)
+                .map(|c| format!("{:08X}", u32::from_ne_bytes([c[0], c[1], c[2], c[3]])))
+                .collect::<String>()
+        };
+        let peer: Ipv6Addr = "2001:db8::1".parse().unwrap();
+        let contents = format!(
+            "  sl  local_address                         remote_address                        st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: {}:20FB {}:C738 01 00000000:00000000 02:00000A8C 00000000  1000        0 77001 1 0000000000000000 20 4 30 10 -1
+",
+            words(Ipv6Addr::LOCALHOST),
+            words(peer)
+        );
+        fs::write(&path, contents).unwrap();
+
+        let v = parse_proc_net_file(&path, ProcNetProto::Tcp6).unwrap();
+        assert_eq!(v.len(), 1);
+        assert_eq!(v[0].local_addr, IpAddr::V6(Ipv6Addr::LOCALHOST));
+        assert_eq!(v[0].local_port, 8443);
+        assert_eq!(v[0].remote_addr, IpAddr::V6(peer));
+        assert_eq!(v[0].remote_port, 51000);
+        assert_eq!(v[0].inode, 77001);
 
         let _ = fs::remove_file(&path);
     }
 
+    #[test]
+    fn parse_proc_net_unix_reads_paths_and_abstract_names() {
+        let contents = "Num       RefCount Protocol Flags    Type St Inode Path\n\
+            0000000000000000: 00000002 00000000 00010000 0001 01 23456 /run/docker.sock\n\
+            0000000000000000: 00000003 00000000 00000000 0001 03 23457\n\
+            0000000000000000: 00000002 00000000 00010000 0001 01 23458 @/tmp/.X11-unix/X0\n\
+            0000000000000000: 00000002 00000000 00000000 0002 01 23459 /run/my app.sock\n\
+            garbage line\n";
+
+        let v = parse_proc_net_unix(contents);
+        assert_eq!(v.len(), 4);
+        assert_eq!(
+            v[0],
+            ProcNetUnixEntry {
+                inode: 23456,
+                path: Some("/run/docker.sock".to_string()),
+                state: 1,
+                kind: 1,
+            }
+        );
+        assert_eq!(v[1].path, None);
+        assert_eq!(v[1].state, 3);
+        assert_eq!(v[2].path.as_deref(), Some("@/tmp/.X11-unix/X0"));
+        assert_eq!(v[3].path.as_deref(), Some("/run/my app.sock"));
+        assert_eq!(v[3].kind, 2);
+    }
+
+    #[test]
+    fn parse_hex_ip_v4_and_v6() {
+        let v4 = u32::from_ne_bytes([192, 168, 1, 10]);
+        assert_eq!(
+            parse_hex_ip(&format!("{v4:08X}")),
+            Some(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 10)))
+        );
+
+        let loopback6: String = Ipv6Addr::LOCALHOST
+            .octets()
+            .chunks_exact(4)
+            .map(|c| format!("{:08X}", u32::from_ne_bytes([c[0], c[1], c[2], c[3]])))
+            .collect();
+        assert_eq!(
+            parse_hex_ip(&loopback6),
+            Some(IpAddr::V6(Ipv6Addr::LOCALHOST))
+        );
+
+        assert_eq!(parse_hex_ip("0100"), None);
+        assert_eq!(parse_hex_ip("ZZZZZZZZ"), None);
+    }
+
+    #[test]
+    fn interface_index_matches_routes_and_v6() {
+        let net = u32::from_ne_bytes([192, 168, 1, 0]);
+        let mask24 = u32::from_ne_bytes([255, 255, 255, 0]);
+        let net10 = u32::from_ne_bytes([10, 0, 0, 0]);
+        let mask8 = u32::from_ne_bytes([255, 0, 0, 0]);
+        let route = format!(
+            "Iface\tDestination\tGateway\tFlags\tRefCnt\tUse\tMetric\tMask\tMTU\tWindow\tIRTT\n\
+             eth0\t00000000\t0101A8C0\t0003\t0\t0\t100\t00000000\t0\t0\t0\n\
+             eth0\t{net:08X}\t00000000\t0001\t0\t0\t100\t{mask24:08X}\t0\t0\t0\n\
+             wg0\t{net10:08X}\t00000000\t0001\t0\t0\t0\t{mask8:08X}\t0\t0\t0\n"
+        );
+        let inet6 = "fe800000000000000a0027fffe4e6f1a 02 40 20 80     eth0\n\
+                     00000000000000000000000000000001 01 80 10 80       lo\n";
+
+        let index = InterfaceIndex {
+            v4_routes: parse_proc_net_route(&route),
+            v6_addrs: parse_if_inet6(inet6),
+        };
+
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+        assert_eq!(index.interface_for(ip("192.168.1.10")), Some("eth0"));
+        assert_eq!(index.interface_for(ip("10.1.2.3")), Some("wg0"));
+        assert_eq!(index.interface_for(ip("172.16.0.1")), None);
+        assert_eq!(index.interface_for(ip("127.0.0.53")), Some("lo"));
+        assert_eq!(index.interface_for(ip("0.0.0.0")), None);
+        assert_eq!(index.interface_for(ip("::")), None);
+        assert_eq!(
+            index.interface_for(ip("fe80::a00:27ff:fe4e:6f1a")),
+            Some("eth0")
+        );
+        assert_eq!(index.interface_for(ip("::ffff:192.168.1.10")), Some("eth0"));
+    }
+
     #[test]
     fn parse_proc_net_file_empty() {
         let path = std::env::temp_dir().join(format!(
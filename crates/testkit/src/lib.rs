@@ -0,0 +1,85 @@
+//! Shared helpers for the tools' JSON contract tests: a synthetic proc
+//! tree to point `--proc-root` at, and running a binary for its JSON.
+
+use serde_json::Value;
+use std::fs;
+use std::os::unix::fs::symlink;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// The process every fixture tree contains.
+pub const FIXTURE_PID: i32 = 4242;
+
+/// Column header line of `/proc/net/{tcp,tcp6,udp,udp6}`.
+pub const NET_HEADER: &str = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode\n";
+
+/// A fresh proc tree under the temp dir with [`FIXTURE_PID`] (`fixture`)
+/// holding `/srv/data.db` (fd 3 and a mapping) and socket inode 999
+/// (fd 4). `tcp_rows` follow the header of `net/tcp`; the other socket
+/// tables are empty. Callers add the rest (exe, cwd, fdinfo, ...).
+pub fn proc_tree(name: &str, tcp_rows: &str) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("zenlixem_json_{name}_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let pid = root.join(FIXTURE_PID.to_string());
+    fs::create_dir_all(pid.join("fd")).unwrap();
+    fs::write(pid.join("comm"), "fixture\n").unwrap();
+    fs::write(
+        pid.join("maps"),
+        "7f0000000000-7f0000001000 r--p 00000000 08:01 1234 /srv/data.db\n",
+    )
+    .unwrap();
+    symlink("/srv/data.db", pid.join("fd/3")).unwrap();
+    symlink("socket:[999]", pid.join("fd/4")).unwrap();
+
+    fs::create_dir_all(root.join("net")).unwrap();
+    fs::write(root.join("net/tcp"), format!("{NET_HEADER}{tcp_rows}")).unwrap();
+    for table in ["tcp6", "udp", "udp6"] {
+        fs::write(root.join("net").join(table), NET_HEADER).unwrap();
+    }
+    root
+}
+
+/// Run `bin` against the proc tree at `root`.
+pub fn run_proc(bin: &str, root: &Path, args: &[&str]) -> Output {
+    Command::new(bin)
+        .arg("--proc-root")
+        .arg(root)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// Run `bin --json` against the proc tree at `root`; the run must succeed.
+pub fn run_proc_json(bin: &str, root: &Path, args: &[&str]) -> Value {
+    let out = Command::new(bin)
+        .arg("--proc-root")
+        .arg(root)
+        .arg("--json")
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{args:?}: {out:?}");
+    stdout_json(&out)
+}
+
+/// Stdout of a run, parsed as one JSON document.
+pub fn stdout_json(out: &Output) -> Value {
+    serde_json::from_slice(&out.stdout).unwrap_or_else(|e| panic!("{e}: {out:?}"))
+}
+
+/// The fields every envelope carries, with their types.
+pub fn assert_base_envelope(v: &Value, mode: &str) {
+    assert!(v["privilege"].is_string(), "{v}");
+    assert!(v["mode_message"].is_string(), "{v}");
+    assert_eq!(v["mode"], mode, "{v}");
+}
+
+/// The fields every process-scan envelope adds, with their types.
+pub fn assert_scan_envelope(v: &Value, mode: &str) {
+    assert_base_envelope(v, mode);
+    assert!(v["partial"].is_boolean(), "{v}");
+    assert!(v["privilege_insufficient"].is_boolean(), "{v}");
+    assert!(v["skipped"].is_u64(), "{v}");
+    assert!(v["errored"].is_u64(), "{v}");
+    assert!(v["distinct_commands"].is_array(), "{v}");
+}
@@ -1,17 +1,20 @@
 // Copyright (c) 2026 rezky_nightky
 
 use clap::{error::ErrorKind, Parser};
+use serde::Serialize;
 use serde_json::json;
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::ffi::OsStr;
 use std::fs;
 use std::os::unix::ffi::OsStrExt;
-use std::os::unix::fs::PermissionsExt;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
 use std::path::{Path, PathBuf};
 
 use cliutil::{
-    error, print_header, print_info, print_json_error, print_json_payload, print_version,
-    privilege_mode, privilege_mode_message, AppError,
+    current_credentials, exit_with_error, print_header, print_info, print_json_envelope,
+    print_json_error, print_version, privilege_mode, privilege_mode_message, set_color_choice,
+    validate_fields, warn, AppError, ColorChoice, Credentials, ExitCode, JsonEnvelope,
 };
 
 #[derive(Parser, Debug)]
@@ -22,6 +25,8 @@ use cliutil::{
     long_about = "envpath shows which PATH entry selected the resolved executable for a bare command name.\n\nCOMMAND must be a bare command name (no '/' characters).",
     after_help = r#"EXAMPLES:
   envpath gcc
+  envpath --dedup gcc
+  envpath --all python3
   envpath --json gcc
 "#
 )]
@@ -43,6 +48,56 @@ struct Args {
     )]
     json: bool,
 
+    #[arg(
+        long = "color",
+        value_enum,
+        value_name = "WHEN",
+        default_value = "auto",
+        help = "Color warnings, errors and headers: auto (terminal, unless NO_COLOR is set), always, or never"
+    )]
+    color: ColorChoice,
+
+    #[arg(
+        long = "include-host",
+        requires = "json",
+        help = "Add a host block (hostname, boot_id) to JSON output"
+    )]
+    include_host: bool,
+
+    #[arg(
+        long = "no-mode-message",
+        requires = "json",
+        help = "Omit the human-oriented mode_message field from JSON output"
+    )]
+    no_mode_message: bool,
+
+    #[arg(
+        long = "fields",
+        value_name = "LIST",
+        value_delimiter = ',',
+        requires = "json",
+        help = "Only include these comma-separated result fields in JSON output"
+    )]
+    fields: Vec<String>,
+
+    #[arg(
+        long = "dedup",
+        help = "Collapse repeated PATH entries, keeping the first occurrence"
+    )]
+    dedup: bool,
+
+    #[arg(
+        long = "all",
+        help = "List every PATH entry holding COMMAND, marking the ones the first match shadows"
+    )]
+    all: bool,
+
+    #[arg(
+        long = "suggest-path",
+        help = "If COMMAND is not found, look in standard bin directories and suggest a PATH fix"
+    )]
+    suggest_path: bool,
+
     #[arg(
         value_name = "COMMAND",
         required_unless_present_any = ["version", "info"],
@@ -61,7 +116,219 @@ fn is_executable(path: &Path) -> bool {
         return false;
     }
 
-    (md.permissions().mode() & 0o111) != 0
+    exec_permitted(
+        md.permissions().mode(),
+        md.uid(),
+        md.gid(),
+        current_credentials(),
+    )
+}
+
+/// Whether `creds` may execute a file with `mode` owned by `uid:gid`, using
+/// only the owner, group or other bit that applies (as the kernel does).
+/// Root needs any exec bit. Without credentials, any exec bit counts.
+fn exec_permitted(mode: u32, uid: u32, gid: u32, creds: Option<&Credentials>) -> bool {
+    let Some(creds) = creds else {
+        return mode & 0o111 != 0;
+    };
+    if creds.uid == 0 {
+        return mode & 0o111 != 0;
+    }
+    if creds.uid == uid {
+        mode & 0o100 != 0
+    } else if creds.in_group(gid) {
+        mode & 0o010 != 0
+    } else {
+        mode & 0o001 != 0
+    }
+}
+
+/// Split a PATH value into entries, keeping empty ones.
+///
+/// An empty entry means the current directory to execvp(3), exactly like
+/// `.`, so dropping it would misreport resolution.
+fn split_path_var(path_var: &OsStr) -> Vec<PathBuf> {
+    path_var
+        .as_bytes()
+        .split(|&b| b == b':')
+        .map(|part| PathBuf::from(OsStr::from_bytes(part)))
+        .collect()
+}
+
+/// For a relative PATH entry (`.`, empty, `bin`, ...), the directory it
+/// resolves to from `cwd`; `None` for absolute entries.
+fn relative_resolution(dir: &Path, cwd: &Path) -> Option<PathBuf> {
+    if dir.is_absolute() {
+        return None;
+    }
+    Some(cwd.join(dir).components().collect())
+}
+
+/// Filesystem facts about one PATH entry, for the JSON `path_order`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct EntryStatus {
+    exists: bool,
+    is_dir: bool,
+    /// An executable file named after the command lives here (what
+    /// resolution looks for, so the first such entry is the selected one).
+    contains_command: bool,
+}
+
+fn entry_status(dir: &Path, command: &str) -> EntryStatus {
+    // An empty entry is the current directory (see `split_path_var`).
+    let dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    let md = fs::metadata(dir);
+    EntryStatus {
+        exists: md.is_ok(),
+        is_dir: md.is_ok_and(|m| m.is_dir()),
+        contains_command: is_executable(&dir.join(command)),
+    }
+}
+
+/// PATH entries holding an executable `command`, in PATH order, as
+/// `(index, candidate)`. Stops at the first (selected) one unless `all`.
+fn find_matches(path_entries: &[PathBuf], command: &str, all: bool) -> Vec<(usize, PathBuf)> {
+    let mut out = Vec::new();
+    for (idx, dir) in path_entries.iter().enumerate() {
+        let candidate = dir.join(command);
+        if is_executable(&candidate) {
+            out.push((idx, candidate));
+            if !all {
+                break;
+            }
+        }
+    }
+    out
+}
+
+/// `--all` marker for the `i`-th match: the first wins, the rest are shadowed.
+fn match_marker(i: usize) -> &'static str {
+    if i == 0 {
+        "<- selected (first match)"
+    } else {
+        "<- shadowed"
+    }
+}
+
+fn display_entry(dir: &Path) -> String {
+    if dir.as_os_str().is_empty() {
+        "(empty)".to_string()
+    } else {
+        dir.display().to_string()
+    }
+}
+
+/// The directory a PATH entry names once symlinks, `.`/`..` and relative
+/// entries are resolved. Entries that cannot be canonicalized (missing
+/// directories) are compared as written.
+fn duplicate_key(dir: &Path) -> PathBuf {
+    let dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf())
+}
+
+/// For each PATH entry, whether an earlier entry has the same
+/// [`duplicate_key`].
+fn duplicate_flags(entries: &[PathBuf]) -> Vec<bool> {
+    let mut seen: HashSet<PathBuf> = HashSet::new();
+    entries
+        .iter()
+        .map(|dir| !seen.insert(duplicate_key(dir)))
+        .collect()
+}
+
+/// Group PATH entries by [`duplicate_key`] for `--dedup`, so the groups
+/// agree with [`duplicate_flags`].
+///
+/// Returns `(first_index, later_indices)` per distinct directory, in order
+/// of first occurrence. Indices are 0-based positions in `entries`.
+fn dedup_entries(entries: &[PathBuf]) -> Vec<(usize, Vec<usize>)> {
+    let mut out: Vec<(usize, Vec<usize>)> = Vec::new();
+    let mut first_of: HashMap<PathBuf, usize> = HashMap::new();
+
+    for (idx, dir) in entries.iter().enumerate() {
+        let key = duplicate_key(dir);
+        match first_of.get(&key) {
+            Some(&slot) => out[slot].1.push(idx),
+            None => {
+                first_of.insert(key, out.len());
+                out.push((idx, Vec::new()));
+            }
+        }
+    }
+
+    out
+}
+
+/// Result keys accepted by `--fields`.
+const RESULT_FIELDS: &[&str] = &["resolved", "path_order", "relative_entries", "suggestion"];
+
+/// System directories `--suggest-path` probes, in the usual PATH order.
+/// Kept to well-known locations so the suggestion never points somewhere
+/// surprising.
+const SUGGEST_SYSTEM_DIRS: &[&str] = &[
+    "/usr/local/sbin",
+    "/usr/local/bin",
+    "/usr/sbin",
+    "/usr/bin",
+    "/sbin",
+    "/bin",
+];
+
+/// Per-user directories `--suggest-path` probes, relative to `$HOME`.
+const SUGGEST_HOME_DIRS: &[&str] = &[".local/bin", "bin"];
+
+/// A directory outside PATH holding the command, and how to add it.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+struct PathSuggestion {
+    dir: String,
+    resolved: String,
+    /// Shell line appending `dir` to PATH, e.g. `export PATH="$PATH:/usr/local/bin"`.
+    export: String,
+}
+
+/// First standard directory not already in PATH that holds an executable
+/// `command`.
+fn suggest_path_fix(
+    command: &str,
+    path_entries: &[PathBuf],
+    home: Option<&Path>,
+) -> Option<PathSuggestion> {
+    let mut candidates: Vec<PathBuf> = SUGGEST_SYSTEM_DIRS.iter().map(PathBuf::from).collect();
+    if let Some(home) = home.filter(|h| h.is_absolute()) {
+        candidates.extend(SUGGEST_HOME_DIRS.iter().map(|d| home.join(d)));
+    }
+
+    candidates
+        .into_iter()
+        .filter(|dir| !path_entries.contains(dir))
+        .find_map(|dir| {
+            let candidate = dir.join(command);
+            is_executable(&candidate).then(|| PathSuggestion {
+                export: export_path_line(&dir),
+                dir: dir.display().to_string(),
+                resolved: candidate.display().to_string(),
+            })
+        })
+}
+
+/// `export PATH="$PATH:<dir>"`, escaped for double quotes.
+fn export_path_line(dir: &Path) -> String {
+    let mut quoted = String::new();
+    for c in dir.display().to_string().chars() {
+        if matches!(c, '"' | '$' | '`' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    format!("export PATH=\"$PATH:{quoted}\"")
 }
 
 fn main() {
@@ -72,49 +339,40 @@ fn main() {
         Err(e) => {
             if matches!(e.kind(), ErrorKind::DisplayHelp | ErrorKind::DisplayVersion) {
                 let _ = e.print();
-                std::process::exit(0);
+                ExitCode::Success.exit();
             }
             if json_requested {
                 print_json_error(AppError::InvalidInput(e.to_string()));
             } else {
                 let _ = e.print();
             }
-            std::process::exit(1);
+            ExitCode::InvalidInput.exit();
         }
     };
 
     match run(args) {
-        Ok(()) => {}
-        Err(AppError::InvalidInput(e)) => {
-            if json_requested {
-                print_json_error(AppError::InvalidInput(e));
-            } else {
-                error(&e);
-            }
-            std::process::exit(1);
-        }
-        Err(AppError::Fatal(e)) => {
-            if json_requested {
-                print_json_error(AppError::Fatal(e));
-            } else {
-                error(&e);
-            }
-            std::process::exit(2);
-        }
+        Ok(code) => code.exit(),
+        Err(e) => exit_with_error(e, json_requested),
     }
 }
 
-fn run(args: Args) -> Result<(), AppError> {
+/// `Success` when the command resolves; `InvalidInput` when it only
+/// resolves through a `--suggest-path` suggestion (it is still not found).
+fn run(args: Args) -> Result<ExitCode, AppError> {
+    set_color_choice(args.color);
+
     if args.version {
         print_version();
-        return Ok(());
+        return Ok(ExitCode::Success);
     }
 
     if args.info {
         print_info();
-        return Ok(());
+        return Ok(ExitCode::Success);
     }
 
+    validate_fields(&args.fields, RESULT_FIELDS)?;
+
     let command = args
         .command
         .ok_or_else(|| AppError::InvalidInput("missing command".to_string()))?;
@@ -127,47 +385,77 @@ fn run(args: Args) -> Result<(), AppError> {
 
     let path_var = env::var_os("PATH").unwrap_or_default();
 
-    let mut path_entries: Vec<PathBuf> = Vec::new();
-    for part in path_var.as_bytes().split(|&b| b == b':') {
-        if part.is_empty() {
-            continue;
-        }
-        path_entries.push(PathBuf::from(OsStr::from_bytes(part)));
-    }
-
-    if path_entries.is_empty() {
+    if path_var.is_empty() {
         return Err(AppError::Fatal("PATH is empty or unset".to_string()));
     }
+    let path_entries = split_path_var(&path_var);
+
+    // Relative entries resolve against the CWD: a classic hijack vector.
+    let cwd = env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    let relative: Vec<Option<PathBuf>> = path_entries
+        .iter()
+        .map(|dir| relative_resolution(dir, &cwd))
+        .collect();
+    let relative_count = relative.iter().filter(|r| r.is_some()).count();
+    let duplicate = duplicate_flags(&path_entries);
+
+    let matches = find_matches(&path_entries, &command, args.all);
+    let selected_index = matches.first().map(|(idx, _)| *idx);
+    let resolved = matches.first().map(|(_, p)| p.clone());
+
+    let suggestion = if resolved.is_none() && args.suggest_path {
+        let home = env::var_os("HOME").map(PathBuf::from);
+        suggest_path_fix(&command, &path_entries, home.as_deref())
+    } else {
+        None
+    };
 
-    let mut resolved: Option<PathBuf> = None;
-    let mut selected_index: Option<usize> = None;
-
-    for (idx, dir) in path_entries.iter().enumerate() {
-        let candidate = dir.join(&command);
-        if is_executable(&candidate) {
-            resolved = Some(candidate);
-            selected_index = Some(idx);
-            break;
-        }
-    }
-
-    if resolved.is_none() {
+    if resolved.is_none() && suggestion.is_none() {
         return Err(AppError::InvalidInput(
             "command not found in PATH".to_string(),
         ));
     }
+    let code = if resolved.is_some() {
+        ExitCode::Success
+    } else {
+        ExitCode::InvalidInput
+    };
+
+    let groups: Vec<(usize, Vec<usize>)> = if args.dedup {
+        dedup_entries(&path_entries)
+    } else {
+        (0..path_entries.len())
+            .map(|idx| (idx, Vec::new()))
+            .collect()
+    };
 
     if args.json {
         let mut order: Vec<serde_json::Value> = Vec::new();
-        for (idx, dir) in path_entries.iter().enumerate() {
-            order.push(json!({
+        for (idx, dups) in &groups {
+            let status = entry_status(&path_entries[*idx], &command);
+            let mut entry = json!({
                 "index": idx + 1,
-                "dir": dir.display().to_string(),
-                "selected": Some(idx) == selected_index,
-            }));
+                "dir": path_entries[*idx].display().to_string(),
+                "selected": Some(*idx) == selected_index,
+                "relative": relative[*idx].is_some(),
+                "exists": status.exists,
+                "duplicate": duplicate[*idx],
+                "is_dir": status.is_dir,
+                "contains_command": status.contains_command,
+            });
+            if let Some(abs) = &relative[*idx] {
+                entry["resolves_to"] = json!(abs.display().to_string());
+            }
+            if args.dedup {
+                entry["duplicate_count"] = json!(dups.len());
+            }
+            if args.all {
+                entry["match"] = json!(matches.iter().any(|(m, _)| m == idx));
+            }
+            order.push(entry);
         }
 
-        let payload = json!({
+        let mut payload = json!({
             "privilege": privilege_mode(),
             "mode_message": privilege_mode_message(),
             "mode": "envpath",
@@ -177,14 +465,31 @@ fn run(args: Args) -> Result<(), AppError> {
             "results": {
                 "resolved": resolved.as_ref().map(|p| p.display().to_string()),
                 "path_order": order,
+                "relative_entries": relative_count,
             }
         });
 
-        print_json_payload(&payload);
-        return Ok(());
+        let envelope = JsonEnvelope {
+            include_host: args.include_host,
+            omit_mode_message: args.no_mode_message,
+            fields: args.fields.clone(),
+            ..JsonEnvelope::default()
+        };
+        if args.suggest_path {
+            payload["results"]["suggestion"] = json!(suggestion);
+        }
+        print_json_envelope(payload, &envelope);
+        return Ok(code);
     }
 
     println!("{}", privilege_mode_message());
+    if relative_count > 0 {
+        warn(&format!(
+            "PATH has {relative_count} relative entr{} (., empty or relative dirs); \
+             they resolve against the current directory, which is a security risk",
+            if relative_count == 1 { "y" } else { "ies" }
+        ));
+    }
     println!("Command: {}", command);
     println!();
     print_header("Resolved to:");
@@ -192,25 +497,113 @@ fn run(args: Args) -> Result<(), AppError> {
         Some(p) => println!("{}", p.display()),
         None => println!("<not found>"),
     }
+    if args.all && !matches.is_empty() {
+        println!();
+        print_header("All matches:");
+        for (i, (_, p)) in matches.iter().enumerate() {
+            println!("{}   {}", p.display(), match_marker(i));
+        }
+    }
     println!();
     print_header("PATH order:");
 
-    for (idx, dir) in path_entries.iter().enumerate() {
+    for (idx, dups) in &groups {
         let n = idx + 1;
-        if Some(idx) == selected_index {
-            println!("{n}. {}   <- selected", dir.display());
-        } else {
-            println!("{n}. {}", dir.display());
+        let dir = &path_entries[*idx];
+        let mut line = format!("{n}. {}", display_entry(dir));
+        if args.all {
+            if let Some(i) = matches.iter().position(|(m, _)| m == idx) {
+                line.push_str("   ");
+                line.push_str(match_marker(i));
+            }
+        } else if Some(*idx) == selected_index {
+            line.push_str("   <- selected");
+        }
+        if let Some(abs) = &relative[*idx] {
+            line.push_str(&format!("   [relative -> {}]", abs.display()));
+        }
+        if !dups.is_empty() {
+            let also = dups
+                .iter()
+                .map(|d| (d + 1).to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            line.push_str(&format!("   ({} duplicate(s) at {also})", dups.len()));
         }
+        println!("{line}");
+    }
+
+    print_path_hygiene(&path_entries, &duplicate);
+
+    if let Some(s) = &suggestion {
+        println!();
+        print_header("Found outside PATH:");
+        println!("{}", s.resolved);
+        println!();
+        print_header("Suggested fix:");
+        println!("{}", s.export);
+    }
+
+    Ok(code)
+}
+
+/// Trailing `PATH hygiene:` section: repeated and missing entries, if any.
+fn print_path_hygiene(path_entries: &[PathBuf], duplicate: &[bool]) {
+    let duplicates: Vec<String> = duplicate
+        .iter()
+        .enumerate()
+        .filter(|(_, d)| **d)
+        .map(|(idx, _)| (idx + 1).to_string())
+        .collect();
+    let missing: Vec<String> = path_entries
+        .iter()
+        .enumerate()
+        .filter(|(_, dir)| !dir.as_os_str().is_empty() && fs::metadata(dir).is_err())
+        .map(|(idx, dir)| format!("{}. {}", idx + 1, dir.display()))
+        .collect();
+    if duplicates.is_empty() && missing.is_empty() {
+        return;
     }
 
-    Ok(())
+    println!();
+    print_header("PATH hygiene:");
+    if !duplicates.is_empty() {
+        println!("Duplicate entries: {}", duplicates.join(", "));
+    }
+    for m in &missing {
+        println!("Missing directory: {m}");
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn split_path_var_keeps_empty_entries() {
+        let entries = split_path_var(OsStr::new("/usr/bin::.:/bin:"));
+        let shown: Vec<String> = entries.iter().map(|e| display_entry(e)).collect();
+        assert_eq!(shown, vec!["/usr/bin", "(empty)", ".", "/bin", "(empty)"]);
+    }
+
+    #[test]
+    fn relative_resolution_flags_non_absolute_entries() {
+        let cwd = Path::new("/home/alice/project");
+        assert_eq!(relative_resolution(Path::new("/usr/bin"), cwd), None);
+        assert_eq!(
+            relative_resolution(Path::new("."), cwd),
+            Some(PathBuf::from("/home/alice/project"))
+        );
+        assert_eq!(
+            relative_resolution(Path::new(""), cwd),
+            Some(PathBuf::from("/home/alice/project"))
+        );
+        assert_eq!(
+            relative_resolution(Path::new("node_modules/.bin"), cwd),
+            Some(PathBuf::from("/home/alice/project/node_modules/.bin"))
+        );
+    }
+
     #[test]
     fn is_executable_on_known_binary() {
         // /bin/sh is always present and executable on Linux
@@ -229,15 +622,152 @@ mod tests {
     }
 
     #[test]
-    fn path_splitting_handles_empty() {
-        // Empty PATH bytes should produce no entries
-        let empty = std::ffi::OsString::new();
-        let entries: Vec<PathBuf> = empty
-            .as_bytes()
-            .split(|&b| b == b':')
-            .filter(|p| !p.is_empty())
-            .map(|p| PathBuf::from(OsStr::from_bytes(p)))
+    fn is_executable_owner_only_file() {
+        let dir = env::temp_dir().join(format!("zenlixem_envpath_exec_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("owner-only");
+        fs::write(&file, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o700)).unwrap();
+        assert!(is_executable(&file));
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o600)).unwrap();
+        assert!(!is_executable(&file));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn exec_permitted_checks_the_applicable_bit() {
+        let user = Credentials {
+            uid: 1000,
+            gid: 1000,
+            groups: vec![27],
+            cap_eff: 0,
+        };
+        assert!(exec_permitted(0o700, 1000, 0, Some(&user)));
+        assert!(!exec_permitted(0o700, 0, 0, Some(&user)));
+        assert!(!exec_permitted(0o070, 1000, 1000, Some(&user)));
+        assert!(exec_permitted(0o010, 0, 27, Some(&user)));
+        assert!(exec_permitted(0o001, 0, 0, Some(&user)));
+
+        let root = Credentials::default();
+        assert!(exec_permitted(0o100, 1000, 1000, Some(&root)));
+        assert!(!exec_permitted(0o644, 1000, 1000, Some(&root)));
+        assert!(exec_permitted(0o700, 0, 0, None));
+    }
+
+    #[test]
+    fn entry_status_reports_existence_and_command() {
+        assert_eq!(
+            entry_status(Path::new("/bin"), "sh"),
+            EntryStatus {
+                exists: true,
+                is_dir: true,
+                contains_command: true,
+            }
+        );
+        assert_eq!(
+            entry_status(Path::new("/nonexistent/bin"), "sh"),
+            EntryStatus {
+                exists: false,
+                is_dir: false,
+                contains_command: false,
+            }
+        );
+        let file = entry_status(Path::new("/etc/hosts"), "sh");
+        assert!(file.exists && !file.is_dir && !file.contains_command);
+        assert!(entry_status(Path::new(""), "sh").is_dir);
+    }
+
+    #[test]
+    fn suggest_path_fix_skips_dirs_already_in_path() {
+        let in_path: Vec<PathBuf> = SUGGEST_SYSTEM_DIRS.iter().map(PathBuf::from).collect();
+        assert_eq!(suggest_path_fix("sh", &in_path, None), None);
+
+        let s = suggest_path_fix("sh", &[], None).unwrap();
+        assert!(s.resolved.ends_with("/sh"));
+        assert_eq!(s.export, format!("export PATH=\"$PATH:{}\"", s.dir));
+        assert_eq!(
+            suggest_path_fix("zenlixem-no-such-command", &[], None),
+            None
+        );
+    }
+
+    #[test]
+    fn export_path_line_escapes_double_quote_specials() {
+        assert_eq!(
+            export_path_line(Path::new("/usr/local/bin")),
+            "export PATH=\"$PATH:/usr/local/bin\""
+        );
+        assert_eq!(
+            export_path_line(Path::new("/home/a b/$x\"q")),
+            "export PATH=\"$PATH:/home/a b/\\$x\\\"q\""
+        );
+    }
+
+    #[test]
+    fn find_matches_stops_at_first_unless_all() {
+        let entries: Vec<PathBuf> = ["/nonexistent/bin", "/bin", "/usr/bin"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        let first = find_matches(&entries, "sh", false);
+        assert_eq!(first, vec![(1, PathBuf::from("/bin/sh"))]);
+
+        let all = find_matches(&entries, "sh", true);
+        assert_eq!(all[0], (1, PathBuf::from("/bin/sh")));
+        assert!(all.iter().all(|(idx, _)| *idx >= 1));
+        assert_eq!(match_marker(0), "<- selected (first match)");
+        assert_eq!(match_marker(1), "<- shadowed");
+    }
+
+    #[test]
+    fn duplicate_flags_compare_resolved_dirs() {
+        let entries: Vec<PathBuf> = [
+            "/usr/bin",
+            "/usr/../usr/bin",
+            "/nonexistent/x",
+            "/nonexistent/x",
+            "/",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+        assert_eq!(
+            duplicate_flags(&entries),
+            vec![false, true, false, true, false]
+        );
+    }
+
+    #[test]
+    fn dedup_entries_keeps_first_occurrence() {
+        let entries: Vec<PathBuf> = [
+            "/usr/bin",
+            "/nonexistent/x",
+            "/usr/../usr/bin",
+            "/nonexistent/y",
+            "/usr/bin",
+            "/nonexistent/x",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+        assert_eq!(
+            dedup_entries(&entries),
+            vec![(0, vec![2, 4]), (1, vec![5]), (3, vec![])]
+        );
+        // Every later index is exactly an entry `duplicate_flags` marks.
+        let mut later: Vec<usize> = dedup_entries(&entries)
+            .into_iter()
+            .flat_map(|(_, dups)| dups)
             .collect();
-        assert!(entries.is_empty());
+        later.sort();
+        let flags = duplicate_flags(&entries);
+        let flagged: Vec<usize> = (0..entries.len()).filter(|&i| flags[i]).collect();
+        assert_eq!(later, flagged);
+    }
+
+    #[test]
+    fn split_path_var_empty_is_one_empty_entry() {
+        // Like the shell, an empty PATH is a single entry naming the cwd.
+        assert_eq!(split_path_var(OsStr::new("")), vec![PathBuf::new()]);
     }
 }
@@ -0,0 +1,43 @@
+//! JSON contract checks: run the binary with a controlled PATH and assert
+//! the documented envelope fields and types.
+
+use serde_json::Value;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::process::Command;
+
+#[test]
+fn resolved_command_json_shape() {
+    let dir = std::env::temp_dir().join(format!("zenlixem_json_envpath_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let tool = dir.join("fixture-tool");
+    fs::write(&tool, "#!/bin/sh\n").unwrap();
+    fs::set_permissions(&tool, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let out = Command::new(env!("CARGO_BIN_EXE_envpath"))
+        .env("PATH", format!("{}:/nonexistent", dir.display()))
+        .args(["--json", "fixture-tool"])
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{out:?}");
+    let v: Value = serde_json::from_slice(&out.stdout).unwrap();
+
+    assert!(v["privilege"].is_string(), "{v}");
+    assert!(v["mode_message"].is_string(), "{v}");
+    assert_eq!(v["mode"], "envpath");
+    assert_eq!(v["command"], "fixture-tool");
+    assert_eq!(v["partial"], false);
+    assert_eq!(v["skipped"], 0);
+    assert_eq!(v["results"]["resolved"], tool.display().to_string());
+
+    let order = v["results"]["path_order"].as_array().unwrap();
+    assert_eq!(order.len(), 2);
+    assert_eq!(order[0]["index"], 1);
+    assert_eq!(order[0]["selected"], true);
+    assert_eq!(order[0]["contains_command"], true);
+    assert_eq!(order[1]["exists"], false);
+    assert!(order[1]["is_dir"].is_boolean());
+
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -2,7 +2,8 @@
 
 use clap::{error::ErrorKind, Parser};
 use serde_json::json;
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
 use std::fs;
 use std::io::{self, BufRead};
 use std::path::{Path, PathBuf};
@@ -10,10 +11,12 @@ use std::process::Command;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use cliutil::{
-    error, print_header, print_info as print_suite_info, print_json_error, print_json_payload,
-    print_version, privilege_mode, privilege_mode_message, warn, AppError,
+    exit_with_error, glob_match, is_glob_pattern, load_passwd, print_header,
+    print_info as print_suite_info, print_json_envelope, print_json_error, print_version,
+    privilege_mode, privilege_mode_message, set_color_choice, uid_to_user, validate_fields, warn,
+    AppError, ColorChoice, ExitCode, JsonEnvelope, Table,
 };
-use fsmeta::format_systemtime_ago;
+use fsmeta::{format_size, format_systemtime_ago, format_unix_utc, parse_unix_utc};
 
 #[derive(Parser, Debug)]
 #[command(
@@ -24,6 +27,9 @@ use fsmeta::format_systemtime_ago;
     after_help = r#"EXAMPLES:
   lasttouch /etc/sysctl.conf
   lasttouch --json /etc/sysctl.conf
+  lasttouch --history 5 /etc/passwd
+  lasttouch --under /etc
+  lasttouch --audit-log '/var/log/audit/audit.log*' --history 10 /etc/shadow
 "#
 )]
 struct Args {
@@ -44,6 +50,68 @@ struct Args {
     )]
     json: bool,
 
+    #[arg(
+        long = "color",
+        value_enum,
+        value_name = "WHEN",
+        default_value = "auto",
+        help = "Color warnings, errors and headers: auto (terminal, unless NO_COLOR is set), always, or never"
+    )]
+    color: ColorChoice,
+
+    #[arg(
+        long = "include-host",
+        requires = "json",
+        help = "Add a host block (hostname, boot_id) to JSON output"
+    )]
+    include_host: bool,
+
+    #[arg(
+        long = "no-mode-message",
+        requires = "json",
+        help = "Omit the human-oriented mode_message field from JSON output"
+    )]
+    no_mode_message: bool,
+
+    #[arg(
+        long = "fields",
+        value_name = "LIST",
+        value_delimiter = ',',
+        requires = "json",
+        help = "Only include these comma-separated result fields in JSON output"
+    )]
+    fields: Vec<String>,
+
+    #[arg(
+        long = "history",
+        value_name = "N",
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u16).range(1..),
+        help = "Report the N most recent modifications, newest first (audit and journal sources)"
+    )]
+    history: u16,
+
+    #[arg(
+        long = "under",
+        help = "PATH is a directory: report the latest modification of anything below it"
+    )]
+    under: bool,
+
+    #[arg(
+        long = "audit-log",
+        value_name = "PATH",
+        help = "Audit log to read instead of /var/log/audit/audit.log; a glob (*, ?, [...]) in the file name selects rotated logs, read oldest first"
+    )]
+    audit_log: Option<String>,
+
+    #[arg(
+        long = "since",
+        value_name = "TIME",
+        value_parser = parse_since,
+        help = "Ignore audit and journal events before TIME: unix seconds or \"YYYY-MM-DD HH:MM:SS\" (UTC)"
+    )]
+    since: Option<u64>,
+
     #[arg(
         value_name = "PATH",
         required_unless_present_any = ["version", "info"],
@@ -52,6 +120,38 @@ struct Args {
     path: Option<String>,
 }
 
+/// Result keys accepted by `--fields`.
+const RESULT_FIELDS: &[&str] = &[
+    "user",
+    "process",
+    "time_unix",
+    "source",
+    "metadata_only",
+    "renamed_from",
+    "renamed_to",
+    "file",
+];
+
+/// What audit PATH names are matched against.
+#[derive(Clone, Debug)]
+enum PathTarget {
+    /// The file itself.
+    Exact(String),
+    /// `--under`: the directory or anything below it.
+    Under(String),
+}
+
+impl PathTarget {
+    fn matches(&self, name: &str) -> bool {
+        match self {
+            PathTarget::Exact(path) => name == path,
+            PathTarget::Under(dir) => name
+                .strip_prefix(dir.trim_end_matches('/'))
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/')),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 struct TouchInfo {
     user: String,
@@ -59,6 +159,12 @@ struct TouchInfo {
     time: SystemTime,
     source: String,
     metadata_only: bool,
+    /// The file was moved into place from this path (audit only).
+    renamed_from: Option<String>,
+    /// The file was moved away to this path (audit only).
+    renamed_to: Option<String>,
+    /// `--under`: the file below the directory that changed (audit only).
+    file: Option<String>,
 }
 
 fn main() {
@@ -69,39 +175,25 @@ fn main() {
         Err(e) => {
             if matches!(e.kind(), ErrorKind::DisplayHelp | ErrorKind::DisplayVersion) {
                 let _ = e.print();
-                std::process::exit(0);
+                ExitCode::Success.exit();
             }
             if json_requested {
                 print_json_error(AppError::InvalidInput(e.to_string()));
             } else {
                 let _ = e.print();
             }
-            std::process::exit(1);
+            ExitCode::InvalidInput.exit();
         }
     };
 
-    match run(args) {
-        Ok(()) => {}
-        Err(AppError::InvalidInput(e)) => {
-            if json_requested {
-                print_json_error(AppError::InvalidInput(e));
-            } else {
-                error(&e);
-            }
-            std::process::exit(1);
-        }
-        Err(AppError::Fatal(e)) => {
-            if json_requested {
-                print_json_error(AppError::Fatal(e));
-            } else {
-                error(&e);
-            }
-            std::process::exit(2);
-        }
+    if let Err(e) = run(args) {
+        exit_with_error(e, json_requested);
     }
 }
 
 fn run(args: Args) -> Result<(), AppError> {
+    set_color_choice(args.color);
+
     if args.version {
         print_version();
         return Ok(());
@@ -112,6 +204,8 @@ fn run(args: Args) -> Result<(), AppError> {
         return Ok(());
     }
 
+    validate_fields(&args.fields, RESULT_FIELDS)?;
+
     let path_arg = args
         .path
         .ok_or_else(|| AppError::InvalidInput("missing path".to_string()))?;
@@ -135,127 +229,361 @@ fn run(args: Args) -> Result<(), AppError> {
         }
     };
     let mtime = md.modified().map_err(|e| AppError::Fatal(e.to_string()))?;
+    if args.under && !md.is_dir() {
+        return Err(AppError::InvalidInput(format!(
+            "{}: --under needs a directory",
+            path.display()
+        )));
+    }
 
-    let passwd = parse_passwd().unwrap_or_default();
-    let info = if let Some(info) = try_audit_log(&path, &passwd).map_err(AppError::Fatal)? {
-        info
-    } else if let Some(info) = try_journalctl(&path, &passwd).map_err(AppError::Fatal)? {
-        info
+    let limit = usize::from(args.history);
+    let target = if args.under {
+        PathTarget::Under(path.to_string_lossy().into_owned())
     } else {
-        TouchInfo {
+        PathTarget::Exact(path.to_string_lossy().into_owned())
+    };
+    let audit_logs = match &args.audit_log {
+        Some(pattern) => {
+            let files = audit_log_files(pattern)
+                .map_err(|e| AppError::InvalidInput(format!("{pattern}: {e}")))?;
+            if files.is_empty() {
+                return Err(AppError::InvalidInput(format!(
+                    "{pattern}: no audit log matches"
+                )));
+            }
+            files
+        }
+        None => vec![PathBuf::from(DEFAULT_AUDIT_LOG)],
+    };
+    let passwd = load_passwd().unwrap_or_default();
+    let mut touches =
+        try_audit_log(&audit_logs, &target, &passwd, limit, args.since).map_err(AppError::Fatal)?;
+    if touches.is_empty() {
+        touches = try_journalctl(&path, &passwd, limit, args.since).map_err(AppError::Fatal)?;
+    }
+    if touches.is_empty() {
+        touches.push(TouchInfo {
             user: "unknown".to_string(),
             process: "unknown".to_string(),
             time: mtime,
             source: "metadata".to_string(),
             metadata_only: true,
-        }
-    };
+            renamed_from: None,
+            renamed_to: None,
+            file: None,
+        });
+    }
+    let info = &touches[0];
+    // A directory's own size says nothing about the files below it.
+    let size = (!args.under).then_some(md.len());
 
     if args.json {
-        let time_unix = info
-            .time
-            .duration_since(UNIX_EPOCH)
-            .map(|d| d.as_secs())
-            .unwrap_or(0);
-
-        let payload = json!({
+        let mut payload = json!({
             "privilege": privilege_mode(),
             "mode_message": privilege_mode_message(),
             "mode": "lasttouch",
             "path": path.display().to_string(),
             "partial": info.metadata_only,
             "skipped": 0,
-            "results": {
-                "user": info.user,
-                "process": info.process,
-                "time_unix": time_unix,
-                "source": info.source,
-                "metadata_only": info.metadata_only,
-            }
+            "results": touch_json(info),
         });
-        print_json_payload(&payload);
+        if let Some(size) = size {
+            payload["size_bytes"] = json!(size);
+        }
+        if let Some(since) = args.since {
+            payload["since_unix"] = json!(since);
+        }
+        if limit > 1 {
+            payload["history"] = touches.iter().map(touch_json).collect();
+        }
+        let envelope = JsonEnvelope {
+            include_host: args.include_host,
+            omit_mode_message: args.no_mode_message,
+            fields: args.fields.clone(),
+            ..JsonEnvelope::default()
+        };
+        print_json_envelope(payload, &envelope);
         return Ok(());
     }
 
-    print_info(&info);
+    if limit > 1 {
+        print_history(&touches, size);
+    } else {
+        print_info(info, size);
+    }
     Ok(())
 }
 
-fn print_info(info: &TouchInfo) {
+fn touch_json(info: &TouchInfo) -> serde_json::Value {
+    let time_unix = info
+        .time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let mut out = json!({
+        "user": info.user,
+        "process": info.process,
+        "time_unix": time_unix,
+        "source": info.source,
+        "metadata_only": info.metadata_only,
+    });
+    if let Some(from) = &info.renamed_from {
+        out["renamed_from"] = json!(from);
+    }
+    if let Some(to) = &info.renamed_to {
+        out["renamed_to"] = json!(to);
+    }
+    if let Some(file) = &info.file {
+        out["file"] = json!(file);
+    }
+    out
+}
+
+/// `renamed from X` / `renamed to Y` for a rename, else `None`.
+fn rename_label(info: &TouchInfo) -> Option<String> {
+    match (&info.renamed_from, &info.renamed_to) {
+        (Some(from), _) => Some(format!("renamed from {from}")),
+        (None, Some(to)) => Some(format!("renamed to {to}")),
+        (None, None) => None,
+    }
+}
+
+fn format_touch_time(time: SystemTime) -> String {
+    let ago = format_systemtime_ago(time);
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(d) => format!("{} ({})", format_unix_utc(d.as_secs()), ago),
+        Err(_) => ago,
+    }
+}
+
+/// `Size: 4.0K (4096 bytes)`; the current size, not the size at the touch.
+fn size_line(bytes: u64) -> String {
+    format!("Size: {} ({bytes} bytes)", format_size(bytes))
+}
+
+fn print_info(info: &TouchInfo, size: Option<u64>) {
     println!("{}", privilege_mode_message());
     print_header("Last modified by:");
+    if let Some(file) = &info.file {
+        println!("File: {file}");
+    }
+    if let Some(size) = size {
+        println!("{}", size_line(size));
+    }
     println!("User: {}", info.user);
     println!("Process: {}", info.process);
-    println!("Time: {}", format_systemtime_ago(info.time));
+    println!("Time: {}", format_touch_time(info.time));
     println!("Source: {}", info.source);
+    if let Some(rename) = rename_label(info) {
+        println!("Action: {rename}");
+    }
 
     if info.metadata_only {
         println!("Modification source unknown (metadata only).");
     }
 }
 
-fn parse_passwd() -> io::Result<HashMap<u32, String>> {
-    let f = fs::File::open("/etc/passwd")?;
-    let reader = io::BufReader::new(f);
-
-    let mut out = HashMap::new();
-
-    for line in reader.lines() {
-        let line = line?;
-        let mut parts = line.split(':');
-        let name = match parts.next() {
-            Some(s) if !s.is_empty() => s.to_string(),
-            _ => continue,
-        };
-        let _passwd = parts.next();
-        let uid = match parts.next() {
-            Some(s) => s.parse::<u32>().ok(),
-            None => None,
-        };
-        if let Some(uid) = uid {
-            out.insert(uid, name);
+/// `--history`: one row per modification, newest first.
+fn print_history(touches: &[TouchInfo], size: Option<u64>) {
+    println!("{}", privilege_mode_message());
+    if let Some(size) = size {
+        println!("{}", size_line(size));
+    }
+    print_header("Recent modifications (newest first):");
+    let show_file = touches.iter().any(|t| t.file.is_some());
+    let show_action = touches.iter().any(|t| rename_label(t).is_some());
+    let mut headers = vec!["TIME", "USER", "PROCESS", "SOURCE"];
+    if show_file {
+        headers.push("FILE");
+    }
+    if show_action {
+        headers.push("ACTION");
+    }
+    let mut table = Table::new(headers).fixed(0);
+    for t in touches {
+        let mut cells = vec![
+            format_touch_time(t.time),
+            t.user.clone(),
+            t.process.clone(),
+            t.source.clone(),
+        ];
+        if show_file {
+            cells.push(t.file.clone().unwrap_or_default());
         }
+        if show_action {
+            cells.push(rename_label(t).unwrap_or_default());
+        }
+        table.row(cells);
     }
+    table.print();
 
-    Ok(out)
+    if touches.iter().any(|t| t.metadata_only) {
+        println!("Modification source unknown (metadata only).");
+    }
 }
 
-fn uid_to_user(uid: u32, passwd: &HashMap<u32, String>) -> String {
-    passwd.get(&uid).cloned().unwrap_or_else(|| uid.to_string())
+/// `--since`: unix seconds, or a UTC `YYYY-MM-DD HH:MM:SS` timestamp (the
+/// form lasttouch prints).
+fn parse_since(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    if !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()) {
+        return s.parse().map_err(|_| format!("{s}: out of range"));
+    }
+    parse_unix_utc(s)
+        .ok_or_else(|| format!("{s}: expected unix seconds or \"YYYY-MM-DD HH:MM:SS\" (UTC)"))
 }
 
-fn try_audit_log(path: &Path, passwd: &HashMap<u32, String>) -> Result<Option<TouchInfo>, String> {
-    let audit_path = Path::new("/var/log/audit/audit.log");
-    if !audit_path.exists() {
-        return Ok(None);
+const DEFAULT_AUDIT_LOG: &str = "/var/log/audit/audit.log";
+
+/// `pattern` itself, or with a glob (`*`, `?`, `[...]`) in its file name
+/// every matching file in that directory, oldest first (by mtime, then name) so events
+/// are read in the order they were logged.
+fn audit_log_files(pattern: &str) -> io::Result<Vec<PathBuf>> {
+    let path = Path::new(pattern);
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    if !is_glob_pattern(&name) {
+        return Ok(if path.exists() {
+            vec![path.to_path_buf()]
+        } else {
+            Vec::new()
+        });
     }
 
-    let f = match fs::File::open(audit_path) {
-        Ok(f) => f,
-        Err(e) => {
-            warn(&format!("audit log not readable: {e}"));
-            return Ok(None);
-        }
+    let dir = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
     };
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)?.flatten() {
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        if !glob_match(&name, &file_name) || !entry.path().is_file() {
+            continue;
+        }
+        let mtime = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .unwrap_or(UNIX_EPOCH);
+        files.push((mtime, entry.path()));
+    }
+    files.sort();
+    Ok(files.into_iter().map(|(_, p)| p).collect())
+}
+
+/// Scan `logs` as one stream, oldest first, ending each file with a newline
+/// so a last line without one is not joined to the next file's first.
+/// Missing files are skipped quietly; unreadable ones with a warning.
+fn try_audit_log(
+    logs: &[PathBuf],
+    target: &PathTarget,
+    passwd: &HashMap<u32, String>,
+    limit: usize,
+    since: Option<u64>,
+) -> Result<Vec<TouchInfo>, String> {
+    let mut reader: Box<dyn io::Read> = Box::new(io::empty());
+    let mut opened = false;
+    for log in logs.iter().filter(|l| l.exists()) {
+        match fs::File::open(log) {
+            Ok(f) => {
+                let file = io::Read::chain(f, &b"\n"[..]);
+                reader = Box::new(io::Read::chain(reader, file));
+                opened = true;
+            }
+            Err(e) => warn(&format!("audit log not readable: {}: {e}", log.display())),
+        }
+    }
+    if !opened {
+        return Ok(Vec::new());
+    }
+
+    Ok(audit_touches(
+        io::BufReader::new(reader),
+        target,
+        limit,
+        since,
+        passwd,
+    ))
+}
+
+/// One audit event (SYSCALL, CWD and PATH records sharing a msg id).
+#[derive(Default)]
+struct AuditEvent {
+    sec: u64,
+    uid: Option<u32>,
+    comm: Option<String>,
+    syscall: Option<u64>,
+    a1: Option<u64>,
+    a2: Option<u64>,
+    cwd: Option<String>,
+    /// PATH `name` values (made absolute against the CWD record) with
+    /// their `nametype` (`DELETE` / `CREATE` mark a rename's old / new name).
+    names: Vec<(String, Option<String>)>,
+    success: Option<bool>,
+    matched: bool,
+}
 
-    let reader = io::BufReader::new(f);
+impl AuditEvent {
+    fn absolute(&self, name: &str) -> String {
+        match &self.cwd {
+            Some(cwd) if !name.starts_with('/') => {
+                Path::new(cwd).join(name).to_string_lossy().into_owned()
+            }
+            _ => name.to_string(),
+        }
+    }
 
-    let target = path.to_string_lossy().to_string();
+    /// The first PATH name `target` matches; for a rename into `--under`
+    /// that is the new name.
+    fn matched_name(&self, target: &PathTarget) -> Option<&str> {
+        let matching = || self.names.iter().filter(|(name, _)| target.matches(name));
+        matching()
+            .find(|(_, t)| t.as_deref() == Some("CREATE"))
+            .or_else(|| matching().next())
+            .map(|(name, _)| name.as_str())
+    }
+
+    fn name_of_type(&self, nametype: &str) -> Option<&str> {
+        self.names
+            .iter()
+            .find(|(_, t)| t.as_deref() == Some(nametype))
+            .map(|(name, _)| name.as_str())
+    }
 
-    #[derive(Default)]
-    struct AuditEvent {
-        sec: u64,
-        uid: Option<u32>,
-        comm: Option<String>,
-        syscall: Option<u64>,
-        a1: Option<u64>,
-        a2: Option<u64>,
-        has_target_path: bool,
-        success: Option<bool>,
+    /// `(renamed_from, renamed_to)` when this is a rename involving
+    /// `target` as its new or old name.
+    fn rename_of(&self, target: &PathTarget) -> (Option<String>, Option<String>) {
+        if !self.syscall.is_some_and(audit_syscall_is_rename) {
+            return (None, None);
+        }
+        let old = self.name_of_type("DELETE");
+        let new = self.name_of_type("CREATE");
+        match (old, new) {
+            (Some(old), Some(new)) if target.matches(new) => (Some(old.to_string()), None),
+            (Some(old), Some(new)) if target.matches(old) => (None, Some(new.to_string())),
+            _ => (None, None),
+        }
     }
+}
 
+/// The `limit` most recent successful modifications of `target` in an
+/// audit log, newest first. Later log lines win ties on the same second.
+/// A rename matches when `target` is either its old or its new name.
+fn audit_touches(
+    reader: impl BufRead,
+    target: &PathTarget,
+    limit: usize,
+    since: Option<u64>,
+    passwd: &HashMap<u32, String>,
+) -> Vec<TouchInfo> {
     let mut events: HashMap<String, AuditEvent> = HashMap::new();
-    let mut last_match: Option<(u64, String)> = None;
+    // Min-heap of the best matches so far, keyed by (second, match order).
+    let mut best: BinaryHeap<Reverse<(u64, usize, String)>> = BinaryHeap::new();
+    let mut order = 0usize;
+    // Heap minimum the last eviction swept with; events are only swept
+    // again once it rises.
+    let mut evicted_below = 0u64;
 
     for line in reader.lines() {
         let line = match line {
@@ -267,10 +595,11 @@ fn try_audit_log(path: &Path, passwd: &HashMap<u32, String>) -> Result<Option<To
             continue;
         };
 
-        // Only track SYSCALL and PATH lines to reduce memory usage
+        // Only track SYSCALL, CWD and PATH lines to reduce memory usage
         let is_syscall = line.contains("type=SYSCALL");
+        let is_cwd = line.contains("type=CWD");
         let is_path = line.contains("type=PATH");
-        if !is_syscall && !is_path {
+        if !is_syscall && !is_cwd && !is_path {
             continue;
         }
 
@@ -291,96 +620,129 @@ fn try_audit_log(path: &Path, passwd: &HashMap<u32, String>) -> Result<Option<To
             entry.success = extract_kv_string(&line, "success").map(|s| s == "yes");
         }
 
+        if is_cwd {
+            entry.cwd = extract_kv_string(&line, "cwd");
+        }
+
         if is_path {
             if let Some(name) = extract_kv_string(&line, "name") {
-                if name == target {
-                    entry.has_target_path = true;
-                }
+                let name = entry.absolute(&name);
+                entry
+                    .names
+                    .push((name, extract_kv_string(&line, "nametype")));
             }
         }
 
-        if entry.has_target_path {
-            if let Some(true) = entry.success {
-                if let Some(syscall) = entry.syscall {
-                    if audit_event_is_modification(syscall, entry.a1, entry.a2) {
-                        let sec = entry.sec;
-                        if sec > 0 {
-                            let update = match &last_match {
-                                Some((last_sec, _)) => sec >= *last_sec,
-                                None => true,
-                            };
-                            if update {
-                                // Evict completed events older than the new best match
-                                if let Some((old_sec, _)) = &last_match {
-                                    events.retain(|_, ev| ev.sec == 0 || ev.sec >= *old_sec);
-                                }
-                                last_match = Some((sec, msg_id.clone()));
-                            }
-                        }
-                    }
+        if entry.matched || entry.matched_name(target).is_none() || entry.success != Some(true) {
+            continue;
+        }
+        let Some(syscall) = entry.syscall else {
+            continue;
+        };
+        if entry.sec == 0 || since.is_some_and(|since| entry.sec < since) {
+            continue;
+        }
+        if !audit_event_is_modification(syscall, entry.a1, entry.a2) {
+            continue;
+        }
+        entry.matched = true;
+        let sec = entry.sec;
+        order += 1;
+        if best.len() == limit {
+            match best.peek() {
+                Some(Reverse((min_sec, _, _))) if sec < *min_sec => continue,
+                _ => {}
+            }
+            best.pop();
+        }
+        best.push(Reverse((sec, order, msg_id)));
+        if best.len() == limit {
+            // Evict events too old to make the list any more
+            if let Some(&Reverse((min_sec, _, _))) = best.peek() {
+                if min_sec > evicted_below {
+                    events.retain(|_, ev| ev.sec == 0 || ev.sec >= min_sec);
+                    evicted_below = min_sec;
                 }
             }
         }
     }
 
-    let Some((sec, id)) = last_match else {
-        return Ok(None);
-    };
-
-    let Some(ev) = events.get(&id) else {
-        return Ok(None);
-    };
-
-    let uid = ev.uid.unwrap_or(0);
-    let user = if ev.uid.is_some() {
-        uid_to_user(uid, passwd)
-    } else {
-        "unknown".to_string()
-    };
-    let process = ev.comm.clone().unwrap_or_else(|| "unknown".to_string());
-    let time = UNIX_EPOCH + Duration::from_secs(sec);
+    let mut best: Vec<(u64, usize, String)> = best.into_iter().map(|Reverse(m)| m).collect();
+    best.sort_by_key(|&(sec, order, _)| Reverse((sec, order)));
+    best.into_iter()
+        .filter_map(|(sec, _, id)| {
+            let ev = events.get(&id)?;
+            let user = match ev.uid {
+                Some(uid) => uid_to_user(uid, passwd),
+                None => "unknown".to_string(),
+            };
+            // Resolved last: a rename's new name is logged after its old one.
+            let (renamed_from, renamed_to) = ev.rename_of(target);
+            let file = match target {
+                PathTarget::Under(_) => ev.matched_name(target).map(str::to_string),
+                PathTarget::Exact(_) => None,
+            };
+            Some(TouchInfo {
+                user,
+                process: ev.comm.clone().unwrap_or_else(|| "unknown".to_string()),
+                time: UNIX_EPOCH + Duration::from_secs(sec),
+                source: "audit".to_string(),
+                metadata_only: false,
+                renamed_from,
+                renamed_to,
+                file,
+            })
+        })
+        .collect()
+}
 
-    Ok(Some(TouchInfo {
-        user,
-        process,
-        time,
-        source: "audit".to_string(),
-        metadata_only: false,
-    }))
-}
-
-/// x86_64 syscall numbers that modify filesystem state.
-const MODIFY_SYSCALLS_X86_64: &[u64] = &[
-    76,  /* truncate */
-    77,  /* ftruncate */
-    82,  /* rename */
-    87,  /* unlink */
-    90,  /* chmod */
-    92,  /* chown */
-    260, /* unlinkat */
-    263, /* renameat */
-    264, /* renameat2 */
-    268, /* fchmodat */
-    280, /* fchownat */
-    316, /* renameat2 (alt) */
+/// x86_64 syscalls (number, name) that modify a file's contents, name or
+/// metadata. open/openat count only with write flags (see
+/// [`open_flags_modify`]) and are not listed.
+const MODIFY_SYSCALLS_X86_64: &[(u64, &str)] = &[
+    (76, "truncate"),   // shorten or extend by path
+    (77, "ftruncate"),  // shorten or extend by fd
+    (82, "rename"),     // move; both names count
+    (87, "unlink"),     // remove a name
+    (90, "chmod"),      // change permission bits
+    (92, "chown"),      // change owner/group
+    (260, "fchownat"),  // chown relative to a dir fd
+    (263, "unlinkat"),  // unlink (or rmdir) relative to a dir fd
+    (264, "renameat"),  // rename relative to dir fds
+    (268, "fchmodat"),  // chmod relative to a dir fd
+    (280, "utimensat"), // set access/modification times
+    (316, "renameat2"), // renameat with exchange/noreplace flags
 ];
 
-/// aarch64 syscall numbers that modify filesystem state.
-const MODIFY_SYSCALLS_AARCH64: &[u64] = &[
-    46, /* ftruncate */
-    48, /* faccessat */
-    49, /* chdir */
-    52, /* renameat */
-    53, /* mkdirat */
-    54, /* unlinkat */
-    55, /* symlinkat */
-    56, /* openat */
-    58, /* linkat */
-    59, /* renameat2 */
-    64, /* fchownat */
-    67, /* fchmodat */
+/// aarch64 (asm-generic numbering) counterpart of
+/// [`MODIFY_SYSCALLS_X86_64`]; the legacy path-only calls do not exist.
+const MODIFY_SYSCALLS_AARCH64: &[(u64, &str)] = &[
+    (35, "unlinkat"),   // unlink (or rmdir) relative to a dir fd
+    (38, "renameat"),   // rename relative to dir fds
+    (45, "truncate"),   // shorten or extend by path
+    (46, "ftruncate"),  // shorten or extend by fd
+    (53, "fchmodat"),   // chmod relative to a dir fd
+    (54, "fchownat"),   // chown relative to a dir fd
+    (88, "utimensat"),  // set access/modification times
+    (276, "renameat2"), // renameat with exchange/noreplace flags
 ];
 
+fn modify_syscalls_contain(table: &[(u64, &str)], syscall: u64) -> bool {
+    table.iter().any(|(nr, _)| *nr == syscall)
+}
+
+/// rename(2), renameat(2) and renameat2(2): two PATH records, the old name
+/// (`nametype=DELETE`) and the new one (`nametype=CREATE`).
+const RENAME_SYSCALLS_X86_64: &[u64] = &[82, 264, 316];
+const RENAME_SYSCALLS_AARCH64: &[u64] = &[38, 276];
+
+fn audit_syscall_is_rename(syscall: u64) -> bool {
+    match std::env::consts::ARCH {
+        "aarch64" => RENAME_SYSCALLS_AARCH64.contains(&syscall),
+        _ => RENAME_SYSCALLS_X86_64.contains(&syscall),
+    }
+}
+
 /// x86_64: open(2) passes flags in a1; openat(257) passes flags in a2.
 const SYSCALL_OPEN_X86_64: u64 = 2;
 const SYSCALL_OPENAT_X86_64: u64 = 257;
@@ -399,13 +761,13 @@ fn audit_event_is_modification(syscall: u64, a1: Option<u64>, a2: Option<u64>) -
             if syscall == SYSCALL_OPENAT_X86_64 {
                 return open_flags_modify(a2.unwrap_or(0));
             }
-            MODIFY_SYSCALLS_X86_64.contains(&syscall)
+            modify_syscalls_contain(MODIFY_SYSCALLS_X86_64, syscall)
         }
         "aarch64" => {
             if syscall == SYSCALL_OPENAT_AARCH64 {
                 return open_flags_modify(a2.unwrap_or(0));
             }
-            MODIFY_SYSCALLS_AARCH64.contains(&syscall)
+            modify_syscalls_contain(MODIFY_SYSCALLS_AARCH64, syscall)
         }
         _ => {
             static WARNED: std::sync::Once = std::sync::Once::new();
@@ -421,7 +783,7 @@ fn audit_event_is_modification(syscall: u64, a1: Option<u64>, a2: Option<u64>) -
             if syscall == SYSCALL_OPENAT_X86_64 {
                 return open_flags_modify(a2.unwrap_or(0));
             }
-            MODIFY_SYSCALLS_X86_64.contains(&syscall)
+            modify_syscalls_contain(MODIFY_SYSCALLS_X86_64, syscall)
         }
     }
 }
@@ -478,33 +840,42 @@ fn extract_kv_hex_u64(line: &str, key: &str) -> Option<u64> {
     u64::from_str_radix(&s, 16).ok()
 }
 
-fn try_journalctl(path: &Path, passwd: &HashMap<u32, String>) -> Result<Option<TouchInfo>, String> {
-    let escaped = escape_journal_regex(&path.to_string_lossy());
+fn try_journalctl(
+    path: &Path,
+    passwd: &HashMap<u32, String>,
+    limit: usize,
+    since: Option<u64>,
+) -> Result<Vec<TouchInfo>, String> {
+    let path = path.to_string_lossy();
 
-    let output = Command::new("journalctl")
-        .arg("--no-pager")
+    let mut cmd = Command::new("journalctl");
+    cmd.arg("--no-pager")
         .arg("-o")
         .arg("export")
         .arg("-r")
         .arg("--grep")
-        .arg(escaped)
+        .arg(journal_path_pattern(&path))
         .arg("-n")
-        .arg("1")
-        .output();
+        .arg(limit.to_string());
+    if let Some(since) = since {
+        // `@` takes unix seconds, sidestepping journalctl's local time zone.
+        cmd.arg("--since").arg(format!("@{since}"));
+    }
+    let output = cmd.output();
 
     let output = match output {
         Ok(o) => o,
         Err(e) => {
             if e.kind() == io::ErrorKind::NotFound {
-                return Ok(None);
+                return Ok(Vec::new());
             }
             warn(&format!("journalctl unavailable: {e}"));
-            return Ok(None);
+            return Ok(Vec::new());
         }
     };
 
     if !output.status.success() {
-        return Ok(None);
+        return Ok(Vec::new());
     }
 
     let stdout_raw = &output.stdout;
@@ -513,26 +884,41 @@ fn try_journalctl(path: &Path, passwd: &HashMap<u32, String>) -> Result<Option<T
     if has_replacement {
         warn("journalctl output contained non-UTF-8 bytes; some fields may be inaccurate");
     }
-    if stdout.trim().is_empty() {
-        return Ok(None);
-    }
+    Ok(parse_journal_export(&stdout, &path, passwd))
+}
 
-    let mut fields = HashMap::new();
-    for line in stdout.lines() {
+/// Touches from `journalctl -o export` output: one record per blank-line
+/// separated block, kept in the order given (`-r` makes it newest first).
+/// Records without a timestamp, or whose MESSAGE does not name `path` as a
+/// whole path (see [`message_mentions_path`]), are skipped.
+fn parse_journal_export(export: &str, path: &str, passwd: &HashMap<u32, String>) -> Vec<TouchInfo> {
+    let mut touches = Vec::new();
+    let mut fields: HashMap<String, String> = HashMap::new();
+    for line in export.lines().chain([""]) {
         if line.trim().is_empty() {
-            break;
+            let mentioned = fields
+                .get("MESSAGE")
+                .is_some_and(|m| message_mentions_path(m, path));
+            if mentioned {
+                touches.extend(journal_touch(&fields, passwd));
+            }
+            fields.clear();
+            continue;
         }
         if let Some((k, v)) = line.split_once('=') {
             fields.insert(k.to_string(), v.to_string());
         }
     }
+    touches
+}
 
-    let Some(us) = fields
+fn journal_touch(
+    fields: &HashMap<String, String>,
+    passwd: &HashMap<u32, String>,
+) -> Option<TouchInfo> {
+    let us = fields
         .get("__REALTIME_TIMESTAMP")
-        .and_then(|s| s.parse::<u64>().ok())
-    else {
-        return Ok(None);
-    };
+        .and_then(|s| s.parse::<u64>().ok())?;
 
     let uid = fields.get("_UID").and_then(|s| s.parse::<u32>().ok());
 
@@ -548,30 +934,73 @@ fn try_journalctl(path: &Path, passwd: &HashMap<u32, String>) -> Result<Option<T
 
     let time = UNIX_EPOCH + Duration::from_micros(us);
 
-    Ok(Some(TouchInfo {
+    Some(TouchInfo {
         user,
         process,
         time,
         source: "journal".to_string(),
         metadata_only: false,
-    }))
+        renamed_from: None,
+        renamed_to: None,
+        file: None,
+    })
 }
 
+/// Characters that may directly precede a path in a log message.
+const PATH_OPENERS: &[char] = &['"', '\'', '=', '(', '[', '<'];
+/// Characters that may directly follow one; a `.` counts only when it ends
+/// the sentence (followed by whitespace or the end).
+const PATH_CLOSERS: &[char] = &['"', '\'', ',', ';', ':', ')', ']', '>'];
+
+/// `journalctl --grep` (PCRE2) pattern for `path` as a whole path:
+/// `/var/log/app` must not match inside `/var/log/app2`, `/var/log/app/x`
+/// or `/srv/var/log/app`. Mirrors [`message_mentions_path`], which
+/// re-checks every returned record.
+fn journal_path_pattern(path: &str) -> String {
+    format!(
+        r#"(?<![^\s"'=(\[<]){}(?=$|[\s"',;:)\]>]|\.(?:\s|$))"#,
+        escape_journal_regex(path)
+    )
+}
+
+/// Escape `s` to match itself literally in a PCRE2 pattern. Every ASCII
+/// character other than letters, digits, `/` and `_` is escaped: PCRE2
+/// treats a backslash before any non-alphanumeric as a literal, so spaces
+/// stay exact even under extended syntax. Control characters become `\x{..}`.
 fn escape_journal_regex(s: &str) -> String {
     let mut out = String::with_capacity(s.len());
     for ch in s.chars() {
-        match ch {
-            '.' | '^' | '$' | '|' | '?' | '*' | '+' | '(' | ')' | '[' | ']' | '{' | '}' | '\\'
-            | '-' => {
-                out.push('\\');
-                out.push(ch);
-            }
-            _ => out.push(ch),
+        if ch.is_ascii_control() {
+            out.push_str(&format!("\\x{{{:x}}}", u32::from(ch)));
+        } else if ch.is_ascii() && !ch.is_ascii_alphanumeric() && ch != '/' && ch != '_' {
+            out.push('\\');
+            out.push(ch);
+        } else {
+            out.push(ch);
         }
     }
     out
 }
 
+/// Whether `message` names `path` as a whole path, with the same
+/// boundaries as [`journal_path_pattern`].
+fn message_mentions_path(message: &str, path: &str) -> bool {
+    if path.is_empty() {
+        return false;
+    }
+    message.match_indices(path).any(|(at, _)| {
+        let before = message[..at].chars().next_back();
+        let mut after = message[at + path.len()..].chars();
+        let opens = before.is_none_or(|c| c.is_whitespace() || PATH_OPENERS.contains(&c));
+        let closes = match after.next() {
+            None => true,
+            Some('.') => after.next().is_none_or(char::is_whitespace),
+            Some(c) => c.is_whitespace() || PATH_CLOSERS.contains(&c),
+        };
+        opens && closes
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -674,10 +1103,63 @@ mod tests {
 
     #[test]
     fn audit_event_is_modification_aarch64_unlinkat() {
-        // On aarch64, unlinkat is syscall 54 — always a modification
+        // On aarch64, unlinkat is syscall 35 — always a modification
         if std::env::consts::ARCH == "aarch64" {
-            assert!(audit_event_is_modification(54, None, None));
+            assert!(audit_event_is_modification(35, None, None));
+        }
+    }
+
+    #[test]
+    fn audit_event_is_modification_unlinkat_but_not_read() {
+        let (table, read) = match std::env::consts::ARCH {
+            "aarch64" => (MODIFY_SYSCALLS_AARCH64, 63),
+            _ => (MODIFY_SYSCALLS_X86_64, 0),
+        };
+        let unlinkat = table
+            .iter()
+            .find(|(_, name)| *name == "unlinkat")
+            .map(|(nr, _)| *nr)
+            .unwrap();
+        assert!(audit_event_is_modification(unlinkat, None, None));
+        assert!(!audit_event_is_modification(read, None, None));
+        assert!(!modify_syscalls_contain(table, read));
+    }
+
+    #[test]
+    fn audit_log_files_are_oldest_first() {
+        let dir = std::env::temp_dir().join(format!("lasttouch_rotated_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for (name, age) in [("audit.log", 0), ("audit.log.1", 100), ("audit.log.2", 200)] {
+            let f = fs::File::create(dir.join(name)).unwrap();
+            f.set_modified(SystemTime::now() - Duration::from_secs(age))
+                .unwrap();
         }
+        fs::File::create(dir.join("other.log")).unwrap();
+
+        let pattern = dir.join("audit.log*");
+        let files = audit_log_files(&pattern.to_string_lossy()).unwrap();
+        let names: Vec<_> = files
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, vec!["audit.log.2", "audit.log.1", "audit.log"]);
+
+        let digits = dir.join("audit.log.[0-9]");
+        let files = audit_log_files(&digits.to_string_lossy()).unwrap();
+        assert_eq!(
+            files,
+            vec![dir.join("audit.log.2"), dir.join("audit.log.1")]
+        );
+
+        let single = dir.join("audit.log.1");
+        assert_eq!(
+            audit_log_files(&single.to_string_lossy()).unwrap(),
+            vec![single]
+        );
+        assert!(audit_log_files(&dir.join("missing").to_string_lossy())
+            .unwrap()
+            .is_empty());
+        fs::remove_dir_all(&dir).unwrap();
     }
 
     #[test]
@@ -686,6 +1168,172 @@ mod tests {
         assert!(!audit_event_is_modification(99999, None, None));
     }
 
+    fn exact(path: &str) -> PathTarget {
+        PathTarget::Exact(path.to_string())
+    }
+
+    fn audit_event(sec: u64, serial: u32, uid: u32, comm: &str, name: &str) -> String {
+        // unlink(at): a modification on both supported architectures
+        let syscall = if std::env::consts::ARCH == "aarch64" {
+            54
+        } else {
+            87
+        };
+        format!(
+            "type=SYSCALL msg=audit({sec}.000:{serial}): syscall={syscall} success=yes uid={uid} comm=\"{comm}\"\n\
+             type=PATH msg=audit({sec}.000:{serial}): item=0 name=\"{name}\"\n"
+        )
+    }
+
+    #[test]
+    fn audit_touches_keeps_newest_n() {
+        let mut log = String::new();
+        log += &audit_event(100, 1, 1, "a", "/etc/x");
+        log += &audit_event(300, 2, 2, "b", "/etc/x");
+        log += &audit_event(400, 3, 3, "c", "/etc/other");
+        log += &audit_event(200, 4, 4, "d", "/etc/x");
+        log += &audit_event(300, 5, 5, "e", "/etc/x");
+        let passwd = HashMap::new();
+
+        let procs = |limit| {
+            audit_touches(log.as_bytes(), &exact("/etc/x"), limit, None, &passwd)
+                .into_iter()
+                .map(|t| t.process)
+                .collect::<Vec<_>>()
+        };
+        // Same-second ties go to the later log line, as before --history.
+        assert_eq!(procs(1), vec!["e"]);
+        assert_eq!(procs(3), vec!["e", "b", "d"]);
+        assert_eq!(procs(10), vec!["e", "b", "d", "a"]);
+    }
+
+    #[test]
+    fn rotated_logs_without_trailing_newline_stay_separate() {
+        let dir = std::env::temp_dir().join(format!("lasttouch_joined_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let older = dir.join("audit.log.1");
+        let newer = dir.join("audit.log");
+        let event = audit_event(100, 1, 1, "a", "/etc/x");
+        fs::write(&older, event.trim_end()).unwrap();
+        fs::write(&newer, audit_event(200, 2, 2, "b", "/etc/x")).unwrap();
+
+        let touches =
+            try_audit_log(&[older, newer], &exact("/etc/x"), &HashMap::new(), 10, None).unwrap();
+        let procs: Vec<_> = touches.into_iter().map(|t| t.process).collect();
+        assert_eq!(procs, vec!["b", "a"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn audit_touches_since_skips_earlier_events() {
+        let mut log = String::new();
+        log += &audit_event(100, 1, 1, "a", "/etc/x");
+        log += &audit_event(200, 2, 2, "b", "/etc/x");
+        log += &audit_event(300, 3, 3, "c", "/etc/x");
+        let passwd = HashMap::new();
+
+        let procs = |since| {
+            audit_touches(log.as_bytes(), &exact("/etc/x"), 10, since, &passwd)
+                .into_iter()
+                .map(|t| t.process)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(procs(Some(200)), vec!["c", "b"]);
+        assert_eq!(procs(Some(301)), Vec::<String>::new());
+        assert_eq!(procs(None), vec!["c", "b", "a"]);
+    }
+
+    #[test]
+    fn parse_since_accepts_epoch_and_utc_timestamps() {
+        assert_eq!(parse_since("0"), Ok(0));
+        assert_eq!(parse_since("1714569727"), Ok(1_714_569_727));
+        assert_eq!(parse_since("2024-05-01 13:22:07"), Ok(1_714_569_727));
+        assert_eq!(parse_since("2024-05-01T13:22:07"), Ok(1_714_569_727));
+        assert_eq!(parse_since("2024-05-01 13:22:07 UTC"), Ok(1_714_569_727));
+        assert_eq!(parse_since(" 2024-05-01 00:00:00 "), Ok(1_714_521_600));
+
+        for bad in ["", "yesterday", "-5", "2024-05-01", "2024-02-30 00:00:00"] {
+            assert!(parse_since(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn audit_touches_follows_renameat2() {
+        let syscall = if std::env::consts::ARCH == "aarch64" {
+            276
+        } else {
+            316
+        };
+        let log = format!(
+            "type=SYSCALL msg=audit(500.000:9): syscall={syscall} success=yes uid=0 comm=\"mv\"\n\
+             type=CWD msg=audit(500.000:9): cwd=\"/etc\"\n\
+             type=PATH msg=audit(500.000:9): item=0 name=\"/tmp/\" nametype=PARENT\n\
+             type=PATH msg=audit(500.000:9): item=1 name=\"/etc/\" nametype=PARENT\n\
+             type=PATH msg=audit(500.000:9): item=2 name=\"/tmp/app.conf.new\" nametype=DELETE\n\
+             type=PATH msg=audit(500.000:9): item=3 name=\"app.conf\" nametype=CREATE\n"
+        );
+        let passwd = HashMap::new();
+
+        let moved_in = audit_touches(log.as_bytes(), &exact("/etc/app.conf"), 1, None, &passwd);
+        assert_eq!(moved_in.len(), 1);
+        assert_eq!(moved_in[0].process, "mv");
+        assert_eq!(
+            moved_in[0].renamed_from.as_deref(),
+            Some("/tmp/app.conf.new")
+        );
+        assert_eq!(moved_in[0].renamed_to, None);
+
+        let moved_away = audit_touches(
+            log.as_bytes(),
+            &exact("/tmp/app.conf.new"),
+            1,
+            None,
+            &passwd,
+        );
+        assert_eq!(moved_away.len(), 1);
+        assert_eq!(moved_away[0].renamed_to.as_deref(), Some("/etc/app.conf"));
+        assert_eq!(
+            rename_label(&moved_away[0]).unwrap(),
+            "renamed to /etc/app.conf"
+        );
+        assert_eq!(moved_away[0].file, None);
+    }
+
+    #[test]
+    fn audit_touches_under_reports_the_newest_file_below() {
+        let mut log = String::new();
+        log += &audit_event(100, 1, 1, "a", "/etc/ssh/sshd_config");
+        log += &audit_event(300, 2, 2, "b", "/etcetera/x");
+        log += &audit_event(200, 3, 3, "c", "/etc/hosts");
+        let passwd = HashMap::new();
+        let under = PathTarget::Under("/etc/".to_string());
+
+        let touches = audit_touches(log.as_bytes(), &under, 1, None, &passwd);
+        assert_eq!(touches.len(), 1);
+        assert_eq!(touches[0].process, "c");
+        assert_eq!(touches[0].file.as_deref(), Some("/etc/hosts"));
+
+        assert!(under.matches("/etc"));
+        assert!(!under.matches("/etcetera/x"));
+    }
+
+    #[test]
+    fn parse_journal_export_reads_every_record() {
+        let export = "__REALTIME_TIMESTAMP=2000000\n_UID=0\n_COMM=vim\nMESSAGE=wrote /etc/app.conf\n\n\
+                      __REALTIME_TIMESTAMP=1500000\n_COMM=other\nMESSAGE=wrote /etc/app.conf2\n\n\
+                      __REALTIME_TIMESTAMP=1000000\nSYSLOG_IDENTIFIER=sed\nMESSAGE=sed -i /etc/app.conf\n\n\
+                      _COMM=no-timestamp\nMESSAGE=/etc/app.conf\n";
+        let mut passwd = HashMap::new();
+        passwd.insert(0, "root".to_string());
+        let touches = parse_journal_export(export, "/etc/app.conf", &passwd);
+        assert_eq!(touches.len(), 2);
+        assert_eq!(touches[0].process, "vim");
+        assert_eq!(touches[0].user, "root");
+        assert_eq!(touches[0].time, UNIX_EPOCH + Duration::from_secs(2));
+        assert_eq!(touches[1].process, "sed");
+        assert_eq!(touches[1].user, "unknown");
+    }
+
     #[test]
     fn escape_journal_regex() {
         assert_eq!(
@@ -693,5 +1341,60 @@ mod tests {
             r"/etc/foo\.bar"
         );
         assert_eq!(super::escape_journal_regex("test[0]"), r"test\[0\]");
+        assert_eq!(
+            super::escape_journal_regex("/srv/My Files/a b.txt"),
+            r"/srv/My\ Files/a\ b\.txt"
+        );
+        assert_eq!(
+            super::escape_journal_regex("/tmp/x#1 (copy)"),
+            r"/tmp/x\#1\ \(copy\)"
+        );
+        assert_eq!(super::escape_journal_regex("/tmp/a\tb"), r"/tmp/a\x{9}b");
+        assert_eq!(
+            super::escape_journal_regex("/home/zoë/ü_1"),
+            "/home/zoë/ü_1"
+        );
+    }
+
+    #[test]
+    fn journal_path_pattern_is_anchored() {
+        assert_eq!(
+            journal_path_pattern("/var/log/app"),
+            r#"(?<![^\s"'=(\[<])/var/log/app(?=$|[\s"',;:)\]>]|\.(?:\s|$))"#
+        );
+    }
+
+    #[test]
+    fn message_mentions_whole_path_only() {
+        let path = "/var/log/app";
+        for message in [
+            "/var/log/app",
+            "rotated /var/log/app",
+            "rotated /var/log/app.",
+            "opened \"/var/log/app\" for writing",
+            "file=/var/log/app mode=0644",
+            "(/var/log/app): truncated",
+            "wrote /var/log/app, then exited",
+            "/var/log/app2 and /var/log/app too",
+        ] {
+            assert!(message_mentions_path(message, path), "{message}");
+        }
+        for message in [
+            "rotated /var/log/app2",
+            "rotated /var/log/app.1",
+            "rotated /var/log/app/current",
+            "rotated /srv/var/log/app",
+            "rotated /var/log/apple",
+        ] {
+            assert!(!message_mentions_path(message, path), "{message}");
+        }
+
+        let spaced = "/srv/My Files/a.txt";
+        assert!(message_mentions_path("saved '/srv/My Files/a.txt'", spaced));
+        assert!(!message_mentions_path(
+            "saved /srv/My Files/a.txt.bak",
+            spaced
+        ));
+        assert!(!message_mentions_path("anything", ""));
     }
 }
@@ -0,0 +1,38 @@
+//! JSON contract checks: run the binary on a fresh file and assert the
+//! documented envelope fields and types.
+
+use serde_json::Value;
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn file_json_shape() {
+    let path = std::env::temp_dir().join(format!("zenlixem_json_lasttouch_{}", std::process::id()));
+    fs::write(&path, "x").unwrap();
+
+    let out = Command::new(env!("CARGO_BIN_EXE_lasttouch"))
+        .arg("--json")
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{out:?}");
+    let v: Value = serde_json::from_slice(&out.stdout).unwrap();
+
+    assert!(v["privilege"].is_string(), "{v}");
+    assert!(v["mode_message"].is_string(), "{v}");
+    assert_eq!(v["mode"], "lasttouch");
+    assert_eq!(v["path"], path.display().to_string());
+    assert!(v["partial"].is_boolean(), "{v}");
+    assert_eq!(v["skipped"], 0);
+    assert_eq!(v["size_bytes"], 1);
+
+    let results = &v["results"];
+    assert!(
+        results["time_unix"].is_i64() || results["time_unix"].is_u64(),
+        "{v}"
+    );
+    assert!(results["source"].is_string(), "{v}");
+    assert!(results["metadata_only"].is_boolean(), "{v}");
+
+    let _ = fs::remove_file(&path);
+}
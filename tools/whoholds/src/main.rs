@@ -3,23 +3,42 @@
 use clap::{error::ErrorKind, Parser};
 use serde::Serialize;
 use serde_json::json;
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::io::{self, IsTerminal, Write};
+use std::net::IpAddr;
+use std::os::unix::fs::FileTypeExt;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 use cliutil::{
-    error, print_header, print_info, print_json_error, print_json_payload, print_version,
-    privilege_mode, privilege_mode_message, AppError,
+    distinct_commands, exit_with_error, expand_glob, format_distinct_commands,
+    install_interrupt_flag, interrupted, is_glob_pattern, print_header, print_info,
+    print_json_error, print_scan_envelope, print_stable, print_version, privilege_insufficient,
+    privilege_mode, privilege_mode_message, reexec_with_sudo, set_color_choice,
+    sleep_unless_interrupted, sort_reasons, stable_line, validate_fields, warn, AppError,
+    ColorChoice, Exclusions, ExitCode, JsonEnvelope, OutputFormat, ReasonKind, RedrawThrottle,
+    ScanScopes, ScanStats, Table, Target,
 };
-use fsmeta::{dev_major_minor, file_id_for_path};
 use procscan::{
-    list_pids, parse_socket_inode, proto_label_and_sort, read_comm_access, read_comm_best_effort,
-    read_fd_links_access, read_proc_net_sockets, scan_pid_mmap_file, scan_pid_open_fd_file,
-    scan_pid_open_fd_socket, socket_state_label, ProcAccess, ProcNetProto, TCP_ESTABLISHED,
-    TCP_LISTEN, UDP_LISTEN,
+    add_bind_views, build_socket_inode_index, capability_names, fd_target_size,
+    file_match_for_inode, file_match_for_path, file_match_under, is_full_capability_set,
+    is_offline, list_pids, parent_chain, parse_dev_hex, pid_dir_link_matches, pid_has_mmap_match,
+    proto_label_and_sort, read_cgroup_unit_best_effort, read_comm_best_effort,
+    read_exe_best_effort, read_fd_targets_access, read_interface_index, read_ns_ids,
+    read_proc_maps_access, read_proc_net_unix, read_proc_status_access, scan_pid_open_fd_socket,
+    set_command_source, set_id_match, set_proc_root, set_unknown_label, socket_state_label,
+    task_comms_best_effort, CgroupUnit, Comm, CommandSource, DirLink, FdIdCache, FileMatch,
+    IdMatch, InterfaceIndex, NsIds, ProcAccess, ProcNetProto, ProcNetSocketEntry, ProcNetUnixEntry,
+    ProcSnapshot, SocketTable, CAP_SYS_ADMIN, TCP_CLOSE, TCP_ESTABLISHED, TCP_LISTEN,
+    TCP_TIME_WAIT, UDP_ESTABLISHED, UDP_LISTEN,
 };
 
 const COMMAND_COL_WIDTH: usize = 16;
 
+/// Clear the terminal and home the cursor before each `--watch` redraw.
+const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
 #[derive(Parser, Debug)]
 #[command(
     name = "whoholds",
@@ -29,8 +48,26 @@ const COMMAND_COL_WIDTH: usize = 16;
     after_help = r#"EXAMPLES:
   whoholds /mnt/data
   whoholds 8080
+  whoholds /mnt/data 5432 6379
   whoholds --ports --listening
+  whoholds --ports --listening --show-iface
+  whoholds --deleted
+  whoholds --show-tree /var/log/app.log
+  whoholds --tree 443
+  whoholds --show-unit 443
+  whoholds --threads /dev/dri/card0
+  whoholds --stream /
+  whoholds --exclude-command node_exporter 9100
+  whoholds --exclude-zombies /var/log/app.log
   whoholds --json 8080
+
+EXIT STATUS:
+  0  success (partial results are flagged by "partial" and
+     "privilege_insufficient" in --json output)
+  1  invalid input
+  2  fatal error before the scan could start (per-process read
+     errors are counted as "errored" instead)
+  3  no holders found (only with --fail-if-empty)
 "#
 )]
 struct Args {
@@ -51,13 +88,52 @@ struct Args {
     )]
     json: bool,
 
+    #[arg(
+        long = "color",
+        value_enum,
+        value_name = "WHEN",
+        default_value = "auto",
+        help = "Color warnings, errors and headers: auto (terminal, unless NO_COLOR is set), always, or never"
+    )]
+    color: ColorChoice,
+
+    #[arg(
+        long = "include-host",
+        requires = "json",
+        help = "Add a host block (hostname, boot_id) to JSON output"
+    )]
+    include_host: bool,
+
+    #[arg(
+        long = "no-mode-message",
+        requires = "json",
+        help = "Omit the human-oriented mode_message field from JSON output"
+    )]
+    no_mode_message: bool,
+
+    #[arg(
+        long = "fields",
+        value_name = "LIST",
+        value_delimiter = ',',
+        requires = "json",
+        help = "Only include these comma-separated result fields in JSON output"
+    )]
+    fields: Vec<String>,
+
+    #[arg(
+        long = "list-skipped",
+        requires = "json",
+        help = "List the PIDs behind the skipped and errored counts in JSON output"
+    )]
+    list_skipped: bool,
+
     #[arg(long = "ports", help = "Scan all ports")]
     ports: bool,
 
     #[arg(
         long = "listening",
         requires = "ports",
-        conflicts_with = "established",
+        conflicts_with_all = ["established", "active"],
         help = "Filter to listening sockets (used with --ports)"
     )]
     listening: bool,
@@ -65,46 +141,295 @@ struct Args {
     #[arg(
         long = "established",
         requires = "ports",
-        conflicts_with = "listening",
-        help = "Filter to established TCP sockets (used with --ports)"
+        conflicts_with_all = ["listening", "active"],
+        help = "Filter to established TCP and connected UDP sockets (used with --ports)"
     )]
     established: bool,
 
+    #[arg(
+        long = "active",
+        requires = "ports",
+        conflicts_with_all = ["listening", "established"],
+        help = "Drop TCP sockets no process owns: TIME_WAIT, and CLOSE with no inode (used with --ports)"
+    )]
+    active: bool,
+
+    #[arg(
+        long = "remote-port",
+        value_name = "PORT",
+        requires = "ports",
+        help = "Filter to sockets whose peer port is PORT (used with --ports)"
+    )]
+    remote_port: Option<u16>,
+
+    #[arg(
+        long = "bind",
+        value_name = "ADDR",
+        requires = "ports",
+        help = "Filter to sockets bound to local address ADDR; 0.0.0.0 or :: for wildcard binds, fe80::1%eth0 for a link-local address (used with --ports)"
+    )]
+    bind: Option<BindAddr>,
+
+    #[arg(
+        long = "show-iface",
+        requires = "ports",
+        help = "Show the network interface owning each local address (used with --ports)"
+    )]
+    show_iface: bool,
+
+    #[arg(
+        long = "queues",
+        requires = "ports",
+        help = "Show the accept queue of TCP listeners and flag nearly full ones (used with --ports)"
+    )]
+    queues: bool,
+
+    #[arg(
+        long = "deleted",
+        conflicts_with_all = ["ports", "targets"],
+        help = "List unlinked files still held open or mapped, largest first"
+    )]
+    deleted: bool,
+
+    #[arg(
+        long = "show-tree",
+        help = "Show the parent process chain of each holder"
+    )]
+    show_tree: bool,
+
+    #[arg(
+        long = "tree",
+        conflicts_with_all = ["ports", "deleted", "stream", "show_tree"],
+        help = "Print holders indented under their parent processes (JSON: ppid per holder)"
+    )]
+    tree: bool,
+
+    #[arg(
+        long = "show-unit",
+        help = "Show the container, pod or systemd unit of each holder (best effort, from cgroups)"
+    )]
+    show_unit: bool,
+
+    #[arg(
+        long = "threads",
+        help = "List the threads (TID and per-thread name) of each holder"
+    )]
+    threads: bool,
+
+    #[arg(
+        long = "format",
+        value_enum,
+        value_name = "FORMAT",
+        default_value = "human",
+        conflicts_with_all = ["json", "stream"],
+        help = "Human output style: human (tables) or stable (sorted tab-separated lines for diff)"
+    )]
+    format: OutputFormat,
+
+    #[arg(
+        long = "no-fd",
+        conflicts_with_all = ["no_mmap", "ports"],
+        help = "Skip the open-fd scan; paths are matched by memory mappings only"
+    )]
+    no_fd: bool,
+
+    #[arg(
+        long = "no-mmap",
+        conflicts_with = "ports",
+        help = "Skip the memory-map scan; paths are matched by open fds only"
+    )]
+    no_mmap: bool,
+
+    #[arg(
+        long = "caps",
+        help = "Show each holder's effective capabilities, flagging CAP_SYS_ADMIN"
+    )]
+    caps: bool,
+
+    #[arg(
+        long = "stream",
+        conflicts_with_all = ["json", "ports", "deleted"],
+        help = "Print each holder as soon as it is found (unsorted; human output only)"
+    )]
+    stream: bool,
+
+    #[arg(
+        long = "watch",
+        value_name = "SECS",
+        value_parser = clap::value_parser!(u64).range(1..),
+        conflicts_with_all = ["ports", "deleted", "stream"],
+        help = "Re-check the targets every SECS seconds until nothing holds them (Ctrl-C stops)"
+    )]
+    watch: Option<u64>,
+
+    #[arg(
+        long = "redraw-interval",
+        value_name = "MS",
+        default_value_t = 100,
+        help = "Minimum milliseconds between terminal updates in --watch and --stream"
+    )]
+    redraw_interval: u64,
+
+    #[arg(
+        long = "glob",
+        conflicts_with_all = ["ports", "deleted"],
+        help = "Treat each TARGET as a shell glob of paths (e.g. '/var/lib/app/*.db')"
+    )]
+    glob: bool,
+
+    #[arg(
+        long = "under",
+        conflicts_with_all = ["ports", "deleted"],
+        help = "For a directory TARGET, match any file or directory beneath it (same device only)"
+    )]
+    under: bool,
+
+    #[arg(
+        long = "by-inode",
+        value_name = "MAJ:MIN:INODE",
+        conflicts_with_all = ["ports", "deleted", "proc_root"],
+        help = "Also find holders of the file with this device (hex, as in /proc/<pid>/maps) and inode, e.g. 08:01:1234"
+    )]
+    by_inode: Vec<String>,
+
+    #[arg(
+        long = "unknown-label",
+        value_name = "LABEL",
+        help = "Text shown for a process name that cannot be read (default <unknown>)"
+    )]
+    unknown_label: Option<String>,
+
+    #[arg(
+        long = "command-source",
+        value_enum,
+        value_name = "SOURCE",
+        default_value = "cmdline",
+        help = "Where command names come from: cmdline (comm, completed from argv[0] when truncated), comm or argv"
+    )]
+    command_source: CommandSource,
+
+    #[arg(
+        long = "full-command",
+        conflicts_with = "command_source",
+        help = "Show each process's full command line instead of its name (same as --command-source argv)"
+    )]
+    full_command: bool,
+
+    #[arg(
+        long = "exclude-pid",
+        value_name = "PID",
+        help = "Drop this PID from the results (repeatable)"
+    )]
+    exclude_pid: Vec<i32>,
+
+    #[arg(
+        long = "exclude-command",
+        value_name = "NAME",
+        help = "Drop holders whose command is NAME (repeatable)"
+    )]
+    exclude_command: Vec<String>,
+
+    #[arg(
+        long = "exclude-zombies",
+        help = "Drop zombie holders instead of labelling them (zombie)"
+    )]
+    exclude_zombies: bool,
+
+    #[arg(
+        long = "fail-if-empty",
+        conflicts_with = "watch",
+        help = "Exit with status 3 when nothing holds the targets"
+    )]
+    fail_if_empty: bool,
+
+    #[arg(
+        long = "inode-only",
+        conflicts_with = "proc_root",
+        help = "Match mappings by inode alone when the device differs (overlayfs, bind mounts)"
+    )]
+    inode_only: bool,
+
+    #[arg(
+        long = "sudo",
+        conflicts_with = "proc_root",
+        help = "Re-run this command under sudo when not already root"
+    )]
+    sudo: bool,
+
+    #[arg(
+        long = "proc-root",
+        value_name = "DIR",
+        help = "Scan a captured proc tree in DIR instead of the live /proc"
+    )]
+    proc_root: Option<PathBuf>,
+
     #[arg(
         value_name = "TARGET",
-        required_unless_present_any = ["version", "info", "ports"],
-        help = "File path or port number to inspect"
+        required_unless_present_any = ["version", "info", "ports", "deleted", "by_inode"],
+        help = "File paths and/or port numbers to inspect, in one scan"
     )]
-    target: Option<String>,
+    targets: Vec<String>,
 }
 
 fn print_json_ports(
     rows: Vec<PortRow>,
-    skipped_permission_denied: usize,
-    listening: bool,
-    established: bool,
+    stats: &ScanStats,
+    filter: &PortFilter,
+    envelope: &JsonEnvelope,
 ) {
-    let partial = skipped_permission_denied > 0;
-    let payload = json!({
+    let partial = stats.partial();
+    let commands = distinct_commands(rows.iter().map(|r| (r.pid, r.command.as_str())));
+    let mut payload = json!({
         "privilege": privilege_mode(),
         "mode_message": privilege_mode_message(),
         "mode": "ports",
-        "listening": listening,
-        "established": established,
+        "listening": filter.listening,
+        "established": filter.established,
+        "active": filter.active,
         "partial": partial,
-        "skipped": skipped_permission_denied,
+        "privilege_insufficient": privilege_insufficient(stats.skipped()),
+        "skipped": stats.skipped(),
+        "errored": stats.errored(),
+        "distinct_commands": commands,
         "results": rows,
     });
-    print_json_payload(&payload);
+    if let Some(port) = filter.remote_port {
+        payload["remote_port"] = json!(port);
+    }
+    if let Some(bind) = &filter.bind {
+        payload["bind"] = json!(bind.to_string());
+    }
+    print_scan_envelope(payload, stats, envelope);
 }
 
 fn print_json_holders(
     mode: &'static str,
     target: String,
-    holders: BTreeMap<i32, (Vec<Reason>, String)>,
-    skipped_permission_denied: usize,
+    holders: BTreeMap<i32, (Vec<Reason>, Comm)>,
+    stats: &ScanStats,
+    notes: &Annotations,
+    envelope: &JsonEnvelope,
 ) {
-    let partial = skipped_permission_denied > 0;
+    let partial = stats.partial();
+    let rows = holder_rows(holders, notes);
+    let commands = distinct_commands(rows.iter().map(|r| (r.pid, r.command.as_str())));
+
+    let payload = json!({
+        "privilege": privilege_mode(),
+        "mode_message": privilege_mode_message(),
+        "mode": mode,
+        "target": target,
+        "partial": partial,
+        "privilege_insufficient": privilege_insufficient(stats.skipped()),
+        "skipped": stats.skipped(),
+        "errored": stats.errored(),
+        "distinct_commands": commands,
+        "results": rows,
+    });
+    print_scan_envelope(payload, stats, envelope);
+}
+
+fn holder_rows(holders: BTreeMap<i32, (Vec<Reason>, Comm)>, notes: &Annotations) -> Vec<HolderRow> {
     let mut rows: Vec<HolderRow> = Vec::new();
 
     for (pid, (reasons, comm)) in holders {
@@ -117,36 +442,70 @@ fn print_json_holders(
             pid,
             command: comm,
             reason: reason_str,
+            exe: read_exe_best_effort(pid),
+            notes: notes.for_pid(pid),
+            ppid: notes.ppids.get(&pid).copied(),
         });
     }
 
-    let payload = json!({
-        "privilege": privilege_mode(),
-        "mode_message": privilege_mode_message(),
-        "mode": mode,
-        "target": target,
-        "partial": partial,
-        "skipped": skipped_permission_denied,
-        "results": rows,
-    });
-    print_json_payload(&payload);
+    rows
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
 enum Reason {
     OpenFd,
     Mmap,
+    Cwd,
+    Root,
+}
+
+impl From<DirLink> for Reason {
+    fn from(link: DirLink) -> Self {
+        match link {
+            DirLink::Cwd => Reason::Cwd,
+            DirLink::Root => Reason::Root,
+        }
+    }
 }
 
 impl Reason {
+    fn kind(&self) -> ReasonKind {
+        match self {
+            Reason::OpenFd => ReasonKind::OpenFd,
+            Reason::Mmap => ReasonKind::Mmap,
+            Reason::Cwd => ReasonKind::Cwd,
+            Reason::Root => ReasonKind::Root,
+        }
+    }
+
     fn as_str(&self) -> &'static str {
         match self {
             Reason::OpenFd => "open fd",
             Reason::Mmap => "mmap",
+            Reason::Cwd => "cwd",
+            Reason::Root => "root",
         }
     }
 }
 
+/// Per-run settings shared by all scan modes.
+struct Options {
+    json: bool,
+    envelope: JsonEnvelope,
+    show_tree: bool,
+    tree: bool,
+    show_unit: bool,
+    threads: bool,
+    caps: bool,
+    scopes: ScanScopes,
+    format: OutputFormat,
+    stream: bool,
+    redraw_interval: Duration,
+    exclude_zombies: bool,
+    exclusions: Exclusions,
+    under: bool,
+}
+
 fn main() {
     let json_requested = std::env::args().any(|a| a == "--json");
 
@@ -155,63 +514,256 @@ fn main() {
         Err(e) => {
             if matches!(e.kind(), ErrorKind::DisplayHelp | ErrorKind::DisplayVersion) {
                 let _ = e.print();
-                std::process::exit(0);
+                ExitCode::Success.exit();
             }
             if json_requested {
                 print_json_error(AppError::InvalidInput(e.to_string()));
             } else {
                 let _ = e.print();
             }
-            std::process::exit(1);
+            ExitCode::InvalidInput.exit();
         }
     };
 
     match run(args) {
-        Ok(()) => {}
-        Err(AppError::InvalidInput(e)) => {
-            if json_requested {
-                print_json_error(AppError::InvalidInput(e));
-            } else {
-                error(&e);
-            }
-            std::process::exit(1);
-        }
-        Err(AppError::Fatal(e)) => {
-            if json_requested {
-                print_json_error(AppError::Fatal(e));
-            } else {
-                error(&e);
-            }
-            std::process::exit(2);
-        }
+        Ok(ExitCode::Success) => {}
+        Ok(code) => code.exit(),
+        Err(e) => exit_with_error(e, json_requested),
     }
 }
 
-fn run(args: Args) -> Result<(), AppError> {
+fn run(args: Args) -> Result<ExitCode, AppError> {
+    set_color_choice(args.color);
+
     if args.version {
         print_version();
-        return Ok(());
+        return Ok(ExitCode::Success);
     }
 
     if args.info {
         print_info();
-        return Ok(());
+        return Ok(ExitCode::Success);
+    }
+
+    if args.sudo {
+        reexec_with_sudo()?;
+    }
+
+    if let Some(root) = &args.proc_root {
+        set_proc_root(root).map_err(|e| AppError::InvalidInput(e.to_string()))?;
     }
 
+    if args.inode_only {
+        set_id_match(IdMatch::InodeOnly);
+    }
+
+    if let Some(label) = &args.unknown_label {
+        set_unknown_label(label);
+    }
+    set_command_source(if args.full_command {
+        CommandSource::Argv
+    } else {
+        args.command_source
+    });
+
+    let known_fields = if args.ports {
+        PORT_FIELDS
+    } else if args.deleted {
+        DELETED_FIELDS
+    } else {
+        HOLDER_FIELDS
+    };
+    validate_fields(&args.fields, known_fields)?;
+
+    let scopes = ScanScopes::new(args.no_fd, args.no_mmap);
+    let opts = Options {
+        json: args.json,
+        envelope: JsonEnvelope {
+            include_host: args.include_host,
+            omit_mode_message: args.no_mode_message,
+            fields: args.fields.clone(),
+            list_skipped: args.list_skipped,
+            scopes: (!args.ports).then_some(scopes),
+        },
+        show_tree: args.show_tree,
+        tree: args.tree,
+        show_unit: args.show_unit,
+        threads: args.threads,
+        caps: args.caps,
+        scopes,
+        format: args.format,
+        stream: args.stream,
+        redraw_interval: Duration::from_millis(args.redraw_interval),
+        exclude_zombies: args.exclude_zombies,
+        exclusions: Exclusions::new(&args.exclude_pid, &args.exclude_command),
+        under: args.under,
+    };
+
     if args.ports {
-        return whoholds_ports(args.listening, args.established, args.json);
+        let filter = PortFilter {
+            listening: args.listening,
+            established: args.established,
+            active: args.active,
+            remote_port: args.remote_port,
+            bind: args.bind.clone(),
+        };
+        let found = whoholds_ports(
+            &filter,
+            args.show_iface,
+            args.queues,
+            &opts,
+            &mut SocketTable::default(),
+        )?;
+        return Ok(ExitCode::for_scan(found, args.fail_if_empty));
+    }
+
+    if args.deleted {
+        let found = whoholds_deleted(&opts)?;
+        return Ok(ExitCode::for_scan(found, args.fail_if_empty));
+    }
+
+    if args.targets.is_empty() && args.by_inode.is_empty() {
+        return Err(AppError::InvalidInput("missing target".to_string()));
+    }
+    let mut targets = if args.glob {
+        glob_targets(&args.targets)?
+    } else {
+        args.targets.iter().map(|t| Target::parse(t)).collect()
+    };
+    for spec in &args.by_inode {
+        targets.push(parse_inode_target(spec)?);
+    }
+    if !scopes.fd {
+        if let Some(port) = targets.iter().find(|t| matches!(t, Target::Port(_))) {
+            return Err(AppError::InvalidInput(format!(
+                "--no-fd cannot be used with port target {port}: sockets are found through open fds"
+            )));
+        }
     }
+    if let Some(secs) = args.watch {
+        watch_targets(targets, Duration::from_secs(secs), &opts)?;
+        return Ok(ExitCode::Success);
+    }
+    let found = whoholds_targets(targets, &opts, &mut SocketTable::default())?;
+    Ok(ExitCode::for_scan(found, args.fail_if_empty))
+}
 
-    let target = args
-        .target
-        .ok_or_else(|| AppError::InvalidInput("missing target".to_string()))?;
+/// Parse a `--by-inode` spec, `MAJ:MIN:INODE` with the device in hex as
+/// /proc/<pid>/maps and audit records print it (`08:01:1234`).
+fn parse_inode_target(spec: &str) -> Result<Target, AppError> {
+    let malformed = || {
+        AppError::InvalidInput(format!(
+            "invalid --by-inode {spec:?}: expected MAJ:MIN:INODE (hex device, e.g. 08:01:1234)"
+        ))
+    };
+    let (dev, inode) = spec.rsplit_once(':').ok_or_else(malformed)?;
+    if dev.matches(':').count() != 1 {
+        return Err(malformed());
+    }
+    let (major, minor) = parse_dev_hex(dev).ok_or_else(malformed)?;
+    let inode = inode.parse::<u64>().map_err(|_| malformed())?;
+    Ok(Target::Inode {
+        major,
+        minor,
+        inode,
+    })
+}
 
-    if let Ok(port) = target.parse::<u16>() {
-        return whoholds_port(port, args.json);
+/// Expand `--glob` patterns into one path target per match (each path once).
+fn glob_targets(patterns: &[String]) -> Result<Vec<Target>, AppError> {
+    let mut seen = HashSet::new();
+    let mut targets = Vec::new();
+    for pattern in patterns {
+        let paths = if is_glob_pattern(pattern) {
+            expand_glob(pattern)
+        } else {
+            vec![PathBuf::from(pattern)]
+        };
+        if paths.is_empty() {
+            return Err(AppError::InvalidInput(format!(
+                "glob matched nothing: {pattern}"
+            )));
+        }
+        for path in paths {
+            if seen.insert(path.clone()) {
+                targets.push(Target::Path(path));
+            }
+        }
     }
+    Ok(targets)
+}
+
+/// Result keys accepted by `--fields`, per mode.
+const PORT_FIELDS: &[&str] = &[
+    "port",
+    "proto",
+    "address",
+    "pid",
+    "command",
+    "state",
+    "iface",
+    "queue",
+    "unit",
+    "zombie",
+    "same_ns",
+    "ns",
+    "caps",
+    "threads",
+    "ancestors",
+];
+const HOLDER_FIELDS: &[&str] = &[
+    "pid",
+    "command",
+    "reason",
+    "exe",
+    "unit",
+    "zombie",
+    "same_ns",
+    "ns",
+    "caps",
+    "threads",
+    "ancestors",
+    "ppid",
+];
+const DELETED_FIELDS: &[&str] = &[
+    "pid",
+    "command",
+    "path",
+    "size",
+    "reason",
+    "unit",
+    "zombie",
+    "same_ns",
+    "ns",
+    "caps",
+    "threads",
+    "ancestors",
+];
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
 
-    let path = PathBuf::from(&target);
-    whoholds_path(&path, args.json)
+/// Per-holder annotations every row kind carries, filled from
+/// [`Annotations`] once the holders are known.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+struct HolderAnnotations {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unit: Option<UnitRow>,
+    #[serde(skip_serializing_if = "is_false")]
+    zombie: bool,
+    /// `false` for a holder in another mount or network namespace (a
+    /// container); omitted when either namespace is unreadable.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    same_ns: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ns: Option<NsIds>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    caps: Option<CapsRow>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    threads: Option<Vec<ThreadRow>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ancestors: Option<Vec<AncestorRow>>,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Serialize)]
@@ -220,374 +772,2179 @@ struct PortRow {
     proto: &'static str,
     #[serde(skip_serializing)]
     proto_sort: u8,
+    /// Local address in canonical form (v4-mapped IPv6 as IPv4).
+    address: String,
+    /// [`address_label`] of the local address, for the human table.
+    #[serde(skip_serializing)]
+    label: String,
     pid: i32,
-    command: String,
+    command: Comm,
     state: String,
+    /// Interface owning the local address; `*` for wildcard binds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    iface: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    queue: Option<QueueRow>,
+    #[serde(flatten)]
+    notes: HolderAnnotations,
 }
 
+/// Accept queue fill (in percent of the backlog) at which a listener is
+/// flagged as nearly full.
+const QUEUE_WARN_PERCENT: u32 = 80;
+
+/// Accept queue of a TCP listener (`--queues`), from the `rx_queue` and
+/// `tx_queue` columns of /proc/net/tcp. A full queue means the process is
+/// not accepting fast enough and new connections are being dropped.
 #[derive(Clone, Debug, Eq, PartialEq, Serialize)]
-struct HolderRow {
-    pid: i32,
-    command: String,
-    reason: String,
+struct QueueRow {
+    accept: u32,
+    backlog: u32,
+    saturated: bool,
 }
 
-fn whoholds_ports(listening: bool, established: bool, json_out: bool) -> Result<(), AppError> {
-    let mut sockets = read_proc_net_sockets().map_err(|e| AppError::Fatal(e.to_string()))?;
-
-    sockets.retain(|s| {
-        if listening {
-            if matches!(s.proto, ProcNetProto::Tcp | ProcNetProto::Tcp6) {
-                return s.state == TCP_LISTEN;
-            }
-            if matches!(s.proto, ProcNetProto::Udp | ProcNetProto::Udp6) {
-                return s.state == UDP_LISTEN;
-            }
-            return false;
+impl QueueRow {
+    /// Only TCP listeners have an accept queue.
+    fn for_socket(s: &ProcNetSocketEntry) -> Option<Self> {
+        let tcp = matches!(s.proto, ProcNetProto::Tcp | ProcNetProto::Tcp6);
+        if !tcp || s.state != TCP_LISTEN {
+            return None;
         }
-        if established {
-            return matches!(s.proto, ProcNetProto::Tcp | ProcNetProto::Tcp6)
-                && s.state == TCP_ESTABLISHED;
-        }
-        true
-    });
-
-    let target_inodes: HashSet<u64> = sockets.iter().map(|s| s.inode).collect();
+        Some(QueueRow::new(s.rx_queue, s.tx_queue))
+    }
 
-    let mut inode_to_pids: BTreeMap<u64, Vec<i32>> = BTreeMap::new();
-    let mut skipped_permission_denied: HashSet<i32> = HashSet::new();
+    fn new(accept: u32, backlog: u32) -> Self {
+        let fill = u64::from(accept) * 100;
+        QueueRow {
+            accept,
+            backlog,
+            saturated: accept > 0 && fill >= u64::from(backlog) * u64::from(QUEUE_WARN_PERCENT),
+        }
+    }
 
-    if target_inodes.is_empty() {
-        if json_out {
-            print_json_ports(
-                Vec::new(),
-                skipped_permission_denied.len(),
-                listening,
-                established,
-            );
+    fn label(&self) -> String {
+        let fill = format!("{}/{} pending", self.accept, self.backlog);
+        if self.saturated {
+            format!("{fill}   <- accept queue nearly full")
         } else {
-            print_ports(Vec::new(), skipped_permission_denied.len());
+            fill
         }
-        return Ok(());
     }
+}
 
-    let pids = list_pids().map_err(|e| AppError::Fatal(e.to_string()))?;
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+struct HolderRow {
+    pid: i32,
+    command: Comm,
+    reason: String,
+    /// Executable path (best effort; omitted when unreadable).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exe: Option<String>,
+    #[serde(flatten)]
+    notes: HolderAnnotations,
+    /// Parent pid (`--tree`); omitted when unreadable.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ppid: Option<i32>,
+}
 
-    let mut comm_cache: HashMap<i32, String> = HashMap::new();
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+struct DeletedRow {
+    pid: i32,
+    command: Comm,
+    path: String,
+    /// `None` when only mapped: the size is recoverable through an fd only.
+    size: Option<u64>,
+    reason: String,
+    #[serde(flatten)]
+    notes: HolderAnnotations,
+}
 
-    for pid in pids {
-        let links = match read_fd_links_access(pid) {
-            ProcAccess::Ok(v) => v,
-            ProcAccess::PermissionDenied => {
-                skipped_permission_denied.insert(pid);
-                continue;
-            }
-            ProcAccess::Gone => continue,
-            ProcAccess::Fatal(e) => return Err(AppError::Fatal(e.to_string())),
-        };
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+struct AncestorRow {
+    pid: i32,
+    command: Comm,
+}
 
-        for (_fd, _fd_path, link) in links {
-            let Some(inode) = parse_socket_inode(&link) else {
-                continue;
-            };
-            if !target_inodes.contains(&inode) {
-                continue;
-            }
+/// One thread of a holder (`--threads`). Maps and fds are per process, so
+/// this names the threads rather than pinning the hold on one of them.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+struct ThreadRow {
+    tid: i32,
+    command: String,
+}
 
-            comm_cache
-                .entry(pid)
-                .or_insert_with(|| match read_comm_access(pid) {
-                    ProcAccess::Ok(s) => s,
-                    ProcAccess::PermissionDenied | ProcAccess::Gone | ProcAccess::Fatal(_) => {
-                        "<unknown>".to_string()
-                    }
-                });
-            inode_to_pids.entry(inode).or_default().push(pid);
-        }
-    }
+fn thread_rows(pid: i32) -> Vec<ThreadRow> {
+    task_comms_best_effort(pid)
+        .into_iter()
+        .map(|(tid, command)| ThreadRow { tid, command })
+        .collect()
+}
 
-    for pids in inode_to_pids.values_mut() {
-        pids.sort_unstable();
-        pids.dedup();
+fn format_threads(threads: &[ThreadRow]) -> String {
+    if threads.is_empty() {
+        return "<unreadable>".to_string();
     }
+    threads
+        .iter()
+        .map(|t| format!("{} {}", t.tid, t.command))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
 
-    let mut rows: Vec<PortRow> = Vec::new();
+/// Effective capabilities of a holder (`--caps`), from `CapEff` in
+/// /proc/<pid>/status.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+struct CapsRow {
+    #[serde(skip_serializing)]
+    mask: u64,
+    /// The raw mask as hex, e.g. `0x000001ffffffffff`.
+    effective: String,
+    names: Vec<String>,
+    sys_admin: bool,
+}
 
-    for s in sockets {
-        let Some(pids) = inode_to_pids.get(&s.inode) else {
+impl CapsRow {
+    fn from_mask(mask: u64) -> Self {
+        CapsRow {
+            mask,
+            effective: format!("{mask:#018x}"),
+            names: capability_names(mask),
+            sys_admin: mask & (1 << CAP_SYS_ADMIN) != 0,
+        }
+    }
+
+    /// `all`, `none` or the names; CAP_SYS_ADMIN is called out since it is
+    /// close to root on its own.
+    fn label(&self) -> String {
+        let list = if is_full_capability_set(self.mask) {
+            "all".to_string()
+        } else if self.names.is_empty() {
+            "none".to_string()
+        } else {
+            self.names.join(", ")
+        };
+        if self.sys_admin {
+            format!("{list}   <- CAP_SYS_ADMIN")
+        } else {
+            list
+        }
+    }
+}
+
+/// Container, pod or systemd unit owning a holder, guessed from its cgroup
+/// path (`--show-unit`). `kind` is `unknown` when nothing matched.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+struct UnitRow {
+    kind: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+}
+
+impl UnitRow {
+    fn from_cgroup(unit: &CgroupUnit) -> Self {
+        UnitRow {
+            kind: unit.kind(),
+            name: unit.name().map(str::to_string),
+        }
+    }
+
+    fn label(&self) -> String {
+        match &self.name {
+            None => "unknown".to_string(),
+            Some(name) if self.kind == "systemd" => name.clone(),
+            Some(name) => format!("{}:{name}", self.kind),
+        }
+    }
+}
+
+const UNIT_COL_WIDTH: usize = 20;
+const UNIT_HEADER: &str = "CONTAINER/UNIT";
+
+/// The `CONTAINER/UNIT` header cell, or nothing when the column is off.
+fn unit_header(show: bool) -> String {
+    if show {
+        format!(" {UNIT_HEADER:<UNIT_COL_WIDTH$}")
+    } else {
+        String::new()
+    }
+}
+
+fn unit_cell(unit: Option<&UnitRow>, show: bool) -> String {
+    if !show {
+        return String::new();
+    }
+    format!(" {:<UNIT_COL_WIDTH$}", unit_label(unit))
+}
+
+fn unit_label(unit: Option<&UnitRow>) -> String {
+    unit.map_or_else(|| "unknown".to_string(), UnitRow::label)
+}
+
+/// `before`, then the CONTAINER/UNIT column when shown, then `after`.
+fn with_unit_column<S: Into<String>>(
+    before: impl IntoIterator<Item = S>,
+    unit: Option<S>,
+    after: impl IntoIterator<Item = S>,
+) -> Vec<String> {
+    before
+        .into_iter()
+        .chain(unit)
+        .chain(after)
+        .map(Into::into)
+        .collect()
+}
+
+/// Per-holder details resolved after the scan: parent chains (only with
+/// `--show-tree`), the process forest (only with `--tree`), units (only with
+/// `--show-unit`), threads (only with `--threads`) and which holders are
+/// zombies.
+#[derive(Debug, Default)]
+struct Annotations {
+    tree: HashMap<i32, Vec<AncestorRow>>,
+    forest: Option<ProcessForest>,
+    ppids: HashMap<i32, i32>,
+    threads: HashMap<i32, Vec<ThreadRow>>,
+    units: HashMap<i32, UnitRow>,
+    caps: HashMap<i32, CapsRow>,
+    zombies: HashSet<i32>,
+    hide_zombies: bool,
+    namespaces: HashMap<i32, NsIds>,
+    /// Namespaces of this process, to compare holders against.
+    own_ns: Option<NsIds>,
+}
+
+impl Annotations {
+    fn ancestors(&self, pid: i32) -> Option<Vec<AncestorRow>> {
+        self.tree.get(&pid).cloned()
+    }
+
+    fn threads(&self, pid: i32) -> Option<Vec<ThreadRow>> {
+        self.threads.get(&pid).cloned()
+    }
+
+    fn unit(&self, pid: i32) -> Option<UnitRow> {
+        self.units.get(&pid).cloned()
+    }
+
+    fn caps(&self, pid: i32) -> Option<CapsRow> {
+        self.caps.get(&pid).cloned()
+    }
+
+    fn is_zombie(&self, pid: i32) -> bool {
+        self.zombies.contains(&pid)
+    }
+
+    fn ns(&self, pid: i32) -> Option<NsIds> {
+        self.namespaces.get(&pid).copied()
+    }
+
+    /// Whether `pid` shares this process's mount and network namespaces;
+    /// `None` when either side could not be read.
+    fn same_ns(&self, pid: i32) -> Option<bool> {
+        Some(self.own_ns?.same_mnt_net(self.namespaces.get(&pid)?))
+    }
+
+    /// A holder in another mount or network namespace, e.g. a container.
+    fn in_container(&self, pid: i32) -> bool {
+        self.same_ns(pid) == Some(false)
+    }
+
+    /// Everything known about `pid`, for its result row.
+    fn for_pid(&self, pid: i32) -> HolderAnnotations {
+        HolderAnnotations {
+            unit: self.unit(pid),
+            zombie: self.is_zombie(pid),
+            same_ns: self.same_ns(pid),
+            ns: self.ns(pid),
+            caps: self.caps(pid),
+            threads: self.threads(pid),
+            ancestors: self.ancestors(pid),
+        }
+    }
+
+    /// Whether `--exclude-zombies` drops this holder.
+    fn hides(&self, pid: i32) -> bool {
+        self.hide_zombies && self.is_zombie(pid)
+    }
+}
+
+fn annotate(opts: &Options, pids: impl IntoIterator<Item = i32>) -> Annotations {
+    let own_ns = match read_ns_ids(std::process::id() as i32) {
+        ProcAccess::Ok(ids) => Some(ids),
+        ProcAccess::PermissionDenied | ProcAccess::Gone | ProcAccess::Fatal(_) => None,
+    };
+    let mut notes = Annotations {
+        hide_zombies: opts.exclude_zombies,
+        own_ns,
+        ..Annotations::default()
+    };
+
+    let pids: HashSet<i32> = pids.into_iter().collect();
+    // Holder -> parent_chain, for --tree.
+    let mut chains: Vec<(i32, Vec<i32>)> = Vec::new();
+    for &pid in &pids {
+        // A zombie thread-group leader whose other threads still run keeps
+        // the process's fds and mappings alive, so it can show up here.
+        if let ProcAccess::Ok(status) = read_proc_status_access(pid) {
+            if status.is_zombie() {
+                notes.zombies.insert(pid);
+            }
+            if opts.caps {
+                notes.caps.insert(pid, CapsRow::from_mask(status.cap_eff));
+            }
+        }
+
+        if let ProcAccess::Ok(ids) = read_ns_ids(pid) {
+            notes.namespaces.insert(pid, ids);
+        }
+
+        if opts.threads {
+            notes.threads.insert(pid, thread_rows(pid));
+        }
+
+        if opts.show_unit {
+            let unit = UnitRow::from_cgroup(&read_cgroup_unit_best_effort(pid));
+            notes.units.insert(pid, unit);
+        }
+
+        // One parent walk serves both --show-tree and --tree.
+        if opts.show_tree || opts.tree {
+            let chain = parent_chain(pid);
+            if opts.show_tree {
+                let rows = chain
+                    .iter()
+                    .map(|&ppid| AncestorRow {
+                        pid: ppid,
+                        command: read_comm_best_effort(ppid),
+                    })
+                    .collect();
+                notes.tree.insert(pid, rows);
+            }
+            if opts.tree {
+                if let Some(&ppid) = chain.first() {
+                    notes.ppids.insert(pid, ppid);
+                }
+                chains.push((pid, chain));
+            }
+        }
+    }
+
+    if opts.tree {
+        let holders: BTreeSet<i32> = pids.iter().copied().collect();
+        let mut forest = ProcessForest::build(chains);
+        for &pid in forest.parents.keys() {
+            if !holders.contains(&pid) {
+                forest.commands.insert(pid, read_comm_best_effort(pid));
+            }
+        }
+        notes.forest = Some(forest);
+    }
+
+    notes
+}
+
+/// `--tree`: the holders and their ancestors up to init, each with its
+/// parent (`None` for a root or an unreadable parent).
+#[derive(Debug, Default)]
+struct ProcessForest {
+    parents: BTreeMap<i32, Option<i32>>,
+    /// Commands of ancestors that are not holders themselves.
+    commands: HashMap<i32, Comm>,
+}
+
+impl ProcessForest {
+    /// Link each holder to its [`parent_chain`] (nearest first), which
+    /// already stops at init, an unreadable parent, a cycle or its depth
+    /// limit. The last ancestor of a chain is a root unless another chain
+    /// gives it a parent.
+    fn build(chains: impl IntoIterator<Item = (i32, Vec<i32>)>) -> Self {
+        let mut parents: BTreeMap<i32, Option<i32>> = BTreeMap::new();
+        for (holder, chain) in chains {
+            let mut child = holder;
+            for ancestor in chain {
+                parents.insert(child, Some(ancestor));
+                child = ancestor;
+            }
+            parents.entry(child).or_insert(None);
+        }
+        ProcessForest {
+            parents,
+            commands: HashMap::new(),
+        }
+    }
+
+    /// Every pid once, depth first with children in pid order, paired with
+    /// its depth. A pid whose parent was never placed is a root; pids left
+    /// over in a cycle are started from the lowest one.
+    fn walk(&self) -> Vec<(usize, i32)> {
+        let mut children: BTreeMap<i32, Vec<i32>> = BTreeMap::new();
+        let mut roots = Vec::new();
+        for (&pid, &parent) in &self.parents {
+            match parent.filter(|p| self.parents.contains_key(p)) {
+                Some(p) => children.entry(p).or_default().push(pid),
+                None => roots.push(pid),
+            }
+        }
+
+        let mut out = Vec::new();
+        let mut seen = HashSet::new();
+        for start in roots.into_iter().chain(self.parents.keys().copied()) {
+            let mut stack = vec![(0, start)];
+            while let Some((depth, pid)) = stack.pop() {
+                if !seen.insert(pid) {
+                    continue;
+                }
+                out.push((depth, pid));
+                if let Some(kids) = children.get(&pid) {
+                    stack.extend(kids.iter().rev().map(|&kid| (depth + 1, kid)));
+                }
+            }
+        }
+        out
+    }
+}
+
+/// The command cell: `(zombie)` for an exited holder, `[container]` for
+/// one in another mount or network namespace.
+fn display_command(command: &str, zombie: bool, container: bool) -> String {
+    let mut out = command.to_string();
+    if zombie {
+        out.push_str(" (zombie)");
+    }
+    if container {
+        out.push_str(" [container]");
+    }
+    out
+}
+
+fn format_ancestors(ancestors: &[AncestorRow]) -> String {
+    if ancestors.is_empty() {
+        return "<none>".to_string();
+    }
+    ancestors
+        .iter()
+        .map(|a| format!("{} {}", a.pid, a.command))
+        .collect::<Vec<_>>()
+        .join(" <- ")
+}
+
+/// `--bind ADDR[%ZONE]`: sockets bound to exactly this local address.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct BindAddr {
+    /// Canonical form: v4-mapped IPv6 addresses become IPv4.
+    ip: IpAddr,
+    /// Zone of a scoped IPv6 address (`fe80::1%eth0`). /proc/net records no
+    /// scope id, so it is compared with the interface owning the address.
+    zone: Option<String>,
+}
+
+impl BindAddr {
+    /// Whether a socket bound to `ip` matches; the IPv4 and IPv6 wildcards
+    /// match each other, and v4-mapped addresses match their IPv4 form.
+    fn matches_ip(&self, ip: IpAddr) -> bool {
+        let ip = ip.to_canonical();
+        if self.ip.is_unspecified() {
+            ip.is_unspecified()
+        } else {
+            ip == self.ip
+        }
+    }
+
+    fn matches_zone(&self, ip: IpAddr, ifaces: Option<&InterfaceIndex>) -> bool {
+        match &self.zone {
+            None => true,
+            Some(zone) => ifaces.and_then(|index| index.interface_for(ip)) == Some(zone.as_str()),
+        }
+    }
+}
+
+impl std::str::FromStr for BindAddr {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, zone) = match s.split_once('%') {
+            Some((addr, zone)) => (addr, Some(zone)),
+            None => (s, None),
+        };
+        let addr = addr
+            .strip_prefix('[')
+            .and_then(|a| a.strip_suffix(']'))
+            .unwrap_or(addr);
+        let ip: IpAddr = addr
+            .parse()
+            .map_err(|_| format!("{s}: not an IPv4 or IPv6 address"))?;
+        let zone = match zone {
+            None => None,
+            Some("") => return Err(format!("{s}: empty zone")),
+            Some(_) if !ip.is_ipv6() => return Err(format!("{s}: a zone needs an IPv6 address")),
+            Some(zone) => Some(zone.to_string()),
+        };
+        Ok(BindAddr {
+            ip: ip.to_canonical(),
+            zone,
+        })
+    }
+}
+
+impl fmt::Display for BindAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.zone {
+            Some(zone) => write!(f, "{}%{zone}", self.ip),
+            None => write!(f, "{}", self.ip),
+        }
+    }
+}
+
+/// Local address shown in the ADDRESS column: `*` for wildcard binds and
+/// `localhost` for 127.0.0.1 / ::1, so one service bound on both address
+/// families collapses to a single row; v4-mapped addresses in IPv4 form;
+/// IPv6 link-local addresses with `%iface` when the interface is known.
+fn address_label(ip: IpAddr, ifaces: Option<&InterfaceIndex>) -> String {
+    let ip = ip.to_canonical();
+    if ip.is_unspecified() {
+        return "*".to_string();
+    }
+    if ip == IpAddr::from([127, 0, 0, 1]) || ip == IpAddr::from(std::net::Ipv6Addr::LOCALHOST) {
+        return "localhost".to_string();
+    }
+    match ip {
+        IpAddr::V6(v6) if v6.is_unicast_link_local() => {
+            match ifaces.and_then(|index| index.interface_for(ip)) {
+                Some(iface) => format!("{v6}%{iface}"),
+                None => v6.to_string(),
+            }
+        }
+        _ => ip.to_string(),
+    }
+}
+
+fn is_link_local_v6(ip: IpAddr) -> bool {
+    matches!(ip.to_canonical(), IpAddr::V6(v6) if v6.is_unicast_link_local())
+}
+
+/// Which sockets `--ports` lists.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+struct PortFilter {
+    listening: bool,
+    established: bool,
+    /// `--active`: drop TCP sockets no process owns: TIME_WAIT, and CLOSE
+    /// with inode 0. A socket that is bound but not listening (or not yet
+    /// connected) is also CLOSE, but still has an inode and an owner.
+    active: bool,
+    remote_port: Option<u16>,
+    bind: Option<BindAddr>,
+}
+
+impl PortFilter {
+    fn keeps(&self, s: &ProcNetSocketEntry) -> bool {
+        if self.remote_port.is_some_and(|p| p != s.remote_port) {
+            return false;
+        }
+        if self
+            .bind
+            .as_ref()
+            .is_some_and(|b| !b.matches_ip(s.local_addr))
+        {
+            return false;
+        }
+        let tcp = matches!(s.proto, ProcNetProto::Tcp | ProcNetProto::Tcp6);
+        if self.listening {
+            return s.state == if tcp { TCP_LISTEN } else { UDP_LISTEN };
+        }
+        if self.established {
+            return s.state
+                == if tcp {
+                    TCP_ESTABLISHED
+                } else {
+                    UDP_ESTABLISHED
+                };
+        }
+        if self.active && tcp {
+            return match s.state {
+                TCP_TIME_WAIT => false,
+                TCP_CLOSE => s.inode != 0,
+                _ => true,
+            };
+        }
+        true
+    }
+}
+
+fn whoholds_ports(
+    filter: &PortFilter,
+    show_iface: bool,
+    queues: bool,
+    opts: &Options,
+    table: &mut SocketTable,
+) -> Result<bool, AppError> {
+    let mut sockets = table.load(warn).to_vec();
+
+    sockets.retain(|s| filter.keeps(s));
+
+    // The interface index resolves --show-iface, --bind zones, and the
+    // `%iface` suffix of link-local addresses.
+    let zoned = filter.bind.as_ref().is_some_and(|b| b.zone.is_some());
+    let ifaces = (show_iface || zoned || sockets.iter().any(|s| is_link_local_v6(s.local_addr)))
+        .then(read_interface_index);
+    if let Some(bind) = &filter.bind {
+        sockets.retain(|s| bind.matches_zone(s.local_addr, ifaces.as_ref()));
+    }
+
+    let target_inodes: HashSet<u64> = sockets.iter().map(|s| s.inode).collect();
+
+    let mut inode_to_pids: BTreeMap<u64, Vec<i32>> = BTreeMap::new();
+    let mut stats = ScanStats::default();
+
+    if target_inodes.is_empty() {
+        if opts.json {
+            print_json_ports(Vec::new(), &stats, filter, &opts.envelope);
+        } else if opts.format == OutputFormat::Stable {
+            print_stable_ports(Vec::new(), &stats);
+        } else {
+            print_ports(Vec::new(), &stats);
+        }
+        return Ok(false);
+    }
+
+    let index = build_socket_inode_index().map_err(|e| AppError::Fatal(e.to_string()))?;
+    for pid in &index.denied {
+        stats.skip(*pid);
+    }
+    for (pid, e) in &index.errored {
+        stats.error(*pid, e);
+    }
+
+    let mut comm_cache: HashMap<i32, Comm> = HashMap::new();
+    for inode in target_inodes {
+        let pids = index.pids(inode);
+        if pids.is_empty() {
+            continue;
+        }
+        for &pid in pids {
+            comm_cache
+                .entry(pid)
+                .or_insert_with(|| read_comm_best_effort(pid));
+        }
+        inode_to_pids.insert(inode, pids.to_vec());
+    }
+
+    let mut rows = build_port_rows(
+        &sockets,
+        &inode_to_pids,
+        &comm_cache,
+        ifaces.as_ref(),
+        show_iface,
+        queues,
+    );
+    sort_dedup_port_rows(&mut rows, !opts.json);
+    rows.retain(|r| !opts.exclusions.excludes(r.pid, &r.command));
+
+    let notes = annotate(opts, rows.iter().map(|r| r.pid));
+    rows.retain(|r| !notes.hides(r.pid));
+    for row in &mut rows {
+        row.notes = notes.for_pid(row.pid);
+    }
+
+    let found = !rows.is_empty();
+    if opts.json {
+        print_json_ports(rows, &stats, filter, &opts.envelope);
+    } else if opts.format == OutputFormat::Stable {
+        print_stable_ports(rows, &stats);
+    } else {
+        print_ports(rows, &stats);
+    }
+    Ok(found)
+}
+
+/// One row per (socket, holder PID); `inode_to_pids` lists each PID once per
+/// inode even when it holds the socket through several fds.
+fn build_port_rows(
+    sockets: &[ProcNetSocketEntry],
+    inode_to_pids: &BTreeMap<u64, Vec<i32>>,
+    comm_cache: &HashMap<i32, Comm>,
+    ifaces: Option<&InterfaceIndex>,
+    show_iface: bool,
+    queues: bool,
+) -> Vec<PortRow> {
+    let mut rows: Vec<PortRow> = Vec::new();
+
+    for s in sockets {
+        let Some(pids) = inode_to_pids.get(&s.inode) else {
             continue;
         };
 
         for pid in pids {
-            let command = comm_cache
-                .get(pid)
-                .cloned()
-                .unwrap_or_else(|| "<unknown>".to_string());
+            let command = comm_cache.get(pid).cloned().unwrap_or_default();
 
             let (proto, proto_sort) = proto_label_and_sort(s.proto);
+            let iface = ifaces.filter(|_| show_iface).map(|index| {
+                if s.local_addr.is_unspecified() {
+                    "*".to_string()
+                } else {
+                    index.interface_for(s.local_addr).unwrap_or("?").to_string()
+                }
+            });
 
             rows.push(PortRow {
                 port: s.local_port,
                 proto,
                 proto_sort,
+                address: s.local_addr.to_canonical().to_string(),
+                label: address_label(s.local_addr, ifaces),
                 pid: *pid,
                 command,
                 state: socket_state_label(s.proto, s.state),
+                iface,
+                queue: queues.then(|| QueueRow::for_socket(s)).flatten(),
+                notes: HolderAnnotations::default(),
             });
         }
     }
 
-    rows.sort_by_key(|a| (a.port, a.proto_sort, a.pid));
-    rows.dedup_by(|a, b| {
-        a.port == b.port && a.proto_sort == b.proto_sort && a.pid == b.pid && a.state == b.state
-    });
+    rows
+}
 
-    if json_out {
-        print_json_ports(
-            rows,
-            skipped_permission_denied.len(),
-            listening,
-            established,
-        );
-    } else {
-        print_ports(rows, skipped_permission_denied.len());
+/// Sort and collapse port rows that would print identically.
+///
+/// The dedup key is (port, protocol family, address, pid, state, iface):
+/// several sockets of one process in the same state (e.g. SO_REUSEPORT
+/// workers) yield a single row. With `by_label` the address is compared by
+/// its [`address_label`], so an IPv4 and an IPv6 socket on the wildcard or
+/// loopback address also collapse, as they print the same; JSON keeps
+/// them apart. The sort uses the full key so equal rows are adjacent
+/// regardless of /proc/net order.
+fn sort_dedup_port_rows(rows: &mut Vec<PortRow>, by_label: bool) {
+    fn key(r: &PortRow, by_label: bool) -> (u16, u8, &str, i32, &str, Option<&str>) {
+        let address = if by_label { &r.label } else { &r.address };
+        (
+            r.port,
+            r.proto_sort,
+            address,
+            r.pid,
+            &r.state,
+            r.iface.as_deref(),
+        )
     }
-    Ok(())
+    rows.sort_by(|a, b| key(a, by_label).cmp(&key(b, by_label)));
+    rows.dedup_by(|a, b| key(a, by_label) == key(b, by_label));
 }
 
-fn whoholds_path(path: &Path, json_out: bool) -> Result<(), AppError> {
-    let target_id = match file_id_for_path(path) {
-        Ok(id) => id,
-        Err(e) => {
-            let msg = format!("{}: {}", path.display(), e);
-            if e.kind() == std::io::ErrorKind::NotFound {
-                return Err(AppError::InvalidInput(msg));
-            }
-            return Err(AppError::Fatal(msg));
-        }
-    };
-    let (tmaj, tmin) = dev_major_minor(target_id.dev);
+/// Deleted paths that never occupied disk space: memfd and SysV shm objects
+/// always render with the deleted marker.
+fn is_disk_backed(path: &str) -> bool {
+    path.starts_with('/') && !path.starts_with("/memfd:") && !path.starts_with("/SYSV")
+}
 
-    let mut holders: BTreeMap<i32, (Vec<Reason>, String)> = BTreeMap::new();
-    let mut skipped_permission_denied: HashSet<i32> = HashSet::new();
+fn whoholds_deleted(opts: &Options) -> Result<bool, AppError> {
+    let mut found: BTreeMap<(i32, String), (Vec<Reason>, Option<u64>)> = BTreeMap::new();
+    let mut stats = ScanStats::default();
 
     let pids = list_pids().map_err(|e| AppError::Fatal(e.to_string()))?;
 
     for pid in pids {
-        let mut reasons: Vec<Reason> = Vec::new();
-        let mut any_denied = false;
-        let mut comm: Option<String> = None;
-
-        match scan_pid_open_fd_file(pid, target_id) {
-            ProcAccess::Ok(true) => {
-                reasons.push(Reason::OpenFd);
-                comm = Some(read_comm_best_effort(pid));
+        let fds = if opts.scopes.fd {
+            read_fd_targets_access(pid)
+        } else {
+            ProcAccess::Ok(Vec::new())
+        };
+        match fds {
+            ProcAccess::Ok(targets) => {
+                for t in targets {
+                    if !t.deleted || !is_disk_backed(&t.target) {
+                        continue;
+                    }
+                    let size = match fd_target_size(pid, t.fd) {
+                        ProcAccess::Ok(size) => Some(size),
+                        ProcAccess::PermissionDenied | ProcAccess::Gone | ProcAccess::Fatal(_) => {
+                            None
+                        }
+                    };
+                    let entry = found.entry((pid, t.target)).or_default();
+                    if !entry.0.contains(&Reason::OpenFd) {
+                        entry.0.push(Reason::OpenFd);
+                    }
+                    entry.1 = entry.1.max(size);
+                }
             }
-            ProcAccess::Ok(false) => {}
             ProcAccess::PermissionDenied => {
-                any_denied = true;
+                stats.skip(pid);
             }
             ProcAccess::Gone => continue,
             ProcAccess::Fatal(e) => {
-                return Err(AppError::Fatal(e.to_string()));
+                stats.error(pid, &e);
+                continue;
             }
         }
 
-        match scan_pid_mmap_file(pid, tmaj, tmin, target_id.inode) {
-            ProcAccess::Ok(true) => {
-                reasons.push(Reason::Mmap);
-                if comm.is_none() {
-                    comm = Some(read_comm_best_effort(pid));
+        let maps = if opts.scopes.mmap {
+            read_proc_maps_access(pid)
+        } else {
+            ProcAccess::Ok(Vec::new())
+        };
+        match maps {
+            ProcAccess::Ok(maps) => {
+                for e in maps {
+                    let Some(pathname) = e.pathname else {
+                        continue;
+                    };
+                    if !e.deleted || e.inode == 0 || !is_disk_backed(&pathname) {
+                        continue;
+                    }
+                    let entry = found.entry((pid, pathname)).or_default();
+                    if !entry.0.contains(&Reason::Mmap) {
+                        entry.0.push(Reason::Mmap);
+                    }
                 }
             }
-            ProcAccess::Ok(false) => {}
             ProcAccess::PermissionDenied => {
-                any_denied = true;
+                stats.skip(pid);
             }
             ProcAccess::Gone => continue,
             ProcAccess::Fatal(e) => {
-                return Err(AppError::Fatal(e.to_string()));
+                stats.error(pid, &e);
+                continue;
             }
         }
+    }
 
-        if reasons.is_empty() {
-            if any_denied {
-                skipped_permission_denied.insert(pid);
-            }
+    let mut comm_cache: HashMap<i32, Comm> = HashMap::new();
+    let mut rows: Vec<DeletedRow> = Vec::new();
+
+    for ((pid, path), (mut reasons, size)) in found {
+        let command = comm_cache
+            .entry(pid)
+            .or_insert_with(|| read_comm_best_effort(pid))
+            .clone();
+        if opts.exclusions.excludes(pid, &command) {
             continue;
         }
+        sort_reasons(&mut reasons, Reason::kind);
+        rows.push(DeletedRow {
+            pid,
+            command,
+            path,
+            size,
+            reason: reasons
+                .iter()
+                .map(|r| r.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+            notes: HolderAnnotations::default(),
+        });
+    }
+
+    sort_deleted_rows(&mut rows);
+
+    let notes = annotate(opts, rows.iter().map(|r| r.pid));
+    rows.retain(|r| !notes.hides(r.pid));
+    for row in &mut rows {
+        row.notes = notes.for_pid(row.pid);
+    }
+
+    let found = !rows.is_empty();
+    if opts.json {
+        print_json_deleted(rows, &stats, &opts.envelope);
+    } else if opts.format == OutputFormat::Stable {
+        print_stable_deleted(rows, &stats);
+    } else {
+        print_deleted(rows, &stats, opts.scopes);
+    }
+    Ok(found)
+}
+
+/// Largest first; rows without a known size (mapped only) go last.
+fn sort_deleted_rows(rows: &mut [DeletedRow]) {
+    rows.sort_by(|a, b| {
+        b.size
+            .cmp(&a.size)
+            .then_with(|| a.pid.cmp(&b.pid))
+            .then_with(|| a.path.cmp(&b.path))
+    });
+}
+
+fn print_json_deleted(rows: Vec<DeletedRow>, stats: &ScanStats, envelope: &JsonEnvelope) {
+    let partial = stats.partial();
+    let commands = distinct_commands(rows.iter().map(|r| (r.pid, r.command.as_str())));
+    let payload = json!({
+        "privilege": privilege_mode(),
+        "mode_message": privilege_mode_message(),
+        "mode": "deleted",
+        "partial": partial,
+        "privilege_insufficient": privilege_insufficient(stats.skipped()),
+        "skipped": stats.skipped(),
+        "errored": stats.errored(),
+        "distinct_commands": commands,
+        "results": rows,
+    });
+    print_scan_envelope(payload, stats, envelope);
+}
+
+fn print_deleted(rows: Vec<DeletedRow>, stats: &ScanStats, scopes: ScanScopes) {
+    println!("{}", privilege_mode_message());
+    scopes.print_notice();
+    stats.print_notices();
+
+    if rows.is_empty() {
+        println!("No deleted files held.");
+        return;
+    }
+
+    let commands = distinct_commands(rows.iter().map(|r| (r.pid, r.command.as_str())));
+    println!("Distinct commands: {}", format_distinct_commands(&commands));
+
+    let show_unit = rows.iter().any(|r| r.notes.unit.is_some());
+    let mut table = Table::new(with_unit_column(
+        ["PID", "COMMAND"],
+        show_unit.then_some(UNIT_HEADER),
+        ["SIZE", "REASON", "PATH"],
+    ))
+    .fixed(0)
+    .align_right(if show_unit { 3 } else { 2 })
+    .fixed(if show_unit { 3 } else { 2 });
+    for r in rows {
+        let size = r
+            .size
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| "-".to_string());
+        table.row(with_unit_column(
+            [
+                r.pid.to_string(),
+                display_command(&r.command, r.notes.zombie, r.notes.same_ns == Some(false)),
+            ],
+            show_unit.then(|| unit_label(r.notes.unit.as_ref())),
+            [size, r.reason, r.path],
+        ));
+        add_details(
+            &mut table,
+            r.notes.threads.as_deref(),
+            r.notes.caps.as_ref(),
+            r.notes.ancestors.as_deref(),
+        );
+    }
+    table.print();
+}
+
+/// What a target is matched against, prepared once before the walk.
+enum Matcher {
+    Path(FileMatch),
+    /// Inodes of the sockets bound to the port or unix socket path; empty
+    /// when nothing is bound.
+    Sockets(HashSet<u64>),
+}
+
+/// Holders found for one target of a (possibly multi-target) scan.
+struct TargetScan {
+    target: Target,
+    matcher: Matcher,
+    holders: BTreeMap<i32, (Vec<Reason>, Comm)>,
+    stats: ScanStats,
+    notes: Annotations,
+}
+
+impl TargetScan {
+    /// A port or socket path nothing is bound to cannot have holders.
+    fn needs_walk(&self) -> bool {
+        !matches!(&self.matcher, Matcher::Sockets(inodes) if inodes.is_empty())
+    }
+
+    fn scan_pid(&mut self, proc: &ProcSnapshot, fd_ids: &mut FdIdCache, scopes: ScanScopes) {
+        match &self.matcher {
+            Matcher::Path(target) => scan_pid_path(
+                proc,
+                target,
+                fd_ids,
+                scopes,
+                &mut self.holders,
+                &mut self.stats,
+            ),
+            Matcher::Sockets(inodes) => {
+                scan_pid_sockets(proc, inodes, &mut self.holders, &mut self.stats)
+            }
+        }
+    }
+}
+
+fn path_matcher(path: &Path, opts: &Options) -> Result<FileMatch, AppError> {
+    if opts.under && (is_offline() || path.is_dir()) {
+        return subtree_matcher(path, opts);
+    }
+
+    let mut target = match file_match_for_path(path) {
+        Ok(m) => m,
+        Err(e) => {
+            let msg = format!("{}: {}", path.display(), e);
+            if e.kind() == std::io::ErrorKind::NotFound {
+                return Err(AppError::InvalidInput(msg));
+            }
+            return Err(AppError::Fatal(msg));
+        }
+    };
+
+    let views = add_bind_views(path, &mut target);
+    if !views.is_empty() && !opts.json {
+        let list = views
+            .iter()
+            .map(|v| v.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        warn(&format!(
+            "{} is also reachable via bind mount(s): {list}; matching all views",
+            path.display()
+        ));
+    }
+
+    Ok(target)
+}
+
+/// `--under`: match everything beneath directory `path` on its device.
+fn subtree_matcher(path: &Path, opts: &Options) -> Result<FileMatch, AppError> {
+    let (target, unreadable) = file_match_under(path).map_err(|e| {
+        let msg = format!("{}: {}", path.display(), e);
+        if e.kind() == std::io::ErrorKind::NotFound {
+            AppError::InvalidInput(msg)
+        } else {
+            AppError::Fatal(msg)
+        }
+    })?;
+    if unreadable > 0 && !opts.json {
+        warn(&format!(
+            "{unreadable} director{} under {} could not be read; files inside them are not matched",
+            if unreadable == 1 { "y" } else { "ies" },
+            path.display()
+        ));
+    }
+    Ok(target)
+}
+
+/// Inodes of the AF_UNIX sockets bound to `path`, or `None` when `path` is
+/// not a unix socket. A socket file's own inode is not the one its holders'
+/// fds point at, so these come from /proc/net/unix (read once into `unix`).
+/// Offline, a path is a unix socket when the captured table names it.
+fn unix_socket_inodes(
+    path: &Path,
+    unix: &mut Option<Vec<ProcNetUnixEntry>>,
+) -> Result<Option<HashSet<u64>>, AppError> {
+    let mut names = vec![path.display().to_string()];
+    if !is_offline() {
+        match std::fs::metadata(path) {
+            Ok(md) if md.file_type().is_socket() => {}
+            _ => return Ok(None),
+        }
+        if let Ok(canonical) = std::fs::canonicalize(path) {
+            names.push(canonical.display().to_string());
+        }
+    }
+
+    if unix.is_none() {
+        let entries = match read_proc_net_unix() {
+            Ok(v) => v,
+            Err(e) if is_offline() && e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(AppError::Fatal(format!("/proc/net/unix: {e}"))),
+        };
+        *unix = Some(entries);
+    }
+    let inodes: HashSet<u64> = unix
+        .iter()
+        .flatten()
+        .filter(|e| e.path.as_ref().is_some_and(|p| names.contains(p)))
+        .map(|e| e.inode)
+        .collect();
+
+    if is_offline() && inodes.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(inodes))
+}
+
+fn prepare_targets(
+    targets: Vec<Target>,
+    opts: &Options,
+    sockets: &mut SocketTable,
+) -> Result<Vec<TargetScan>, AppError> {
+    let mut scans = Vec::new();
+    let mut unix = None;
+
+    for target in targets {
+        let matcher = match &target {
+            Target::Path(path) => match unix_socket_inodes(path, &mut unix)? {
+                Some(_) if !opts.scopes.fd => {
+                    return Err(AppError::InvalidInput(format!(
+                        "--no-fd cannot be used with unix socket {}: sockets are found through open fds",
+                        path.display()
+                    )));
+                }
+                Some(inodes) => Matcher::Sockets(inodes),
+                None => Matcher::Path(path_matcher(path, opts)?),
+            },
+            Target::Inode {
+                major,
+                minor,
+                inode,
+            } => Matcher::Path(file_match_for_inode(*major, *minor, *inode)),
+            Target::Port(port) => {
+                let inodes = sockets
+                    .load(warn)
+                    .iter()
+                    .filter(|s| s.local_port == *port)
+                    .map(|s| s.inode)
+                    .collect();
+                Matcher::Sockets(inodes)
+            }
+        };
+        scans.push(TargetScan {
+            target,
+            matcher,
+            holders: BTreeMap::new(),
+            stats: ScanStats::default(),
+            notes: Annotations::default(),
+        });
+    }
+
+    Ok(scans)
+}
+
+/// Scan every path/port target in one walk of the process table. Port
+/// targets take their sockets from `sockets`, read here unless prefetched.
+/// Returns whether any target has a holder.
+fn whoholds_targets(
+    targets: Vec<Target>,
+    opts: &Options,
+    sockets: &mut SocketTable,
+) -> Result<bool, AppError> {
+    let mut scans = prepare_targets(targets, opts, sockets)?;
+    if opts.stream {
+        return stream_targets(scans, opts);
+    }
+    walk_targets(&mut scans, opts)?;
+    let held = scans.iter().any(|s| !s.holders.is_empty());
+    print_targets(scans, opts);
+    Ok(held)
+}
+
+/// `--watch`: rerun the scan every `interval`, redrawing the result (one
+/// JSON line per round with `--json`), until no target is held or Ctrl-C.
+/// Sockets and file identities are re-read each round, so a port that is
+/// released and re-bound is followed.
+fn watch_targets(targets: Vec<Target>, interval: Duration, opts: &Options) -> Result<(), AppError> {
+    install_interrupt_flag();
+    let clear = !opts.json && io::stdout().is_terminal();
+    let mut first = true;
+    let mut throttle = RedrawThrottle::new(opts.redraw_interval);
+    loop {
+        let mut scans = prepare_targets(targets.clone(), opts, &mut SocketTable::default())?;
+        walk_targets(&mut scans, opts)?;
+        let held = scans.iter().any(|s| !s.holders.is_empty());
+
+        let wait = throttle.until_next(Instant::now());
+        if !wait.is_zero() && !sleep_unless_interrupted(wait) {
+            return Ok(());
+        }
+        throttle.should_redraw(Instant::now());
+
+        if clear {
+            print!("{CLEAR_SCREEN}");
+        } else if !opts.json && !first {
+            println!();
+        }
+        first = false;
+        print_targets(scans, opts);
+        let _ = io::stdout().flush();
+
+        if !held || !sleep_unless_interrupted(interval) {
+            return Ok(());
+        }
+    }
+}
+
+/// Scan the process table once for every target that can have holders,
+/// then drop excluded and hidden holders.
+fn walk_targets(scans: &mut [TargetScan], opts: &Options) -> Result<(), AppError> {
+    if scans.iter().any(TargetScan::needs_walk) {
+        let pids = list_pids().map_err(|e| AppError::Fatal(e.to_string()))?;
+        for pid in pids {
+            let ProcAccess::Ok(proc) = ProcSnapshot::capture(pid) else {
+                continue;
+            };
+            let mut fd_ids = FdIdCache::new(pid);
+            for scan in scans.iter_mut().filter(|s| s.needs_walk()) {
+                scan.scan_pid(&proc, &mut fd_ids, opts.scopes);
+            }
+            if interrupted() {
+                break;
+            }
+        }
+    }
+
+    for scan in scans.iter_mut() {
+        scan.holders
+            .retain(|pid, (_, comm)| !opts.exclusions.excludes(*pid, comm));
+        scan.notes = annotate(opts, scan.holders.keys().copied());
+        let notes = &scan.notes;
+        scan.holders.retain(|pid, _| !notes.hides(*pid));
+    }
+    Ok(())
+}
+
+fn print_targets(mut scans: Vec<TargetScan>, opts: &Options) {
+    if opts.format == OutputFormat::Stable {
+        print_stable_targets(scans);
+        return;
+    }
+
+    if scans.len() == 1 {
+        let scan = scans.remove(0);
+        if opts.json {
+            print_json_holders(
+                scan.target.mode(),
+                scan.target.to_string(),
+                scan.holders,
+                &scan.stats,
+                &scan.notes,
+                &opts.envelope,
+            );
+        } else {
+            print_holders(scan.holders, &scan.stats, &scan.notes, opts.scopes);
+        }
+        return;
+    }
+
+    let mut stats = ScanStats::default();
+    for scan in &scans {
+        stats.merge(&scan.stats);
+    }
+    if opts.json {
+        print_json_multi(scans, &stats, &opts.envelope);
+    } else {
+        print_multi(scans, &stats, opts.scopes);
+    }
+}
+
+fn scan_pid_path(
+    proc: &ProcSnapshot,
+    target: &FileMatch,
+    fd_ids: &mut FdIdCache,
+    scopes: ScanScopes,
+    holders: &mut BTreeMap<i32, (Vec<Reason>, Comm)>,
+    stats: &mut ScanStats,
+) {
+    // A failed read is counted as an error but keeps the evidence found
+    // through the other links.
+    let pid = proc.pid;
+    let mut reasons: Vec<Reason> = Vec::new();
+    let mut any_denied = false;
+
+    let fds = if scopes.fd {
+        fd_ids.find_match(target)
+    } else {
+        ProcAccess::Ok(Vec::new())
+    };
+    match fds {
+        ProcAccess::Ok(fds) if !fds.is_empty() => {
+            reasons.push(Reason::OpenFd);
+        }
+        ProcAccess::Ok(_) => {}
+        ProcAccess::PermissionDenied => {
+            any_denied = true;
+        }
+        ProcAccess::Gone => return,
+        ProcAccess::Fatal(e) => stats.error(pid, &e),
+    }
+
+    let mapped = if scopes.mmap {
+        pid_has_mmap_match(pid, target)
+    } else {
+        ProcAccess::Ok(false)
+    };
+    match mapped {
+        ProcAccess::Ok(true) => reasons.push(Reason::Mmap),
+        ProcAccess::Ok(false) => {}
+        ProcAccess::PermissionDenied => {
+            any_denied = true;
+        }
+        ProcAccess::Gone => return,
+        ProcAccess::Fatal(e) => stats.error(pid, &e),
+    }
+
+    // A cwd or chroot keeps a directory busy without any open fd. A missing
+    // link (e.g. left out of a captured tree) is just no evidence.
+    for link in DirLink::ALL.into_iter().filter(|_| scopes.fd) {
+        match pid_dir_link_matches(pid, link, target) {
+            ProcAccess::Ok(true) => reasons.push(Reason::from(link)),
+            ProcAccess::Ok(false) | ProcAccess::Gone => {}
+            ProcAccess::PermissionDenied => any_denied = true,
+            ProcAccess::Fatal(e) => stats.error(pid, &e),
+        }
+    }
 
-        let comm = comm.unwrap_or_else(|| "<unknown>".to_string());
-        holders.insert(pid, (reasons, comm));
+    if reasons.is_empty() {
+        if any_denied {
+            stats.skip(pid);
+        }
+        return;
     }
 
-    if json_out {
-        print_json_holders(
-            "path",
-            path.display().to_string(),
-            holders,
-            skipped_permission_denied.len(),
-        );
-    } else {
-        print_holders(holders, skipped_permission_denied.len());
+    sort_reasons(&mut reasons, Reason::kind);
+    holders.insert(pid, (reasons, proc.comm.clone()));
+}
+
+fn scan_pid_sockets(
+    proc: &ProcSnapshot,
+    inodes: &HashSet<u64>,
+    holders: &mut BTreeMap<i32, (Vec<Reason>, Comm)>,
+    stats: &mut ScanStats,
+) {
+    let pid = proc.pid;
+    match scan_pid_open_fd_socket(pid, inodes) {
+        ProcAccess::Ok(true) => {
+            holders.insert(pid, (vec![Reason::OpenFd], proc.comm.clone()));
+        }
+        ProcAccess::Ok(false) | ProcAccess::Gone => {}
+        ProcAccess::PermissionDenied => stats.skip(pid),
+        ProcAccess::Fatal(e) => stats.error(pid, &e),
     }
-    Ok(())
 }
 
-fn whoholds_port(port: u16, json_out: bool) -> Result<(), AppError> {
-    let sockets = read_proc_net_sockets().map_err(|e| AppError::Fatal(e.to_string()))?;
+fn print_json_multi(scans: Vec<TargetScan>, stats: &ScanStats, envelope: &JsonEnvelope) {
+    let mut all: Vec<(i32, Comm)> = Vec::new();
+    let mut groups: Vec<serde_json::Value> = Vec::new();
+
+    for scan in scans {
+        let rows = holder_rows(scan.holders, &scan.notes);
+        all.extend(rows.iter().map(|r| (r.pid, r.command.clone())));
+        groups.push(json!({
+            "target_mode": scan.target.mode(),
+            "target": scan.target.to_string(),
+            "partial": scan.stats.partial(),
+            "skipped": scan.stats.skipped(),
+            "errored": scan.stats.errored(),
+            "distinct_commands": distinct_commands(rows.iter().map(|r| (r.pid, r.command.as_str()))),
+            "results": rows,
+        }));
+    }
 
-    let target_inodes: HashSet<u64> = sockets
-        .into_iter()
-        .filter(|s| s.local_port == port)
-        .map(|s| s.inode)
-        .collect();
+    let commands = distinct_commands(all.iter().map(|(pid, c)| (*pid, c.as_str())));
+    let payload = json!({
+        "privilege": privilege_mode(),
+        "mode_message": privilege_mode_message(),
+        "mode": "multi",
+        "partial": stats.partial(),
+        "privilege_insufficient": privilege_insufficient(stats.skipped()),
+        "skipped": stats.skipped(),
+        "errored": stats.errored(),
+        "distinct_commands": commands,
+        "targets": groups,
+    });
+    print_scan_envelope(payload, stats, envelope);
+}
 
-    let mut holders: BTreeMap<i32, (Vec<Reason>, String)> = BTreeMap::new();
-    let mut skipped_permission_denied: HashSet<i32> = HashSet::new();
+fn print_multi(scans: Vec<TargetScan>, stats: &ScanStats, scopes: ScanScopes) {
+    println!("{}", privilege_mode_message());
+    scopes.print_notice();
+    stats.print_notices();
 
-    if target_inodes.is_empty() {
-        if json_out {
-            print_json_holders(
-                "port",
-                port.to_string(),
-                holders,
-                skipped_permission_denied.len(),
-            );
-        } else {
-            print_holders(holders, skipped_permission_denied.len());
-        }
-        return Ok(());
+    for scan in scans {
+        println!();
+        print_header(&format!("Target {}: {}", scan.target.mode(), scan.target));
+        print_holder_table(scan.holders, &scan.notes);
     }
+}
 
-    let pids = list_pids().map_err(|e| AppError::Fatal(e.to_string()))?;
-
-    for pid in pids {
-        match scan_pid_open_fd_socket(pid, &target_inodes) {
-            ProcAccess::Ok(true) => {
-                let comm = read_comm_best_effort(pid);
-                holders.insert(pid, (vec![Reason::OpenFd], comm));
-            }
-            ProcAccess::Ok(false) => {}
-            ProcAccess::PermissionDenied => {
-                skipped_permission_denied.insert(pid);
-            }
-            ProcAccess::Gone => {}
-            ProcAccess::Fatal(e) => {
-                return Err(AppError::Fatal(e.to_string()));
+/// `--format stable`: `PID COMMAND REASONS` per holder, prefixed by the
+/// target when there are several. Annotations are left out on purpose.
+fn print_stable_targets(scans: Vec<TargetScan>) {
+    let multi = scans.len() > 1;
+    let mut stats = ScanStats::default();
+    let mut lines = Vec::new();
+    for scan in scans {
+        stats.merge(&scan.stats);
+        let target = scan.target.to_string();
+        for (pid, (reasons, comm)) in scan.holders {
+            let reason_str = reasons
+                .iter()
+                .map(|r| r.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            let mut fields = vec![pid.to_string(), comm.to_string(), reason_str];
+            if multi {
+                fields.insert(0, target.clone());
             }
+            lines.push(stable_line(fields));
         }
     }
+    stats.warn_notices();
+    print_stable(lines);
+}
 
-    if json_out {
-        print_json_holders(
-            "port",
-            port.to_string(),
-            holders,
-            skipped_permission_denied.len(),
-        );
-    } else {
-        print_holders(holders, skipped_permission_denied.len());
-    }
-    Ok(())
+fn print_stable_ports(rows: Vec<PortRow>, stats: &ScanStats) {
+    stats.warn_notices();
+    print_stable(
+        rows.into_iter()
+            .map(|r| {
+                stable_line([
+                    r.port.to_string(),
+                    r.proto.to_string(),
+                    r.pid.to_string(),
+                    r.command.to_string(),
+                    r.state,
+                ])
+            })
+            .collect(),
+    );
+}
+
+/// Sizes change as held files grow, so only identity goes in the line.
+fn print_stable_deleted(rows: Vec<DeletedRow>, stats: &ScanStats) {
+    stats.warn_notices();
+    print_stable(
+        rows.into_iter()
+            .map(|r| stable_line([r.pid.to_string(), r.command.to_string(), r.path, r.reason]))
+            .collect(),
+    );
 }
 
-fn print_ports(rows: Vec<PortRow>, skipped_permission_denied: usize) {
+fn print_ports(rows: Vec<PortRow>, stats: &ScanStats) {
     println!("{}", privilege_mode_message());
-    if skipped_permission_denied > 0 {
-        println!(
-            "Partial result: {skipped_permission_denied} processes skipped (permission denied)"
-        );
-    }
+    stats.print_notices();
 
     if rows.is_empty() {
         println!("No active holders detected.");
         return;
     }
 
-    print_header(&format!(
-        "{:<5} {:<5} {:<5} {:<width$} {}",
-        "PORT",
-        "PROTO",
-        "PID",
-        "COMMAND",
-        "STATE",
-        width = COMMAND_COL_WIDTH
-    ));
+    let commands = distinct_commands(rows.iter().map(|r| (r.pid, r.command.as_str())));
+    println!("Distinct commands: {}", format_distinct_commands(&commands));
+
+    let show_iface = rows.iter().any(|r| r.iface.is_some());
+    let show_unit = rows.iter().any(|r| r.notes.unit.is_some());
+    let mut headers = vec!["PORT", "PROTO", "ADDRESS"];
+    if show_iface {
+        headers.push("IFACE");
+    }
+    headers.extend(["PID", "COMMAND"]);
+    let pid_col = headers.len() - 2;
+    let mut table = Table::new(with_unit_column(
+        headers,
+        show_unit.then_some(UNIT_HEADER),
+        ["STATE"],
+    ))
+    .fixed(0)
+    .fixed(pid_col);
     for r in rows {
-        println!(
-            "{:<5} {:<5} {:<5} {:<width$} {}",
-            r.port,
-            r.proto,
-            r.pid,
-            r.command,
-            r.state,
-            width = COMMAND_COL_WIDTH
+        let mut cells = vec![r.port.to_string(), r.proto.to_string(), r.label.clone()];
+        if show_iface {
+            cells.push(r.iface.clone().unwrap_or_default());
+        }
+        cells.extend([
+            r.pid.to_string(),
+            display_command(&r.command, r.notes.zombie, r.notes.same_ns == Some(false)),
+        ]);
+        table.row(with_unit_column(
+            cells,
+            show_unit.then(|| unit_label(r.notes.unit.as_ref())),
+            [r.state.clone()],
+        ));
+        if let Some(queue) = &r.queue {
+            table.detail(format!("      queue: {}", queue.label()));
+        }
+        add_details(
+            &mut table,
+            r.notes.threads.as_deref(),
+            r.notes.caps.as_ref(),
+            r.notes.ancestors.as_deref(),
         );
     }
+    table.print();
 }
 
-fn print_holders(holders: BTreeMap<i32, (Vec<Reason>, String)>, skipped_permission_denied: usize) {
+fn print_holders(
+    holders: BTreeMap<i32, (Vec<Reason>, Comm)>,
+    stats: &ScanStats,
+    notes: &Annotations,
+    scopes: ScanScopes,
+) {
     println!("{}", privilege_mode_message());
-    if skipped_permission_denied > 0 {
-        println!(
-            "Partial result: {skipped_permission_denied} processes skipped (permission denied)"
-        );
-    }
+    scopes.print_notice();
+    stats.print_notices();
+    print_holder_table(holders, notes);
+}
 
+fn print_holder_table(holders: BTreeMap<i32, (Vec<Reason>, Comm)>, notes: &Annotations) {
     if holders.is_empty() {
         println!("No active holders detected.");
         return;
     }
 
+    let commands = distinct_commands(holders.iter().map(|(pid, (_, comm))| (*pid, comm.as_str())));
+    println!("Distinct commands: {}", format_distinct_commands(&commands));
+
+    if let Some(forest) = &notes.forest {
+        print_holder_forest(&holders, forest, notes);
+        return;
+    }
+
+    let show_unit = !notes.units.is_empty();
     print_header("Held by:");
+    let mut table = Table::new(with_unit_column(
+        ["PID", "COMMAND"],
+        show_unit.then_some(UNIT_HEADER),
+        ["REASON"],
+    ))
+    .fixed(0);
+    for (pid, (reasons, comm)) in holders {
+        table.row(with_unit_column(
+            [
+                pid.to_string(),
+                display_command(&comm, notes.is_zombie(pid), notes.in_container(pid)),
+            ],
+            show_unit.then(|| unit_label(notes.units.get(&pid))),
+            [join_reasons(&reasons)],
+        ));
+        add_details(
+            &mut table,
+            notes.threads.get(&pid).map(Vec::as_slice),
+            notes.caps.get(&pid),
+            notes.tree.get(&pid).map(Vec::as_slice),
+        );
+    }
+    table.print();
+}
+
+/// `--tree`: holders indented under their parents; ancestors that hold
+/// nothing are shown without a reason.
+fn print_holder_forest(
+    holders: &BTreeMap<i32, (Vec<Reason>, Comm)>,
+    forest: &ProcessForest,
+    notes: &Annotations,
+) {
+    print_header("Held by (process tree):");
+    for (depth, pid) in forest.walk() {
+        let indent = "  ".repeat(depth);
+        let Some((reasons, comm)) = holders.get(&pid) else {
+            let comm = forest.commands.get(&pid).cloned().unwrap_or_default();
+            println!("{indent}{pid} {comm}");
+            continue;
+        };
+        let comm = display_command(comm, notes.is_zombie(pid), notes.in_container(pid));
+        let unit = notes
+            .units
+            .get(&pid)
+            .map(|u| format!(" [{}]", unit_label(Some(u))))
+            .unwrap_or_default();
+        println!("{indent}{pid} {comm}{unit}  {}", join_reasons(reasons));
+        if let Some(threads) = notes.threads.get(&pid) {
+            println!("{indent}      threads: {}", format_threads(threads));
+        }
+        if let Some(caps) = notes.caps.get(&pid) {
+            println!("{indent}      caps: {}", caps.label());
+        }
+    }
+}
+
+fn join_reasons(reasons: &[Reason]) -> String {
+    reasons
+        .iter()
+        .map(|r| r.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Detail lines shared by every holder table, in print order.
+fn add_details(
+    table: &mut Table,
+    threads: Option<&[ThreadRow]>,
+    caps: Option<&CapsRow>,
+    ancestors: Option<&[AncestorRow]>,
+) {
+    if let Some(threads) = threads {
+        table.detail(format!("      threads: {}", format_threads(threads)));
+    }
+    if let Some(caps) = caps {
+        table.detail(format!("      caps: {}", caps.label()));
+    }
+    if let Some(ancestors) = ancestors {
+        table.detail(format!("      parents: {}", format_ancestors(ancestors)));
+    }
+}
+
+/// One holder row plus its detail lines; `target` adds a TARGET column
+/// (streamed multi-target scans).
+fn write_holder_line(
+    out: &mut Vec<u8>,
+    pid: i32,
+    reasons: &[Reason],
+    comm: &str,
+    notes: &Annotations,
+    show_unit: bool,
+    target: Option<&str>,
+) {
+    let reason_str = join_reasons(reasons);
+    let comm = display_command(comm, notes.is_zombie(pid), notes.in_container(pid));
+    let unit = unit_cell(notes.units.get(&pid), show_unit);
+    let _ = match target {
+        Some(target) => writeln!(
+            out,
+            "{pid:<5} {comm:<width$}{unit} {reason_str:<12} {target}",
+            width = COMMAND_COL_WIDTH
+        ),
+        None => writeln!(
+            out,
+            "{pid:<5} {comm:<width$}{unit} {reason_str}",
+            width = COMMAND_COL_WIDTH
+        ),
+    };
+    if let Some(threads) = notes.threads.get(&pid) {
+        let _ = writeln!(out, "      threads: {}", format_threads(threads));
+    }
+    if let Some(caps) = notes.caps.get(&pid) {
+        let _ = writeln!(out, "      caps: {}", caps.label());
+    }
+    if let Some(ancestors) = notes.tree.get(&pid) {
+        let _ = writeln!(out, "      parents: {}", format_ancestors(ancestors));
+    }
+}
+
+/// Write out what `pending` holds and empty it.
+fn flush_pending(pending: &mut Vec<u8>) {
+    let mut stdout = io::stdout().lock();
+    let _ = stdout.write_all(pending);
+    let _ = stdout.flush();
+    pending.clear();
+}
+
+/// `--stream`: print each holder the moment it is found, in process-table
+/// order, instead of collecting and sorting. Lines found within one
+/// `--redraw-interval` are written together. Notices come last because the
+/// skip and error counts are only known once the walk is done.
+fn stream_targets(mut scans: Vec<TargetScan>, opts: &Options) -> Result<bool, AppError> {
+    println!("{}", privilege_mode_message());
+    println!("Streaming holders as they are found; order is not stable.");
+    opts.scopes.print_notice();
+
+    let multi = scans.len() > 1;
+    let unit_header = unit_header(opts.show_unit);
+    let target_header = if multi { " TARGET" } else { "" };
     print_header(&format!(
-        "{:<5} {:<width$} {}",
+        "{:<5} {:<width$}{unit_header} {:<reason_width$}{target_header}",
         "PID",
         "COMMAND",
         "REASON",
-        width = COMMAND_COL_WIDTH
+        width = COMMAND_COL_WIDTH,
+        reason_width = if multi { 12 } else { 0 }
     ));
 
-    for (pid, (reasons, comm)) in holders {
-        let reason_str = reasons
-            .iter()
-            .map(|r| r.as_str())
-            .collect::<Vec<_>>()
-            .join(", ");
-        println!(
-            "{pid:<5} {comm:<width$} {reason_str}",
-            width = COMMAND_COL_WIDTH
-        );
+    let mut found = 0usize;
+    let mut throttle = RedrawThrottle::new(opts.redraw_interval);
+    let mut pending = Vec::new();
+    if scans.iter().any(TargetScan::needs_walk) {
+        let pids = list_pids().map_err(|e| AppError::Fatal(e.to_string()))?;
+        for pid in pids {
+            let ProcAccess::Ok(proc) = ProcSnapshot::capture(pid) else {
+                continue;
+            };
+            let mut fd_ids = FdIdCache::new(pid);
+            for scan in scans.iter_mut().filter(|s| s.needs_walk()) {
+                scan.scan_pid(&proc, &mut fd_ids, opts.scopes);
+                let Some((reasons, comm)) = scan.holders.remove(&pid) else {
+                    continue;
+                };
+                if opts.exclusions.excludes(pid, &comm) {
+                    continue;
+                }
+                let notes = annotate(opts, [pid]);
+                if notes.hides(pid) {
+                    continue;
+                }
+                let target = scan.target.to_string();
+                let target = multi.then_some(target.as_str());
+                write_holder_line(
+                    &mut pending,
+                    pid,
+                    &reasons,
+                    &comm,
+                    &notes,
+                    opts.show_unit,
+                    target,
+                );
+                found += 1;
+            }
+            if !pending.is_empty() && throttle.should_redraw(Instant::now()) {
+                flush_pending(&mut pending);
+            }
+        }
+    }
+    flush_pending(&mut pending);
+
+    if found == 0 {
+        println!("No active holders detected.");
     }
+    let mut stats = ScanStats::default();
+    for scan in &scans {
+        stats.merge(&scan.stats);
+    }
+    stats.print_notices();
+    Ok(found > 0)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn keys(value: serde_json::Value) -> Vec<String> {
+        value.as_object().unwrap().keys().cloned().collect()
+    }
+
+    fn sorted(fields: &[&str]) -> Vec<String> {
+        let mut v: Vec<String> = fields.iter().map(|f| f.to_string()).collect();
+        v.sort();
+        v
+    }
+
+    fn socket(proto: ProcNetProto, port: u16, inode: u64, state: u8) -> ProcNetSocketEntry {
+        ProcNetSocketEntry {
+            proto,
+            local_addr: std::net::IpAddr::V4(std::net::Ipv4Addr::UNSPECIFIED),
+            local_port: port,
+            remote_addr: std::net::IpAddr::V4(std::net::Ipv4Addr::UNSPECIFIED),
+            remote_port: 0,
+            inode,
+            state,
+            tx_queue: 0,
+            rx_queue: 0,
+            timer: 0,
+            timer_ticks: 0,
+        }
+    }
+
+    #[test]
+    fn port_filter_active_and_remote_port() {
+        let listen = socket(ProcNetProto::Tcp, 80, 1, TCP_LISTEN);
+        let time_wait = socket(ProcNetProto::Tcp6, 80, 0, TCP_TIME_WAIT);
+        let closed = socket(ProcNetProto::Tcp, 80, 0, TCP_CLOSE);
+        let bound = socket(ProcNetProto::Tcp, 81, 4, TCP_CLOSE);
+        let udp = socket(ProcNetProto::Udp, 53, 2, UDP_LISTEN);
+        let mut peer = socket(ProcNetProto::Tcp, 40000, 3, TCP_ESTABLISHED);
+        peer.remote_port = 5432;
+
+        let all = PortFilter::default();
+        assert!([&listen, &time_wait, &closed, &udp]
+            .iter()
+            .all(|s| all.keeps(s)));
+
+        let active = PortFilter {
+            active: true,
+            ..PortFilter::default()
+        };
+        assert!(active.keeps(&listen) && active.keeps(&udp) && active.keeps(&peer));
+        assert!(active.keeps(&bound));
+        assert!(!active.keeps(&time_wait) && !active.keeps(&closed));
+
+        let remote = PortFilter {
+            remote_port: Some(5432),
+            ..PortFilter::default()
+        };
+        assert!(remote.keeps(&peer));
+        assert!(!remote.keeps(&listen));
+        let established = PortFilter {
+            established: true,
+            ..remote
+        };
+        assert!(established.keeps(&peer));
+    }
+
+    fn port_rows_for(sockets: &[ProcNetSocketEntry], fd_links: &[(i32, u64)]) -> Vec<PortRow> {
+        port_rows_by(sockets, fd_links, true)
+    }
+
+    fn port_rows_by(
+        sockets: &[ProcNetSocketEntry],
+        fd_links: &[(i32, u64)],
+        by_label: bool,
+    ) -> Vec<PortRow> {
+        let mut inode_to_pids: BTreeMap<u64, Vec<i32>> = BTreeMap::new();
+        for (pid, inode) in fd_links {
+            inode_to_pids.entry(*inode).or_default().push(*pid);
+        }
+        for pids in inode_to_pids.values_mut() {
+            pids.sort_unstable();
+            pids.dedup();
+        }
+        let comm: HashMap<i32, Comm> = fd_links
+            .iter()
+            .map(|(pid, _)| (*pid, Comm::known(format!("proc{pid}"))))
+            .collect();
+        let mut rows = build_port_rows(sockets, &inode_to_pids, &comm, None, false, false);
+        sort_dedup_port_rows(&mut rows, by_label);
+        rows
+    }
+
+    #[test]
+    fn one_socket_via_two_fds_is_one_row() {
+        let sockets = [socket(ProcNetProto::Tcp, 8080, 555, TCP_LISTEN)];
+        let rows = port_rows_for(&sockets, &[(10, 555), (10, 555)]);
+        assert_eq!(rows.len(), 1);
+        assert_eq!((rows[0].port, rows[0].pid), (8080, 10));
+    }
+
+    #[test]
+    fn port_dedup_key_ignores_socket_order() {
+        // listening / established / listening on one port by one PID, plus
+        // a dual-stack tcp6 listener: two rows, not three or four.
+        let sockets = [
+            socket(ProcNetProto::Tcp, 8080, 1, TCP_LISTEN),
+            socket(ProcNetProto::Tcp, 8080, 2, TCP_ESTABLISHED),
+            socket(ProcNetProto::Tcp, 8080, 3, TCP_LISTEN),
+            socket(ProcNetProto::Tcp6, 8080, 4, TCP_LISTEN),
+        ];
+        let rows = port_rows_for(&sockets, &[(10, 1), (10, 2), (10, 3), (10, 4)]);
+        let states: Vec<&str> = rows.iter().map(|r| r.state.as_str()).collect();
+        assert_eq!(states, vec!["established", "listening"]);
+
+        // A second holder of the same socket keeps its own row.
+        let rows = port_rows_for(&sockets[..1], &[(10, 1), (11, 1)]);
+        let pids: Vec<i32> = rows.iter().map(|r| r.pid).collect();
+        assert_eq!(pids, vec![10, 11]);
+    }
+
+    fn bound(proto: ProcNetProto, addr: &str, inode: u64) -> ProcNetSocketEntry {
+        ProcNetSocketEntry {
+            local_addr: addr.parse().unwrap(),
+            ..socket(proto, 443, inode, TCP_LISTEN)
+        }
+    }
+
+    #[test]
+    fn bind_filter_compares_canonical_addresses() {
+        let bind = |s: &str| PortFilter {
+            bind: Some(s.parse().unwrap()),
+            ..PortFilter::default()
+        };
+        let any4 = bound(ProcNetProto::Tcp, "0.0.0.0", 1);
+        let any6 = bound(ProcNetProto::Tcp6, "::", 2);
+        let lo4 = bound(ProcNetProto::Tcp, "127.0.0.1", 3);
+        let lo6 = bound(ProcNetProto::Tcp6, "::1", 4);
+        let public = bound(ProcNetProto::Tcp, "203.0.113.7", 5);
+        let mapped = bound(ProcNetProto::Tcp6, "::ffff:203.0.113.7", 6);
+
+        for wildcard in ["0.0.0.0", "::"] {
+            let f = bind(wildcard);
+            assert!(f.keeps(&any4) && f.keeps(&any6), "{wildcard}");
+            assert!(!f.keeps(&lo4) && !f.keeps(&public), "{wildcard}");
+        }
+        let f = bind("203.0.113.7");
+        assert!(f.keeps(&public) && f.keeps(&mapped));
+        assert!(!f.keeps(&any4) && !f.keeps(&lo4));
+        assert!(bind("::ffff:203.0.113.7").keeps(&public));
+        assert!(bind("::1").keeps(&lo6) && !bind("::1").keeps(&lo4));
+
+        assert!("fe80::1%eth0".parse::<BindAddr>().is_ok());
+        assert!("[::1]".parse::<BindAddr>().is_ok());
+        assert!("10.0.0.1%eth0".parse::<BindAddr>().is_err());
+        assert!("fe80::1%".parse::<BindAddr>().is_err());
+        assert!("localhost".parse::<BindAddr>().is_err());
+        let zoned: BindAddr = "fe80::1%eth0".parse().unwrap();
+        assert_eq!(zoned.to_string(), "fe80::1%eth0");
+        assert!(!zoned.matches_zone("fe80::1".parse().unwrap(), None));
+    }
+
+    #[test]
+    fn address_labels_collapse_wildcard_and_loopback_families() {
+        let label = |s: &str| address_label(s.parse().unwrap(), None);
+        assert_eq!(label("0.0.0.0"), "*");
+        assert_eq!(label("::"), "*");
+        assert_eq!(label("127.0.0.1"), "localhost");
+        assert_eq!(label("::1"), "localhost");
+        assert_eq!(label("127.0.0.53"), "127.0.0.53");
+        assert_eq!(label("::ffff:10.0.0.5"), "10.0.0.5");
+        assert_eq!(label("fe80::1"), "fe80::1");
+
+        let sockets = [
+            bound(ProcNetProto::Tcp, "0.0.0.0", 1),
+            bound(ProcNetProto::Tcp6, "::", 2),
+            bound(ProcNetProto::Tcp, "127.0.0.1", 3),
+            bound(ProcNetProto::Tcp6, "::1", 4),
+            bound(ProcNetProto::Tcp, "203.0.113.7", 5),
+        ];
+        let rows = port_rows_for(&sockets, &[(10, 1), (10, 2), (10, 3), (10, 4), (10, 5)]);
+        let labels: Vec<&str> = rows.iter().map(|r| r.label.as_str()).collect();
+        assert_eq!(labels, vec!["*", "203.0.113.7", "localhost"]);
+
+        // JSON rows keep the bound address, one per family.
+        let rows = port_rows_by(&sockets[..4], &[(10, 1), (10, 2), (10, 3), (10, 4)], false);
+        let addresses: Vec<&str> = rows.iter().map(|r| r.address.as_str()).collect();
+        assert_eq!(addresses, vec!["0.0.0.0", "127.0.0.1", "::", "::1"]);
+    }
+
+    #[test]
+    fn field_lists_match_serialized_rows() {
+        let threads = Some(vec![ThreadRow {
+            tid: 11,
+            command: "worker-3".to_string(),
+        }]);
+        let unit = Some(UnitRow {
+            kind: "systemd",
+            name: Some("nginx.service".to_string()),
+        });
+        let ancestors = Some(vec![AncestorRow {
+            pid: 1,
+            command: Comm::known("init"),
+        }]);
+        let caps = Some(CapsRow::from_mask(0x400));
+        let ns = Some(NsIds {
+            mnt: 4026532100,
+            net: 4026532101,
+            pid: 4026532102,
+        });
+        let notes = HolderAnnotations {
+            unit,
+            zombie: true,
+            same_ns: Some(false),
+            ns,
+            caps,
+            threads,
+            ancestors,
+        };
+        let port = PortRow {
+            port: 80,
+            proto: "tcp",
+            proto_sort: 0,
+            address: "0.0.0.0".to_string(),
+            label: "*".to_string(),
+            pid: 10,
+            command: Comm::known("nginx"),
+            state: "listening".to_string(),
+            iface: Some("*".to_string()),
+            queue: Some(QueueRow::new(3, 128)),
+            notes: notes.clone(),
+        };
+        let holder = HolderRow {
+            pid: 10,
+            command: Comm::known("nginx"),
+            reason: "open fd".to_string(),
+            exe: Some("/usr/sbin/nginx".to_string()),
+            notes: notes.clone(),
+            ppid: Some(1),
+        };
+        let deleted = DeletedRow {
+            pid: 10,
+            command: Comm::known("nginx"),
+            path: "/var/log/old".to_string(),
+            size: Some(1),
+            reason: "open fd".to_string(),
+            notes,
+        };
+        assert_eq!(
+            keys(serde_json::to_value(port).unwrap()),
+            sorted(PORT_FIELDS)
+        );
+        assert_eq!(
+            keys(serde_json::to_value(holder).unwrap()),
+            sorted(HOLDER_FIELDS)
+        );
+        assert_eq!(
+            keys(serde_json::to_value(deleted).unwrap()),
+            sorted(DELETED_FIELDS)
+        );
+    }
+
     #[test]
     fn reason_as_str() {
         assert_eq!(Reason::OpenFd.as_str(), "open fd");
         assert_eq!(Reason::Mmap.as_str(), "mmap");
+        assert_eq!(Reason::from(DirLink::Cwd).as_str(), "cwd");
+        assert_eq!(Reason::Root.as_str(), "root");
     }
 
     #[test]
     fn reason_ordering() {
-        assert!(Reason::Mmap > Reason::OpenFd);
+        let mut reasons = vec![Reason::Root, Reason::Mmap, Reason::Cwd, Reason::OpenFd];
+        sort_reasons(&mut reasons, Reason::kind);
+        assert_eq!(
+            reasons,
+            vec![Reason::OpenFd, Reason::Mmap, Reason::Cwd, Reason::Root]
+        );
     }
 
     #[test]
     fn holder_row_serializes_json() {
         let row = HolderRow {
             pid: 1234,
-            command: "bash".to_string(),
+            command: Comm::known("bash"),
             reason: "open fd".to_string(),
+            exe: None,
+            notes: HolderAnnotations::default(),
+            ppid: None,
         };
         let json = serde_json::to_value(&row).unwrap();
         assert_eq!(json["pid"], 1234);
         assert_eq!(json["command"], "bash");
         assert_eq!(json["reason"], "open fd");
+        assert!(json.get("exe").is_none());
+        assert!(json.get("ancestors").is_none());
+    }
+
+    #[test]
+    fn holder_row_serializes_ancestors() {
+        let row = HolderRow {
+            pid: 1234,
+            command: Comm::known("worker"),
+            reason: "open fd".to_string(),
+            exe: None,
+            notes: HolderAnnotations {
+                ancestors: Some(vec![AncestorRow {
+                    pid: 1,
+                    command: Comm::known("systemd"),
+                }]),
+                ..HolderAnnotations::default()
+            },
+            ppid: None,
+        };
+        let json = serde_json::to_value(&row).unwrap();
+        assert_eq!(json["ancestors"][0]["pid"], 1);
+        assert_eq!(json["ancestors"][0]["command"], "systemd");
+    }
+
+    #[test]
+    fn format_threads_lists_tid_and_name() {
+        let threads = vec![
+            ThreadRow {
+                tid: 10,
+                command: "app".to_string(),
+            },
+            ThreadRow {
+                tid: 13,
+                command: "worker-3".to_string(),
+            },
+        ];
+        assert_eq!(format_threads(&threads), "10 app, 13 worker-3");
+        assert_eq!(format_threads(&[]), "<unreadable>");
+    }
+
+    fn forest(chains: &[(i32, &[i32])]) -> ProcessForest {
+        ProcessForest::build(chains.iter().map(|&(pid, chain)| (pid, chain.to_vec())))
+    }
+
+    #[test]
+    fn process_forest_groups_workers_under_their_master() {
+        // systemd(1) -> nginx master(900) -> workers 901, 902; sshd(700) apart.
+        let f = forest(&[(902, &[900, 1]), (901, &[900, 1]), (700, &[1])]);
+        assert_eq!(
+            f.walk(),
+            vec![(0, 1), (1, 700), (1, 900), (2, 901), (2, 902)]
+        );
+        assert_eq!(f.parents.get(&1), Some(&None));
+    }
+
+    #[test]
+    fn process_forest_survives_cycles_across_chains() {
+        // A chain ending below init (unreadable parent) makes its own root.
+        let f = forest(&[(10, &[11])]);
+        assert_eq!(f.walk(), vec![(0, 11), (1, 10)]);
+
+        // Two racy reads claiming 10 -> 11 and 11 -> 10: both print, once.
+        let f = forest(&[(10, &[11]), (11, &[10])]);
+        assert_eq!(f.walk(), vec![(0, 10), (1, 11)]);
+
+        // A holder without readable parents is a root of its own.
+        let f = forest(&[(5, &[])]);
+        assert_eq!(f.walk(), vec![(0, 5)]);
+    }
+
+    #[test]
+    fn format_ancestors_joins_nearest_first() {
+        let ancestors = vec![
+            AncestorRow {
+                pid: 900,
+                command: Comm::known("php-fpm"),
+            },
+            AncestorRow {
+                pid: 1,
+                command: Comm::known("systemd"),
+            },
+        ];
+        assert_eq!(format_ancestors(&ancestors), "900 php-fpm <- 1 systemd");
+        assert_eq!(format_ancestors(&[]), "<none>");
+    }
+
+    #[test]
+    fn unit_labels_and_cells() {
+        let docker = UnitRow::from_cgroup(&CgroupUnit::Container {
+            runtime: "docker",
+            id: "4f2a9c1e7b3d".to_string(),
+        });
+        assert_eq!(docker.label(), "docker:4f2a9c1e7b3d");
+        let service = UnitRow::from_cgroup(&CgroupUnit::Systemd("nginx.service".to_string()));
+        assert_eq!(service.label(), "nginx.service");
+        let unknown = UnitRow::from_cgroup(&CgroupUnit::Unknown);
+        assert_eq!(unknown.label(), "unknown");
+        assert!(serde_json::to_value(&unknown)
+            .unwrap()
+            .get("name")
+            .is_none());
+
+        assert_eq!(unit_cell(Some(&service), false), "");
+        assert_eq!(unit_cell(None, true).trim(), "unknown");
+        assert_eq!(unit_cell(Some(&service), true).len(), UNIT_COL_WIDTH + 1);
+    }
+
+    #[test]
+    fn holders_in_other_namespaces_are_marked() {
+        let own = NsIds {
+            mnt: 4026531841,
+            net: 4026531840,
+            pid: 4026531836,
+        };
+        let mut notes = Annotations {
+            own_ns: Some(own),
+            ..Annotations::default()
+        };
+        notes.namespaces.insert(10, own);
+        notes.namespaces.insert(
+            11,
+            NsIds {
+                net: 4026532100,
+                ..own
+            },
+        );
+        notes.namespaces.insert(
+            12,
+            NsIds {
+                pid: 4026532200,
+                ..own
+            },
+        );
+        assert_eq!(notes.same_ns(10), Some(true));
+        assert_eq!(notes.same_ns(11), Some(false));
+        // Only the mount and network namespaces decide what a holder sees.
+        assert_eq!(notes.same_ns(12), Some(true));
+        assert_eq!(notes.same_ns(13), None);
+        assert!(notes.in_container(11) && !notes.in_container(13));
+
+        assert_eq!(
+            display_command("nginx", false, notes.in_container(11)),
+            "nginx [container]"
+        );
+        assert_eq!(
+            display_command("nginx", true, true),
+            "nginx (zombie) [container]"
+        );
+        notes.own_ns = None;
+        assert_eq!(notes.same_ns(11), None);
+    }
+
+    #[test]
+    fn zombie_holders_are_labelled_or_hidden() {
+        let mut notes = Annotations::default();
+        notes.zombies.insert(42);
+        assert_eq!(
+            display_command("nginx", notes.is_zombie(42), false),
+            "nginx (zombie)"
+        );
+        assert_eq!(display_command("nginx", notes.is_zombie(7), false), "nginx");
+        assert!(!notes.hides(42));
+
+        notes.hide_zombies = true;
+        assert!(notes.hides(42));
+        assert!(!notes.hides(7));
     }
 
     #[test]
@@ -596,9 +2953,14 @@ mod tests {
             port: 8080,
             proto: "tcp",
             proto_sort: 0,
+            address: "0.0.0.0".to_string(),
+            label: "*".to_string(),
             pid: 42,
-            command: "nginx".to_string(),
+            command: Comm::known("nginx"),
             state: "listening".to_string(),
+            iface: None,
+            queue: None,
+            notes: HolderAnnotations::default(),
         };
         let json = serde_json::to_value(&row).unwrap();
         assert_eq!(json["port"], 8080);
@@ -610,6 +2972,146 @@ mod tests {
         assert!(json.get("proto_sort").is_none());
     }
 
+    #[test]
+    fn unknown_command_serializes_null() {
+        let row = HolderRow {
+            pid: 7,
+            command: Comm::unknown(),
+            reason: "open_fd".to_string(),
+            exe: None,
+            notes: HolderAnnotations::default(),
+            ppid: None,
+        };
+        let json = serde_json::to_value(&row).unwrap();
+        assert!(json["command"].is_null());
+    }
+
+    #[test]
+    fn queue_row_flags_nearly_full_listeners() {
+        assert!(!QueueRow::new(3, 128).saturated);
+        assert!(QueueRow::new(103, 128).saturated);
+        assert!(QueueRow::new(129, 128).saturated);
+        assert!(!QueueRow::new(0, 0).saturated);
+        assert_eq!(QueueRow::new(3, 128).label(), "3/128 pending");
+        assert_eq!(
+            QueueRow::new(128, 128).label(),
+            "128/128 pending   <- accept queue nearly full"
+        );
+
+        let mut listener = socket(ProcNetProto::Tcp, 80, 1, TCP_LISTEN);
+        listener.rx_queue = 5;
+        listener.tx_queue = 4096;
+        assert_eq!(
+            QueueRow::for_socket(&listener),
+            Some(QueueRow::new(5, 4096))
+        );
+        let udp = socket(ProcNetProto::Udp, 53, 2, UDP_LISTEN);
+        assert_eq!(QueueRow::for_socket(&udp), None);
+    }
+
+    #[test]
+    fn caps_row_flags_sys_admin() {
+        let caps = CapsRow::from_mask(0x200400);
+        assert_eq!(
+            caps.label(),
+            "cap_net_bind_service, cap_sys_admin   <- CAP_SYS_ADMIN"
+        );
+        let json = serde_json::to_value(&caps).unwrap();
+        assert_eq!(json["effective"], "0x0000000000200400");
+        assert_eq!(json["sys_admin"], true);
+        assert!(json.get("mask").is_none());
+
+        assert_eq!(CapsRow::from_mask(0).label(), "none");
+        assert_eq!(
+            CapsRow::from_mask(0x000001ffffffffff).label(),
+            "all   <- CAP_SYS_ADMIN"
+        );
+    }
+
+    #[test]
+    fn parse_inode_target_reads_hex_device() {
+        assert_eq!(
+            parse_inode_target("08:11:1234").unwrap(),
+            Target::Inode {
+                major: 8,
+                minor: 17,
+                inode: 1234
+            }
+        );
+        assert_eq!(
+            parse_inode_target("fd:01:7").unwrap().to_string(),
+            "fd:01:7"
+        );
+        for bad in [
+            "1234",
+            "08:1234",
+            "08:01:02:1234",
+            "zz:01:1",
+            "08:01:x",
+            "08:01:",
+        ] {
+            assert!(
+                matches!(parse_inode_target(bad), Err(AppError::InvalidInput(_))),
+                "{bad}"
+            );
+        }
+    }
+
+    #[test]
+    fn glob_targets_rejects_empty_match() {
+        let pattern = format!("/nonexistent-{}/*.db", std::process::id());
+        assert!(matches!(
+            glob_targets(&[pattern]),
+            Err(AppError::InvalidInput(msg)) if msg.starts_with("glob matched nothing")
+        ));
+        assert_eq!(
+            glob_targets(&["/etc/host[s]".to_string(), "/etc/hosts".to_string()]).unwrap(),
+            vec![Target::Path(PathBuf::from("/etc/hosts"))]
+        );
+    }
+
+    #[test]
+    fn is_disk_backed_skips_memory_objects() {
+        assert!(is_disk_backed("/var/log/app.log"));
+        assert!(!is_disk_backed("/memfd:wayland-shm"));
+        assert!(!is_disk_backed("/SYSV00000000"));
+        assert!(!is_disk_backed("anon_inode:[eventfd]"));
+    }
+
+    #[test]
+    fn deleted_rows_sort_by_size_desc() {
+        let row = |pid: i32, size: Option<u64>| DeletedRow {
+            pid,
+            command: Comm::known("app"),
+            path: format!("/tmp/{pid}"),
+            size,
+            reason: "open fd".to_string(),
+            notes: HolderAnnotations::default(),
+        };
+        let mut rows = vec![
+            row(1, Some(10)),
+            row(2, None),
+            row(3, Some(5000)),
+            row(4, Some(10)),
+        ];
+        sort_deleted_rows(&mut rows);
+        let order: Vec<i32> = rows.iter().map(|r| r.pid).collect();
+        assert_eq!(order, vec![3, 1, 4, 2]);
+    }
+
+    #[test]
+    fn unbound_port_skips_the_walk() {
+        let scan = |matcher| TargetScan {
+            target: Target::Port(9),
+            matcher,
+            holders: BTreeMap::new(),
+            stats: ScanStats::default(),
+            notes: Annotations::default(),
+        };
+        assert!(!scan(Matcher::Sockets(HashSet::new())).needs_walk());
+        assert!(scan(Matcher::Sockets(HashSet::from([42]))).needs_walk());
+    }
+
     #[test]
     fn target_parse_port() {
         assert!("8080".parse::<u16>().is_ok());
@@ -0,0 +1,171 @@
+//! JSON contract checks: run the binary against a synthetic proc tree
+//! (`--proc-root`) and assert the documented envelope fields and types.
+
+use serde_json::Value;
+use std::fs;
+use std::os::unix::fs::symlink;
+use std::path::{Path, PathBuf};
+use testkit::{assert_scan_envelope, proc_tree, run_proc, run_proc_json};
+
+const BIN: &str = env!("CARGO_BIN_EXE_whoholds");
+
+/// A proc tree with one process holding `/srv/data.db` (fd 3 and a
+/// mapping), a socket listening on port 8080 (fd 4), a deleted log (fd 5)
+/// and the unix socket `/run/app.sock` (fd 6), running in `/srv`.
+fn fixture(name: &str) -> PathBuf {
+    let root = proc_tree(
+        name,
+        "   0: 00000000:1F90 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 999 1 0000000000000000 100 0 0 10 0\n",
+    );
+    let pid = root.join("4242");
+    symlink("/var/log/old.log (deleted)", pid.join("fd/5")).unwrap();
+    symlink("socket:[777]", pid.join("fd/6")).unwrap();
+    symlink("/srv", pid.join("cwd")).unwrap();
+    symlink("/usr/bin/fixture", pid.join("exe")).unwrap();
+    fs::write(
+        root.join("net/unix"),
+        "Num       RefCount Protocol Flags    Type St Inode Path\n\
+         0000000000000000: 00000002 00000000 00010000 0001 01 777 /run/app.sock\n",
+    )
+    .unwrap();
+    root
+}
+
+fn run_json(root: &Path, args: &[&str]) -> Value {
+    run_proc_json(BIN, root, args)
+}
+
+#[test]
+fn path_target_json_shape() {
+    let root = fixture("whoholds_path");
+    let v = run_json(&root, &["/srv/data.db"]);
+    assert_scan_envelope(&v, "path");
+    assert_eq!(v["target"], "/srv/data.db");
+    assert_eq!(v["scopes"], serde_json::json!(["fd", "mmap"]));
+
+    let results = v["results"].as_array().unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0]["pid"], 4242);
+    assert_eq!(results[0]["command"], "fixture");
+    assert_eq!(results[0]["exe"], "/usr/bin/fixture");
+    assert!(results[0]["reason"].is_string());
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn port_and_multi_target_json_shape() {
+    let root = fixture("whoholds_port");
+    let v = run_json(&root, &["8080"]);
+    assert_scan_envelope(&v, "port");
+    assert_eq!(v["results"][0]["pid"], 4242);
+
+    let v = run_json(&root, &["8080", "/srv/data.db"]);
+    assert_scan_envelope(&v, "multi");
+    let targets = v["targets"].as_array().unwrap();
+    assert_eq!(targets.len(), 2);
+    for t in targets {
+        assert!(t["target"].is_string());
+        assert!(t["partial"].is_boolean());
+        assert!(t["results"].is_array());
+    }
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn cwd_holds_directory() {
+    let root = fixture("whoholds_cwd");
+    let v = run_json(&root, &["/srv"]);
+    assert_scan_envelope(&v, "path");
+    assert_eq!(v["results"][0]["reason"], "cwd");
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn unix_socket_path_maps_to_holder() {
+    let root = fixture("whoholds_unix");
+    let v = run_json(&root, &["/run/app.sock"]);
+    assert_scan_envelope(&v, "path");
+    assert_eq!(v["results"][0]["pid"], 4242);
+    assert_eq!(v["results"][0]["reason"], "open fd");
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn ports_and_deleted_json_shape() {
+    let root = fixture("whoholds_modes");
+    let v = run_json(&root, &["--ports"]);
+    assert_scan_envelope(&v, "ports");
+    assert!(v["listening"].is_boolean());
+    assert_eq!(v["results"][0]["port"], 8080);
+    assert_eq!(v["results"][0]["address"], "0.0.0.0");
+    let out = run_proc(BIN, &root, &["--ports"]);
+    let text = String::from_utf8_lossy(&out.stdout);
+    assert!(text.contains("\n8080 tcp   *       4242"), "{text}");
+
+    let v = run_json(&root, &["--ports", "--bind", "::"]);
+    assert_eq!(v["bind"], "::");
+    assert_eq!(v["results"][0]["port"], 8080);
+    let v = run_json(&root, &["--ports", "--bind", "127.0.0.1"]);
+    assert_eq!(v["results"].as_array().map(Vec::len), Some(0));
+
+    let v = run_json(&root, &["--deleted"]);
+    assert_scan_envelope(&v, "deleted");
+    assert_eq!(v["results"][0]["path"], "/var/log/old.log");
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn under_matches_files_below_directory() {
+    let root = fixture("whoholds_under");
+    let v = run_json(&root, &["--no-mmap", "/srv"]);
+    assert_eq!(v["results"][0]["reason"], "cwd");
+
+    let v = run_json(&root, &["--under", "--no-mmap", "/srv"]);
+    assert_scan_envelope(&v, "path");
+    assert_eq!(v["results"][0]["pid"], 4242);
+    assert_eq!(v["results"][0]["reason"], "open fd, cwd");
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn tree_adds_ppid_per_holder() {
+    let root = fixture("whoholds_tree");
+    fs::write(
+        root.join("4242/status"),
+        "Name:\tfixture\nState:\tS (sleeping)\nPPid:\t4100\n",
+    )
+    .unwrap();
+    fs::create_dir_all(root.join("4100")).unwrap();
+    fs::write(root.join("4100/comm"), "supervisor\n").unwrap();
+    fs::write(
+        root.join("4100/status"),
+        "Name:\tsupervisor\nState:\tS (sleeping)\nPPid:\t1\n",
+    )
+    .unwrap();
+
+    let v = run_json(&root, &["--tree", "/srv/data.db"]);
+    assert_scan_envelope(&v, "path");
+    assert_eq!(v["results"][0]["pid"], 4242);
+    assert_eq!(v["results"][0]["ppid"], 4100);
+
+    let out = run_proc(BIN, &root, &["--tree", "/srv/data.db"]);
+    let text = String::from_utf8_lossy(&out.stdout);
+    assert!(
+        text.contains("\n  4100 supervisor\n    4242 fixture  open fd"),
+        "{text}"
+    );
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn unreadable_maps_keep_fd_evidence() {
+    let root = fixture("whoholds_maps_error");
+    fs::remove_file(root.join("4242/maps")).unwrap();
+    fs::create_dir(root.join("4242/maps")).unwrap();
+    let v = run_json(&root, &["/srv/data.db"]);
+    assert_eq!(v["errored"], 1, "{v}");
+    assert_eq!(v["partial"], true);
+    assert_eq!(v["results"][0]["pid"], 4242);
+    assert_eq!(v["results"][0]["reason"], "open fd");
+    let _ = fs::remove_dir_all(&root);
+}
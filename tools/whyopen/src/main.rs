@@ -3,18 +3,27 @@
 use clap::{error::ErrorKind, Parser};
 use serde::Serialize;
 use serde_json::json;
-use std::collections::{BTreeMap, HashMap, HashSet};
-use std::path::{Path, PathBuf};
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::path::PathBuf;
 
 use cliutil::{
-    error, print_header, print_info, print_json_error, print_json_payload, print_version,
-    privilege_mode, privilege_mode_message, AppError,
+    distinct_commands, exit_with_error, format_distinct_commands, print_header, print_info,
+    print_json_envelope, print_json_error, print_scan_envelope, print_stable, print_version,
+    privilege_insufficient, privilege_mode, privilege_mode_message, reexec_with_sudo,
+    set_color_choice, sort_reasons, stable_line, validate_fields, warn, AppError, ColorChoice,
+    Exclusions, ExitCode, JsonEnvelope, OutputFormat, ReasonKind, ScanScopes, ScanStats, Table,
+    Target,
 };
-use fsmeta::{dev_major_minor, file_id_for_path};
 use procscan::{
-    list_pids, parse_socket_inode, proto_label, read_comm_best_effort, read_fd_links_access,
-    read_proc_net_sockets, scan_pid_mmap_file, scan_pid_open_fd_file, socket_state_label,
-    ProcAccess,
+    access_mode, fd_rows, file_match_for_inode, file_match_for_path, find_pid_mmap_match,
+    find_pid_open_fd_match, list_pids, open_flag_names, parse_socket_inode, pid_dir_link_matches,
+    pid_exe_match, proto_label, read_comm_best_effort, read_exe_best_effort,
+    read_fd_targets_access, read_fdinfo_flags, read_proc_maps_access, read_smaps_rollup,
+    read_socket_inodes, set_command_source, set_id_match, set_proc_root, set_unknown_label,
+    socket_state_label, socket_timer_name, task_comms_best_effort, Comm, CommandSource, DirLink,
+    FileMatch, IdMatch, ProcAccess, ProcNetSocketEntry, ProcSnapshot, SocketTable, FD_ROW_FIELDS,
+    SOCKET_TIMER_TICKS_PER_SEC,
 };
 
 const COMMAND_COL_WIDTH: usize = 16;
@@ -28,7 +37,22 @@ const COMMAND_COL_WIDTH: usize = 16;
     after_help = r#"EXAMPLES:
   whyopen /var/log/syslog
   whyopen 443
+  whyopen --exclude-pid 1 /run/lock
+  whyopen --mem /usr/lib/x86_64-linux-gnu/libc.so.6
+  whyopen --threads /dev/dri/card0
+  whyopen 80 /etc/hosts 443
+  whyopen --stream /usr/lib/x86_64-linux-gnu/libc.so.6
+  whyopen --pid 1234 --fds
+  whyopen --pid 1234
   whyopen --json 443
+
+EXIT STATUS:
+  0  success (partial results are flagged by "partial" and
+     "privilege_insufficient" in --json output)
+  1  invalid input
+  2  fatal error before the scan could start (per-process read
+     errors are counted as "errored" instead)
+  3  no process found (only with --fail-if-empty)
 "#
 )]
 struct Args {
@@ -49,19 +73,414 @@ struct Args {
     )]
     json: bool,
 
+    #[arg(
+        long = "color",
+        value_enum,
+        value_name = "WHEN",
+        default_value = "auto",
+        help = "Color warnings, errors and headers: auto (terminal, unless NO_COLOR is set), always, or never"
+    )]
+    color: ColorChoice,
+
+    #[arg(
+        long = "include-host",
+        requires = "json",
+        help = "Add a host block (hostname, boot_id) to JSON output"
+    )]
+    include_host: bool,
+
+    #[arg(
+        long = "no-mode-message",
+        requires = "json",
+        help = "Omit the human-oriented mode_message field from JSON output"
+    )]
+    no_mode_message: bool,
+
+    #[arg(
+        long = "fields",
+        value_name = "LIST",
+        value_delimiter = ',',
+        requires = "json",
+        help = "Only include these comma-separated result fields in JSON output"
+    )]
+    fields: Vec<String>,
+
+    #[arg(
+        long = "list-skipped",
+        requires = "json",
+        help = "List the PIDs behind the skipped and errored counts in JSON output"
+    )]
+    list_skipped: bool,
+
+    #[arg(
+        long = "exclude-pid",
+        value_name = "PID",
+        help = "Drop this PID from the results (repeatable)"
+    )]
+    exclude_pid: Vec<i32>,
+
+    #[arg(
+        long = "exclude-command",
+        value_name = "NAME",
+        help = "Drop processes whose command is NAME (repeatable)"
+    )]
+    exclude_command: Vec<String>,
+
+    #[arg(
+        long = "fail-if-empty",
+        help = "Exit with status 3 when no process has the targets open"
+    )]
+    fail_if_empty: bool,
+
+    #[arg(
+        long = "inode-only",
+        conflicts_with = "proc_root",
+        help = "Match mappings by inode alone when the device differs (overlayfs, bind mounts)"
+    )]
+    inode_only: bool,
+
+    #[arg(
+        long = "sudo",
+        conflicts_with = "proc_root",
+        help = "Re-run this command under sudo when not already root"
+    )]
+    sudo: bool,
+
+    #[arg(
+        long = "proc-root",
+        value_name = "DIR",
+        help = "Scan a captured proc tree in DIR instead of the live /proc"
+    )]
+    proc_root: Option<PathBuf>,
+
+    #[arg(
+        long = "mem",
+        help = "Annotate memory-mapped reasons with the process RSS/PSS (smaps_rollup)"
+    )]
+    mem: bool,
+
+    #[arg(
+        long = "fd-flags",
+        help = "Show how each matching fd was opened (access mode, append, cloexec, ...)"
+    )]
+    fd_flags: bool,
+
+    #[arg(
+        long = "socket-timers",
+        help = "Show the pending kernel timer of each socket (keepalive, retransmit, ...)"
+    )]
+    socket_timers: bool,
+
+    #[arg(
+        long = "no-fd",
+        conflicts_with = "no_mmap",
+        help = "Skip the open-fd scan; paths are matched by memory mappings only"
+    )]
+    no_fd: bool,
+
+    #[arg(
+        long = "no-mmap",
+        help = "Skip the memory-map scan; paths are matched by open fds only"
+    )]
+    no_mmap: bool,
+
+    #[arg(
+        long = "threads",
+        help = "List the threads (TID and per-thread name) of each matching process"
+    )]
+    threads: bool,
+
+    #[arg(
+        long = "pid",
+        value_name = "PID",
+        conflicts_with_all = ["targets", "stream", "fail_if_empty"],
+        help = "Invert the question: list what PID holds (files by fd, mappings, sockets)"
+    )]
+    pid: Option<i32>,
+
+    #[arg(
+        long = "fds",
+        requires = "pid",
+        conflicts_with = "no_fd",
+        help = "With --pid, list every fd by type (file, socket, pipe, anon, other) instead of grouping by target"
+    )]
+    fds: bool,
+
+    #[arg(
+        long = "format",
+        value_enum,
+        value_name = "FORMAT",
+        default_value = "human",
+        conflicts_with_all = ["json", "stream"],
+        help = "Human output style: human (narrative) or stable (sorted tab-separated lines for diff)"
+    )]
+    format: OutputFormat,
+
+    #[arg(
+        long = "stream",
+        conflicts_with = "json",
+        help = "Print each process as soon as it is found (unsorted; human output only)"
+    )]
+    stream: bool,
+
+    #[arg(
+        long = "unknown-label",
+        value_name = "LABEL",
+        help = "Text shown for a process name that cannot be read (default <unknown>)"
+    )]
+    unknown_label: Option<String>,
+
+    #[arg(
+        long = "command-source",
+        value_enum,
+        value_name = "SOURCE",
+        default_value = "cmdline",
+        help = "Where command names come from: cmdline (comm, completed from argv[0] when truncated), comm or argv"
+    )]
+    command_source: CommandSource,
+
+    #[arg(
+        long = "full-command",
+        conflicts_with = "command_source",
+        help = "Show each process's full command line instead of its name (same as --command-source argv)"
+    )]
+    full_command: bool,
+
     #[arg(
         value_name = "TARGET",
-        required_unless_present_any = ["version", "info"],
-        help = "File path or port number to inspect"
+        required_unless_present_any = ["version", "info", "pid"],
+        help = "File paths and/or port numbers to inspect, in one scan"
     )]
-    target: Option<String>,
+    targets: Vec<String>,
+}
+
+/// Result keys accepted by `--fields`.
+const RESULT_FIELDS: &[&str] = &["pid", "command", "exe", "reasons", "threads"];
+const HELD_FIELDS: &[&str] = &["target", "deleted", "remote", "reasons"];
+
+/// One thing a `--pid` process holds and every way it holds it.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+struct HeldRow {
+    target: String,
+    /// The file has been unlinked since it was opened or mapped.
+    #[serde(skip_serializing_if = "is_false")]
+    deleted: bool,
+    /// Peer address of a connected socket (`target` is the local one).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    remote: Option<String>,
+    reasons: Vec<Reason>,
+}
+
+impl HeldRow {
+    fn new(target: String) -> Self {
+        HeldRow {
+            target,
+            deleted: false,
+            remote: None,
+            reasons: Vec::new(),
+        }
+    }
+
+    fn label(&self) -> String {
+        let mut out = self.target.clone();
+        if let Some(remote) = &self.remote {
+            out.push_str(&format!(" -> {remote}"));
+        }
+        if self.deleted {
+            out.push_str(" (deleted)");
+        }
+        out
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Serialize)]
 struct ProcResult {
     pid: i32,
+    command: Comm,
+    /// Executable path (best effort; omitted when unreadable).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exe: Option<String>,
+    reasons: Vec<Reason>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    threads: Option<Vec<ThreadRow>>,
+}
+
+/// One thread of a matching process (`--threads`). Fds and mappings belong
+/// to the whole process; per-thread names only tell the threads apart.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+struct ThreadRow {
+    tid: i32,
     command: String,
-    reasons: Vec<String>,
+}
+
+fn thread_rows(pid: i32) -> Vec<ThreadRow> {
+    task_comms_best_effort(pid)
+        .into_iter()
+        .map(|(tid, command)| ThreadRow { tid, command })
+        .collect()
+}
+
+fn attach_threads(results: &mut BTreeMap<i32, ProcResult>) {
+    for r in results.values_mut() {
+        r.threads = Some(thread_rows(r.pid));
+    }
+}
+
+/// One piece of evidence for why a process holds the target.
+///
+/// Serialized with a stable `type` tag plus details. Display order is the
+/// shared [`ReasonKind`] order (see [`Reason::kind`]), then these details.
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Reason {
+    OpenFd {
+        fd: i32,
+        /// `ro`, `wo` or `rw` from fdinfo; `None` when it could not be read.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        access: Option<&'static str>,
+        /// Decoded fdinfo flags (`--fd-flags`), e.g. `["wo", "append"]`.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        flags: Option<Vec<&'static str>>,
+    },
+    /// The target is the process's executable (/proc/<pid>/exe).
+    Executing {
+        /// The running binary has since been replaced or removed.
+        #[serde(skip_serializing_if = "is_false")]
+        deleted: bool,
+    },
+    Mmap {
+        perms: Vec<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        process_rss_kb: Option<u64>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        process_pss_kb: Option<u64>,
+        /// The mapped file has been unlinked (`(deleted)` in maps).
+        #[serde(skip_serializing_if = "is_false")]
+        deleted: bool,
+    },
+    /// The target is the process's working directory.
+    Cwd,
+    /// The target is the process's root directory (chroot).
+    Root,
+    Socket {
+        proto: &'static str,
+        state: String,
+        /// Armed kernel timer (`--socket-timers`).
+        #[serde(skip_serializing_if = "Option::is_none")]
+        timer: Option<SocketTimer>,
+    },
+}
+
+/// A socket's pending timer from the /proc/net `tr` and `tm->when`
+/// columns. A keepalive timer marks a live idle connection; retransmit or
+/// time_wait point at one that is stalled or already closing.
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Serialize)]
+struct SocketTimer {
+    name: &'static str,
+    expires_secs: u64,
+}
+
+impl SocketTimer {
+    fn from_entry(s: &ProcNetSocketEntry) -> Option<Self> {
+        Some(SocketTimer {
+            name: socket_timer_name(s.timer)?,
+            expires_secs: s.timer_ticks / SOCKET_TIMER_TICKS_PER_SEC,
+        })
+    }
+}
+
+impl From<DirLink> for Reason {
+    fn from(link: DirLink) -> Self {
+        match link {
+            DirLink::Cwd => Reason::Cwd,
+            DirLink::Root => Reason::Root,
+        }
+    }
+}
+
+impl Reason {
+    fn kind(&self) -> ReasonKind {
+        match self {
+            Reason::OpenFd { .. } => ReasonKind::OpenFd,
+            Reason::Executing { .. } => ReasonKind::Exe,
+            Reason::Mmap { .. } => ReasonKind::Mmap,
+            Reason::Cwd => ReasonKind::Cwd,
+            Reason::Root => ReasonKind::Root,
+            Reason::Socket { .. } => ReasonKind::Socket,
+        }
+    }
+
+    /// Human-readable form used by the text output.
+    fn label(&self) -> String {
+        match self {
+            Reason::OpenFd {
+                fd,
+                flags: Some(flags),
+                ..
+            } => format!("open fd {fd} ({})", flags.join(", ")),
+            Reason::OpenFd {
+                fd,
+                access: Some(access),
+                flags: None,
+            } => format!("open fd {fd} ({access})"),
+            Reason::OpenFd {
+                fd,
+                access: None,
+                flags: None,
+            } => format!("open fd {fd}"),
+            Reason::Executing { deleted: false } => "executing".to_string(),
+            Reason::Executing { deleted: true } => "executing (deleted)".to_string(),
+            Reason::Mmap {
+                perms,
+                process_rss_kb,
+                process_pss_kb,
+                deleted,
+            } => {
+                let mut details = perms.join(", ");
+                if *deleted {
+                    details.push_str(", deleted");
+                }
+                if let (Some(rss), Some(pss)) = (process_rss_kb, process_pss_kb) {
+                    details.push_str(&format!(
+                        "; process rss {}, pss {}",
+                        format_kb_as_mib(*rss),
+                        format_kb_as_mib(*pss)
+                    ));
+                }
+                format!("memory mapped ({details})")
+            }
+            Reason::Cwd => "working directory (cwd)".to_string(),
+            Reason::Root => "root directory (chroot)".to_string(),
+            Reason::Socket {
+                proto,
+                state,
+                timer: None,
+            } => format!("socket {proto} {state}"),
+            Reason::Socket {
+                proto,
+                state,
+                timer: Some(t),
+            } => format!(
+                "socket {proto} {state}, {} timer ({}s left)",
+                t.name.replace('_', " "),
+                t.expires_secs
+            ),
+        }
+    }
+}
+
+/// Per-run settings shared by the path and port modes.
+struct Options {
+    json: bool,
+    envelope: JsonEnvelope,
+    mem: bool,
+    fd_flags: bool,
+    socket_timers: bool,
+    scopes: ScanScopes,
+    threads: bool,
+    format: OutputFormat,
+    stream: bool,
+    exclusions: Exclusions,
 }
 
 fn main() {
@@ -72,269 +491,698 @@ fn main() {
         Err(e) => {
             if matches!(e.kind(), ErrorKind::DisplayHelp | ErrorKind::DisplayVersion) {
                 let _ = e.print();
-                std::process::exit(0);
+                ExitCode::Success.exit();
             }
             if json_requested {
                 print_json_error(AppError::InvalidInput(e.to_string()));
             } else {
                 let _ = e.print();
             }
-            std::process::exit(1);
+            ExitCode::InvalidInput.exit();
         }
     };
 
     match run(args) {
-        Ok(()) => std::process::exit(0),
-        Err(AppError::InvalidInput(e)) => {
-            if json_requested {
-                print_json_error(AppError::InvalidInput(e));
-            } else {
-                error(&e);
-            }
-            std::process::exit(1);
-        }
-        Err(AppError::Fatal(e)) => {
-            if json_requested {
-                print_json_error(AppError::Fatal(e));
-            } else {
-                error(&e);
-            }
-            std::process::exit(2);
-        }
+        Ok(ExitCode::Success) => {}
+        Ok(code) => code.exit(),
+        Err(e) => exit_with_error(e, json_requested),
     }
 }
 
-fn run(args: Args) -> Result<(), AppError> {
+fn run(args: Args) -> Result<ExitCode, AppError> {
+    set_color_choice(args.color);
+
     if args.version {
         print_version();
-        return Ok(());
+        return Ok(ExitCode::Success);
     }
 
     if args.info {
         print_info();
-        return Ok(());
+        return Ok(ExitCode::Success);
     }
 
-    let target = args
-        .target
-        .ok_or_else(|| AppError::InvalidInput("missing target".to_string()))?;
+    if args.sudo {
+        reexec_with_sudo()?;
+    }
 
-    if let Ok(port) = target.parse::<u16>() {
-        return whyopen_port(port, args.json);
+    if let Some(root) = &args.proc_root {
+        set_proc_root(root).map_err(|e| AppError::InvalidInput(e.to_string()))?;
     }
 
-    let path = PathBuf::from(&target);
-    whyopen_path(&path, args.json)
+    if args.inode_only {
+        set_id_match(IdMatch::InodeOnly);
+    }
+
+    if args.targets.is_empty() && args.pid.is_none() {
+        return Err(AppError::InvalidInput("missing target".to_string()));
+    }
+
+    if let Some(label) = &args.unknown_label {
+        set_unknown_label(label);
+    }
+    set_command_source(if args.full_command {
+        CommandSource::Argv
+    } else {
+        args.command_source
+    });
+
+    if let (true, Some(pid)) = (args.fds, args.pid) {
+        validate_fields(&args.fields, FD_ROW_FIELDS)?;
+        let envelope = JsonEnvelope {
+            include_host: args.include_host,
+            omit_mode_message: args.no_mode_message,
+            fields: args.fields.clone(),
+            ..JsonEnvelope::default()
+        };
+        whyopen_fds(pid, args.json, args.format, &envelope)?;
+        return Ok(ExitCode::Success);
+    }
+
+    let known_fields = if args.pid.is_some() {
+        HELD_FIELDS
+    } else {
+        RESULT_FIELDS
+    };
+    validate_fields(&args.fields, known_fields)?;
+
+    let targets: Vec<Target> = args.targets.iter().map(|t| Target::parse(t)).collect();
+    let scopes = ScanScopes::new(args.no_fd, args.no_mmap);
+    if !scopes.fd {
+        if let Some(port) = targets.iter().find(|t| matches!(t, Target::Port(_))) {
+            return Err(AppError::InvalidInput(format!(
+                "--no-fd cannot be used with port target {port}: sockets are found through open fds"
+            )));
+        }
+    }
+
+    let opts = Options {
+        json: args.json,
+        envelope: JsonEnvelope {
+            include_host: args.include_host,
+            omit_mode_message: args.no_mode_message,
+            fields: args.fields.clone(),
+            list_skipped: args.list_skipped,
+            scopes: Some(scopes),
+        },
+        mem: args.mem,
+        fd_flags: args.fd_flags,
+        socket_timers: args.socket_timers,
+        scopes,
+        format: args.format,
+        threads: args.threads,
+        stream: args.stream,
+        exclusions: Exclusions::new(&args.exclude_pid, &args.exclude_command),
+    };
+
+    if let Some(pid) = args.pid {
+        whyopen_pid(pid, &opts, &mut SocketTable::default())?;
+        return Ok(ExitCode::Success);
+    }
+    let found = whyopen_targets(targets, &opts, &mut SocketTable::default())?;
+    Ok(ExitCode::for_scan(found, args.fail_if_empty))
 }
 
-fn whyopen_path(path: &Path, json_out: bool) -> Result<(), AppError> {
-    let target_id = match file_id_for_path(path) {
-        Ok(id) => id,
-        Err(e) => {
-            let msg = format!("{}: {}", path.display(), e);
-            if e.kind() == std::io::ErrorKind::NotFound {
-                return Err(AppError::InvalidInput(msg));
-            }
-            return Err(AppError::Fatal(msg));
+/// `--pid --fds`: every fd `pid` holds, with its kind, so pipes, eventfds and
+/// epoll instances show up next to files and sockets.
+fn whyopen_fds(
+    pid: i32,
+    json_out: bool,
+    format: OutputFormat,
+    envelope: &JsonEnvelope,
+) -> Result<(), AppError> {
+    let targets = match read_fd_targets_access(pid) {
+        ProcAccess::Ok(v) => v,
+        ProcAccess::PermissionDenied => {
+            return Err(AppError::Fatal(format!(
+                "permission denied reading /proc/{pid}/fd (re-run as root)"
+            )))
         }
+        ProcAccess::Gone => return Err(AppError::InvalidInput(format!("no such process: {pid}"))),
+        ProcAccess::Fatal(e) => return Err(AppError::Fatal(e.to_string())),
     };
+    let command = read_comm_best_effort(pid);
+    let rows = fd_rows(targets, SocketTable::default().load(warn));
 
-    let (tmaj, tmin) = dev_major_minor(target_id.dev);
+    if json_out {
+        let payload = json!({
+            "privilege": privilege_mode(),
+            "mode_message": privilege_mode_message(),
+            "mode": "whyopen",
+            "target_mode": "fds",
+            "target": pid.to_string(),
+            "pid": pid,
+            "command": command,
+            "results": rows,
+        });
+        print_json_envelope(payload, envelope);
+        return Ok(());
+    }
 
-    let mut results: BTreeMap<i32, ProcResult> = BTreeMap::new();
-    let mut skipped_permission_denied: HashSet<i32> = HashSet::new();
+    if format == OutputFormat::Stable {
+        print_stable(
+            rows.iter()
+                .map(|r| stable_line([r.fd.to_string(), r.kind.to_string(), r.detail()]))
+                .collect(),
+        );
+        return Ok(());
+    }
 
-    let pids = list_pids().map_err(|e| AppError::Fatal(e.to_string()))?;
+    println!("{}", privilege_mode_message());
+    println!("Process: {pid} ({command})");
+    if rows.is_empty() {
+        println!("No open fds.");
+        return Ok(());
+    }
+    let mut table = Table::new(["FD", "TYPE", "TARGET"]).fixed(0);
+    for r in &rows {
+        table.row([r.fd.to_string(), r.kind.to_string(), r.detail()]);
+    }
+    table.print();
+    Ok(())
+}
 
-    for pid in pids {
-        let mut any_denied = false;
-        let mut reasons: Vec<String> = Vec::new();
-        let mut comm: Option<String> = None;
+fn socket_reason(s: &ProcNetSocketEntry, timers: bool) -> Reason {
+    Reason::Socket {
+        proto: proto_label(s.proto),
+        state: socket_state_label(s.proto, s.state),
+        timer: timers.then(|| SocketTimer::from_entry(s)).flatten(),
+    }
+}
 
-        match scan_pid_open_fd_file(pid, target_id) {
-            ProcAccess::Ok(true) => {
-                reasons.push("open fd".to_string());
-                comm = Some(read_comm_best_effort(pid));
-            }
-            ProcAccess::Ok(false) => {}
-            ProcAccess::PermissionDenied => {
-                any_denied = true;
-            }
-            ProcAccess::Gone => continue,
-            ProcAccess::Fatal(e) => return Err(AppError::Fatal(e.to_string())),
-        }
+/// Error for a `--pid` read that failed: the one process asked about must
+/// not be skipped silently.
+fn pid_read_error<T>(pid: i32, what: &str, access: ProcAccess<T>) -> Result<T, AppError> {
+    match access {
+        ProcAccess::Ok(v) => Ok(v),
+        ProcAccess::PermissionDenied => Err(AppError::Fatal(format!(
+            "permission denied reading /proc/{pid}/{what} (re-run as root)"
+        ))),
+        ProcAccess::Gone => Err(AppError::InvalidInput(format!("no such process: {pid}"))),
+        ProcAccess::Fatal(e) => Err(AppError::Fatal(format!("/proc/{pid}/{what}: {e}"))),
+    }
+}
+
+/// `--pid`: everything `pid` holds, grouped by target. Files come from the
+/// fd links (an unlinked file loses its ` (deleted)` marker) and map
+/// pathnames, sockets from /proc/net (a socket not found there, e.g. a
+/// unix socket, is listed by its link as an open fd). Sockets are grouped
+/// by inode, so connections sharing a local address stay apart.
+fn held_by_pid(
+    pid: i32,
+    opts: &Options,
+    sockets: &mut SocketTable,
+) -> Result<Vec<HeldRow>, AppError> {
+    // (target, socket inode or 0) -> row.
+    let mut held: BTreeMap<(String, u64), HeldRow> = BTreeMap::new();
 
-        match scan_pid_mmap_file(pid, tmaj, tmin, target_id.inode) {
-            ProcAccess::Ok(true) => {
-                reasons.push("memory mapped".to_string());
-                if comm.is_none() {
-                    comm = Some(read_comm_best_effort(pid));
+    if opts.scopes.fd {
+        let fds = pid_read_error(pid, "fd", read_fd_targets_access(pid))?;
+        let needs_sockets = fds.iter().any(|t| parse_socket_inode(&t.target).is_some());
+        let by_inode: HashMap<u64, &ProcNetSocketEntry> = if needs_sockets {
+            sockets.load(warn).iter().map(|s| (s.inode, s)).collect()
+        } else {
+            HashMap::new()
+        };
+        for t in fds {
+            let socket = parse_socket_inode(&t.target).and_then(|i| by_inode.get(&i));
+            let deleted = t.deleted;
+            let (key, reason) = match socket {
+                Some(s) => (
+                    (
+                        SocketAddr::new(s.local_addr, s.local_port).to_string(),
+                        s.inode,
+                    ),
+                    socket_reason(s, opts.socket_timers),
+                ),
+                None => {
+                    let flags = fd_flags(pid, t.fd);
+                    let reason = Reason::OpenFd {
+                        fd: t.fd,
+                        access: flags.map(access_mode),
+                        flags: flags.filter(|_| opts.fd_flags).map(open_flag_names),
+                    };
+                    ((t.target, 0), reason)
                 }
+            };
+            let row = held
+                .entry(key)
+                .or_insert_with_key(|(target, _)| HeldRow::new(target.clone()));
+            if let Some(s) = socket.filter(|s| s.remote_port != 0) {
+                row.remote = Some(SocketAddr::new(s.remote_addr, s.remote_port).to_string());
             }
-            ProcAccess::Ok(false) => {}
-            ProcAccess::PermissionDenied => {
-                any_denied = true;
-            }
-            ProcAccess::Gone => continue,
-            ProcAccess::Fatal(e) => return Err(AppError::Fatal(e.to_string())),
+            row.deleted |= deleted;
+            row.reasons.push(reason);
         }
+    }
 
-        if reasons.is_empty() {
-            if any_denied {
-                skipped_permission_denied.insert(pid);
-            }
-            continue;
+    if opts.scopes.mmap {
+        let maps = pid_read_error(pid, "maps", read_proc_maps_access(pid))?;
+        let mut mapped: BTreeMap<String, (Vec<String>, bool)> = BTreeMap::new();
+        for e in maps {
+            let Some(pathname) = e.pathname.filter(|_| e.inode != 0) else {
+                continue;
+            };
+            let entry = mapped.entry(pathname).or_default();
+            entry.0.push(e.perms);
+            entry.1 |= e.deleted;
+        }
+        for (path, (perms, deleted)) in mapped {
+            let row = held
+                .entry((path, 0))
+                .or_insert_with_key(|(target, _)| HeldRow::new(target.clone()));
+            row.deleted |= deleted;
+            row.reasons.push(mmap_reason(pid, perms, deleted, opts.mem));
         }
+    }
 
-        reasons.sort();
-        reasons.dedup();
+    Ok(held
+        .into_values()
+        .map(|mut row| {
+            sort_reasons(&mut row.reasons, Reason::kind);
+            row.reasons.dedup();
+            row
+        })
+        .collect())
+}
 
-        let comm = comm.unwrap_or_else(|| "<unknown>".to_string());
+fn whyopen_pid(pid: i32, opts: &Options, sockets: &mut SocketTable) -> Result<(), AppError> {
+    let rows = held_by_pid(pid, opts, sockets)?;
+    let command = read_comm_best_effort(pid);
 
-        results.insert(
-            pid,
-            ProcResult {
-                pid,
-                command: comm,
-                reasons,
-            },
-        );
+    if opts.json {
+        let payload = json!({
+            "privilege": privilege_mode(),
+            "mode_message": privilege_mode_message(),
+            "mode": "whyopen",
+            "target_mode": "pid",
+            "target": pid.to_string(),
+            "pid": pid,
+            "command": command,
+            "exe": read_exe_best_effort(pid),
+            "results": rows,
+        });
+        print_scan_envelope(payload, &ScanStats::default(), &opts.envelope);
+        return Ok(());
     }
 
-    if json_out {
-        print_json(
-            "path",
-            path.display().to_string(),
-            results,
-            skipped_permission_denied.len(),
-        );
-    } else {
-        print_human(
-            "path",
-            &path.display().to_string(),
-            results,
-            skipped_permission_denied.len(),
-        );
+    if opts.format == OutputFormat::Stable {
+        let lines = rows
+            .iter()
+            .flat_map(|r| {
+                r.reasons
+                    .iter()
+                    .map(|reason| stable_line([r.label(), reason.label()]))
+            })
+            .collect();
+        print_stable(lines);
+        return Ok(());
     }
 
+    println!("{}", privilege_mode_message());
+    opts.scopes.print_notice();
+    println!("Process: {pid} ({command})");
+    println!();
+    if rows.is_empty() {
+        println!("Holds nothing.");
+        return Ok(());
+    }
+    print_header("Holds:");
+    let mut table = Table::new(["TARGET"]);
+    for r in &rows {
+        table.row([r.label()]);
+        for reason in &r.reasons {
+            table.detail(format!("  - {}", reason.label()));
+        }
+    }
+    table.print();
     Ok(())
 }
 
-fn whyopen_port(port: u16, json_out: bool) -> Result<(), AppError> {
-    let sockets = read_proc_net_sockets().map_err(|e| AppError::Fatal(e.to_string()))?;
+/// What a target is matched against, prepared once before the walk.
+enum Matcher {
+    Path(FileMatch),
+    /// Socket reasons per inode bound to the port; empty when nothing is bound.
+    Port(HashMap<u64, Vec<Reason>>),
+}
 
-    let mut inode_to_labels: HashMap<u64, Vec<String>> = HashMap::new();
+/// Reasons found for one target of a (possibly multi-target) scan.
+struct TargetScan {
+    target: Target,
+    matcher: Matcher,
+    results: BTreeMap<i32, ProcResult>,
+    stats: ScanStats,
+}
 
-    for s in sockets {
-        if s.local_port != port {
-            continue;
-        }
-        let label = format!(
-            "socket {} {}",
-            proto_label(s.proto),
-            socket_state_label(s.proto, s.state)
-        );
-        inode_to_labels.entry(s.inode).or_default().push(label);
+impl TargetScan {
+    /// A port nothing is bound to cannot have holders.
+    fn needs_walk(&self) -> bool {
+        !matches!(&self.matcher, Matcher::Port(labels) if labels.is_empty())
     }
 
-    let target_inodes: HashSet<u64> = inode_to_labels.keys().copied().collect();
-
-    let mut results: BTreeMap<i32, ProcResult> = BTreeMap::new();
-    let mut skipped_permission_denied: HashSet<i32> = HashSet::new();
-
-    if target_inodes.is_empty() {
-        if json_out {
-            print_json("port", port.to_string(), results, 0);
-        } else {
-            print_human("port", &port.to_string(), results, 0);
+    fn scan_pid(&mut self, proc: &ProcSnapshot, opts: &Options) {
+        let found = match &self.matcher {
+            Matcher::Path(target) => scan_pid_path(proc, target, opts, &mut self.stats),
+            Matcher::Port(labels) => scan_pid_port(proc, labels, &mut self.stats),
+        };
+        if let Some(result) = found {
+            self.results.insert(proc.pid, result);
         }
-        return Ok(());
     }
+}
 
-    let pids = list_pids().map_err(|e| AppError::Fatal(e.to_string()))?;
+fn prepare_targets(
+    targets: Vec<Target>,
+    opts: &Options,
+    sockets: &mut SocketTable,
+) -> Result<Vec<TargetScan>, AppError> {
+    let mut scans = Vec::new();
 
-    for pid in pids {
-        let links = match read_fd_links_access(pid) {
-            ProcAccess::Ok(v) => v,
-            ProcAccess::PermissionDenied => {
-                skipped_permission_denied.insert(pid);
-                continue;
+    for target in targets {
+        let matcher = match &target {
+            Target::Path(path) => match file_match_for_path(path) {
+                Ok(m) => Matcher::Path(m),
+                Err(e) => {
+                    let msg = format!("{}: {}", path.display(), e);
+                    if e.kind() == std::io::ErrorKind::NotFound {
+                        return Err(AppError::InvalidInput(msg));
+                    }
+                    return Err(AppError::Fatal(msg));
+                }
+            },
+            Target::Inode {
+                major,
+                minor,
+                inode,
+            } => Matcher::Path(file_match_for_inode(*major, *minor, *inode)),
+            Target::Port(port) => {
+                let entries = sockets.load(warn);
+                let mut labels: HashMap<u64, Vec<Reason>> = HashMap::new();
+                for s in entries.iter().filter(|s| s.local_port == *port) {
+                    let reason = socket_reason(s, opts.socket_timers);
+                    labels.entry(s.inode).or_default().push(reason);
+                }
+                Matcher::Port(labels)
             }
-            ProcAccess::Gone => continue,
-            ProcAccess::Fatal(e) => return Err(AppError::Fatal(e.to_string())),
         };
+        scans.push(TargetScan {
+            target,
+            matcher,
+            results: BTreeMap::new(),
+            stats: ScanStats::default(),
+        });
+    }
 
-        let mut reasons: Vec<String> = Vec::new();
-        let mut comm: Option<String> = None;
+    Ok(scans)
+}
+
+/// Explain every path/port target in one walk of the process table. Port
+/// targets take their sockets from `sockets`, read here unless prefetched.
+fn whyopen_targets(
+    targets: Vec<Target>,
+    opts: &Options,
+    sockets: &mut SocketTable,
+) -> Result<bool, AppError> {
+    let mut scans = prepare_targets(targets, opts, sockets)?;
+    if opts.stream {
+        return stream_targets(scans, opts);
+    }
 
-        for (_fd, _fd_path, link) in links {
-            let Some(inode) = parse_socket_inode(&link) else {
+    if scans.iter().any(TargetScan::needs_walk) {
+        let pids = list_pids().map_err(|e| AppError::Fatal(e.to_string()))?;
+        for pid in pids {
+            let ProcAccess::Ok(proc) = ProcSnapshot::capture(pid) else {
                 continue;
             };
-            if !target_inodes.contains(&inode) {
-                continue;
+            for scan in scans.iter_mut().filter(|s| s.needs_walk()) {
+                scan.scan_pid(&proc, opts);
             }
+        }
+    }
 
-            if comm.is_none() {
-                comm = Some(read_comm_best_effort(pid));
-            }
+    for scan in &mut scans {
+        scan.results
+            .retain(|pid, r| !opts.exclusions.excludes(*pid, &r.command));
+        if opts.threads {
+            attach_threads(&mut scan.results);
+        }
+    }
+    let found = scans.iter().any(|s| !s.results.is_empty());
 
-            if let Some(labels) = inode_to_labels.get(&inode) {
-                reasons.extend(labels.iter().cloned());
-            } else {
-                reasons.push("socket".to_string());
-            }
+    if opts.format == OutputFormat::Stable {
+        print_stable_targets(scans);
+        return Ok(found);
+    }
+
+    if scans.len() == 1 {
+        let scan = scans.remove(0);
+        let mode = scan.target.mode();
+        let target = scan.target.to_string();
+        if opts.json {
+            print_json(mode, target, scan.results, &scan.stats, &opts.envelope);
+        } else {
+            print_human(mode, &target, scan.results, &scan.stats, opts.scopes);
         }
+        return Ok(found);
+    }
 
-        if reasons.is_empty() {
-            continue;
+    let mut stats = ScanStats::default();
+    for scan in &scans {
+        stats.merge(&scan.stats);
+    }
+    if opts.json {
+        print_json_multi(scans, &stats, &opts.envelope);
+    } else {
+        print_human_multi(scans, &stats, opts.scopes);
+    }
+    Ok(found)
+}
+
+fn scan_pid_path(
+    proc: &ProcSnapshot,
+    target: &FileMatch,
+    opts: &Options,
+    stats: &mut ScanStats,
+) -> Option<ProcResult> {
+    // A failed read is counted as an error but keeps the evidence found
+    // through the other links.
+    let pid = proc.pid;
+    let mut any_denied = false;
+    let mut reasons: Vec<Reason> = Vec::new();
+
+    let fds = if opts.scopes.fd {
+        find_pid_open_fd_match(pid, target)
+    } else {
+        ProcAccess::Ok(Vec::new())
+    };
+    match fds {
+        ProcAccess::Ok(fds) if !fds.is_empty() => {
+            reasons.extend(fds.into_iter().map(|fd| {
+                let flags = fd_flags(pid, fd);
+                Reason::OpenFd {
+                    fd,
+                    access: flags.map(access_mode),
+                    flags: flags.filter(|_| opts.fd_flags).map(open_flag_names),
+                }
+            }));
+        }
+        ProcAccess::Ok(_) => {}
+        ProcAccess::PermissionDenied => {
+            any_denied = true;
         }
+        ProcAccess::Gone => return None,
+        ProcAccess::Fatal(e) => stats.error(pid, &e),
+    }
 
-        reasons.sort();
-        reasons.dedup();
+    let maps = if opts.scopes.mmap {
+        find_pid_mmap_match(pid, target)
+    } else {
+        ProcAccess::Ok(Vec::new())
+    };
+    match maps {
+        ProcAccess::Ok(entries) if !entries.is_empty() => {
+            let deleted = entries.iter().any(|e| e.deleted);
+            let perms = entries.into_iter().map(|e| e.perms).collect();
+            reasons.push(mmap_reason(pid, perms, deleted, opts.mem));
+        }
+        ProcAccess::Ok(_) => {}
+        ProcAccess::PermissionDenied => {
+            any_denied = true;
+        }
+        ProcAccess::Gone => return None,
+        ProcAccess::Fatal(e) => stats.error(pid, &e),
+    }
 
-        let comm = comm.unwrap_or_else(|| "<unknown>".to_string());
+    // The running binary is also mapped, but being its exe is the stronger
+    // evidence. Kernel threads have no exe link.
+    if opts.scopes.mmap {
+        match pid_exe_match(pid, target) {
+            ProcAccess::Ok(Some(deleted)) => reasons.push(Reason::Executing { deleted }),
+            ProcAccess::Ok(None) | ProcAccess::Gone => {}
+            ProcAccess::PermissionDenied => any_denied = true,
+            ProcAccess::Fatal(e) => stats.error(pid, &e),
+        }
+    }
 
-        results.insert(
-            pid,
-            ProcResult {
-                pid,
-                command: comm,
-                reasons,
-            },
-        );
+    // A cwd or chroot keeps a directory busy without any open fd. A missing
+    // link (e.g. left out of a captured tree) is just no evidence.
+    for link in DirLink::ALL.into_iter().filter(|_| opts.scopes.fd) {
+        match pid_dir_link_matches(pid, link, target) {
+            ProcAccess::Ok(true) => reasons.push(Reason::from(link)),
+            ProcAccess::Ok(false) | ProcAccess::Gone => {}
+            ProcAccess::PermissionDenied => any_denied = true,
+            ProcAccess::Fatal(e) => stats.error(pid, &e),
+        }
     }
 
-    if json_out {
-        print_json(
-            "port",
-            port.to_string(),
-            results,
-            skipped_permission_denied.len(),
-        );
+    if reasons.is_empty() {
+        if any_denied {
+            stats.skip(pid);
+        }
+        return None;
+    }
+
+    sort_reasons(&mut reasons, Reason::kind);
+    reasons.dedup();
+
+    Some(ProcResult {
+        pid,
+        command: proc.comm.clone(),
+        exe: read_exe_best_effort(pid),
+        reasons,
+        threads: None,
+    })
+}
+
+/// Best effort: an fd closed since it was matched just loses its flags.
+fn fd_flags(pid: i32, fd: i32) -> Option<u32> {
+    match read_fdinfo_flags(pid, fd) {
+        ProcAccess::Ok(flags) => Some(flags),
+        ProcAccess::PermissionDenied | ProcAccess::Gone | ProcAccess::Fatal(_) => None,
+    }
+}
+
+fn scan_pid_port(
+    proc: &ProcSnapshot,
+    labels: &HashMap<u64, Vec<Reason>>,
+    stats: &mut ScanStats,
+) -> Option<ProcResult> {
+    let pid = proc.pid;
+    let inodes = match read_socket_inodes(pid) {
+        ProcAccess::Ok(v) => v,
+        ProcAccess::PermissionDenied => {
+            stats.skip(pid);
+            return None;
+        }
+        ProcAccess::Gone => return None,
+        ProcAccess::Fatal(e) => {
+            stats.error(pid, &e);
+            return None;
+        }
+    };
+
+    let mut reasons: Vec<Reason> = inodes
+        .iter()
+        .filter_map(|inode| labels.get(inode))
+        .flatten()
+        .cloned()
+        .collect();
+
+    if reasons.is_empty() {
+        return None;
+    }
+
+    sort_reasons(&mut reasons, Reason::kind);
+    reasons.dedup();
+
+    Some(ProcResult {
+        pid,
+        command: proc.comm.clone(),
+        exe: read_exe_best_effort(pid),
+        reasons,
+        threads: None,
+    })
+}
+
+fn mmap_reason(pid: i32, mut perms: Vec<String>, deleted: bool, mem: bool) -> Reason {
+    perms.sort();
+    perms.dedup();
+
+    let rollup = if mem {
+        match read_smaps_rollup(pid) {
+            ProcAccess::Ok(r) => Some(r),
+            ProcAccess::PermissionDenied | ProcAccess::Gone | ProcAccess::Fatal(_) => None,
+        }
     } else {
-        print_human(
-            "port",
-            &port.to_string(),
-            results,
-            skipped_permission_denied.len(),
-        );
+        None
+    };
+
+    Reason::Mmap {
+        perms,
+        process_rss_kb: rollup.as_ref().map(|r| r.rss_kb),
+        process_pss_kb: rollup.as_ref().map(|r| r.pss_kb),
+        deleted,
     }
+}
 
-    Ok(())
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+fn format_kb_as_mib(kb: u64) -> String {
+    format!("{:.1} MiB", kb as f64 / 1024.0)
 }
 
 fn print_human(
     mode: &'static str,
     target: &str,
     results: BTreeMap<i32, ProcResult>,
-    skipped: usize,
+    stats: &ScanStats,
+    scopes: ScanScopes,
 ) {
     println!("{}", privilege_mode_message());
-    if skipped > 0 {
-        println!("Partial result: {skipped} processes skipped (permission denied)");
+    scopes.print_notice();
+    stats.print_notices();
+    print_target_results(mode, target, results);
+}
+
+/// `--format stable`: one `PID COMMAND REASON` line per reason, prefixed by
+/// the target when there are several.
+fn print_stable_targets(scans: Vec<TargetScan>) {
+    let multi = scans.len() > 1;
+    let mut stats = ScanStats::default();
+    let mut lines = Vec::new();
+    for scan in scans {
+        stats.merge(&scan.stats);
+        let target = scan.target.to_string();
+        for r in scan.results.values() {
+            for reason in &r.reasons {
+                let mut fields = vec![r.pid.to_string(), r.command.to_string(), reason.label()];
+                if multi {
+                    fields.insert(0, target.clone());
+                }
+                lines.push(stable_line(fields));
+            }
+        }
+    }
+    stats.warn_notices();
+    print_stable(lines);
+}
+
+fn print_human_multi(scans: Vec<TargetScan>, stats: &ScanStats, scopes: ScanScopes) {
+    println!("{}", privilege_mode_message());
+    scopes.print_notice();
+    stats.print_notices();
+
+    for (idx, scan) in scans.into_iter().enumerate() {
+        if idx > 0 {
+            println!();
+        }
+        print_target_results(scan.target.mode(), &scan.target.to_string(), scan.results);
     }
+}
 
+fn print_target_results(mode: &'static str, target: &str, results: BTreeMap<i32, ProcResult>) {
     match mode {
         "path" => println!("Target path: {target}"),
         "port" => println!("Target port: {target}"),
@@ -347,32 +1195,130 @@ fn print_human(
         return;
     }
 
+    let commands = distinct_commands(results.values().map(|r| (r.pid, r.command.as_str())));
+    println!("Distinct commands: {}", format_distinct_commands(&commands));
+
     print_header("Because:");
+    let mut table = Table::new(["PID", "COMMAND"]).fixed(0);
+    for r in results.values() {
+        table.row([r.pid.to_string(), r.command.to_string()]);
+        for line in result_details(r) {
+            table.detail(line);
+        }
+    }
+    table.print();
+}
 
-    for (_pid, r) in results {
-        println!(
+/// One process and its reasons; `target` tags the block in streamed
+/// multi-target scans.
+fn print_result(r: &ProcResult, target: Option<&Target>) {
+    let comm = &r.command;
+    match target {
+        Some(t) => println!(
+            "{pid:<5} {comm:<width$} [{} {t}]",
+            t.mode(),
+            pid = r.pid,
+            width = COMMAND_COL_WIDTH
+        ),
+        None => println!(
             "{pid:<5} {comm:<width$}",
             pid = r.pid,
-            comm = r.command,
             width = COMMAND_COL_WIDTH
-        );
-        for reason in r.reasons {
-            println!("  - {reason}");
+        ),
+    }
+    for line in result_details(r) {
+        println!("{line}");
+    }
+}
+
+/// The reason lines (and `--threads` line) under a process.
+fn result_details(r: &ProcResult) -> Vec<String> {
+    let mut lines: Vec<String> = r
+        .reasons
+        .iter()
+        .map(|reason| format!("  - {}", reason.label()))
+        .collect();
+    if let Some(threads) = &r.threads {
+        lines.push(format!("  {}", format_threads(threads)));
+    }
+    lines
+}
+
+/// `threads (N): tid name, ...` for the human `--threads` line.
+fn format_threads(threads: &[ThreadRow]) -> String {
+    if threads.is_empty() {
+        return "threads: <unreadable>".to_string();
+    }
+    let listed = threads
+        .iter()
+        .map(|t| format!("{} {}", t.tid, t.command))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("threads ({}): {listed}", threads.len())
+}
+
+/// `--stream`: print each process the moment its reasons are found, in
+/// process-table order, instead of collecting and sorting. Notices come
+/// last because the skip and error counts are only known after the walk.
+fn stream_targets(mut scans: Vec<TargetScan>, opts: &Options) -> Result<bool, AppError> {
+    println!("{}", privilege_mode_message());
+    println!("Streaming processes as they are found; order is not stable.");
+    opts.scopes.print_notice();
+
+    let multi = scans.len() > 1;
+    if let [scan] = scans.as_slice() {
+        println!("Target {}: {}", scan.target.mode(), scan.target);
+    }
+    println!();
+
+    let mut found = 0usize;
+    if scans.iter().any(TargetScan::needs_walk) {
+        let pids = list_pids().map_err(|e| AppError::Fatal(e.to_string()))?;
+        for pid in pids {
+            let ProcAccess::Ok(proc) = ProcSnapshot::capture(pid) else {
+                continue;
+            };
+            for scan in scans.iter_mut().filter(|s| s.needs_walk()) {
+                scan.scan_pid(&proc, opts);
+                let Some(mut r) = scan.results.remove(&pid) else {
+                    continue;
+                };
+                if opts.exclusions.excludes(pid, &r.command) {
+                    continue;
+                }
+                if opts.threads {
+                    r.threads = Some(thread_rows(pid));
+                }
+                print_result(&r, multi.then_some(&scan.target));
+                found += 1;
+            }
         }
     }
+
+    if found == 0 {
+        println!("No active reasons detected.");
+    }
+    let mut stats = ScanStats::default();
+    for scan in &scans {
+        stats.merge(&scan.stats);
+    }
+    stats.print_notices();
+    Ok(found > 0)
 }
 
 fn print_json(
     mode: &'static str,
     target: String,
     results: BTreeMap<i32, ProcResult>,
-    skipped: usize,
+    stats: &ScanStats,
+    envelope: &JsonEnvelope,
 ) {
-    let partial = skipped > 0;
+    let partial = stats.partial();
     let mut rows: Vec<ProcResult> = Vec::new();
     for (_pid, r) in results {
         rows.push(r);
     }
+    let commands = distinct_commands(rows.iter().map(|r| (r.pid, r.command.as_str())));
 
     let payload = json!({
         "privilege": privilege_mode(),
@@ -381,11 +1327,49 @@ fn print_json(
         "target_mode": mode,
         "target": target,
         "partial": partial,
-        "skipped": skipped,
+        "privilege_insufficient": privilege_insufficient(stats.skipped()),
+        "skipped": stats.skipped(),
+        "errored": stats.errored(),
+        "distinct_commands": commands,
         "results": rows,
     });
 
-    print_json_payload(&payload);
+    print_scan_envelope(payload, stats, envelope);
+}
+
+fn print_json_multi(scans: Vec<TargetScan>, stats: &ScanStats, envelope: &JsonEnvelope) {
+    let mut all: Vec<(i32, Comm)> = Vec::new();
+    let mut groups: Vec<serde_json::Value> = Vec::new();
+
+    for scan in scans {
+        let rows: Vec<ProcResult> = scan.results.into_values().collect();
+        all.extend(rows.iter().map(|r| (r.pid, r.command.clone())));
+        groups.push(json!({
+            "target_mode": scan.target.mode(),
+            "target": scan.target.to_string(),
+            "partial": scan.stats.partial(),
+            "skipped": scan.stats.skipped(),
+            "errored": scan.stats.errored(),
+            "distinct_commands": distinct_commands(rows.iter().map(|r| (r.pid, r.command.as_str()))),
+            "results": rows,
+        }));
+    }
+
+    let commands = distinct_commands(all.iter().map(|(pid, c)| (*pid, c.as_str())));
+    let payload = json!({
+        "privilege": privilege_mode(),
+        "mode_message": privilege_mode_message(),
+        "mode": "whyopen",
+        "target_mode": "multi",
+        "partial": stats.partial(),
+        "privilege_insufficient": privilege_insufficient(stats.skipped()),
+        "skipped": stats.skipped(),
+        "errored": stats.errored(),
+        "distinct_commands": commands,
+        "targets": groups,
+    });
+
+    print_scan_envelope(payload, stats, envelope);
 }
 
 #[cfg(test)]
@@ -396,20 +1380,288 @@ mod tests {
     fn proc_result_serializes_json() {
         let result = ProcResult {
             pid: 1234,
-            command: "myapp".to_string(),
-            reasons: vec!["open fd".to_string(), "memory mapped".to_string()],
+            command: Comm::known("myapp"),
+            exe: Some("/opt/myapp/bin/myapp (deleted)".to_string()),
+            reasons: vec![
+                Reason::OpenFd {
+                    fd: 4,
+                    access: None,
+                    flags: None,
+                },
+                Reason::Mmap {
+                    perms: vec!["r-xp".to_string()],
+                    process_rss_kb: None,
+                    process_pss_kb: None,
+                    deleted: false,
+                },
+            ],
+            threads: None,
         };
         let json = serde_json::to_value(&result).unwrap();
         assert_eq!(json["pid"], 1234);
         assert_eq!(json["command"], "myapp");
+        assert_eq!(json["exe"], "/opt/myapp/bin/myapp (deleted)");
         assert_eq!(json["reasons"].as_array().unwrap().len(), 2);
     }
 
+    #[test]
+    fn reason_serializes_tagged() {
+        let fd = serde_json::to_value(Reason::OpenFd {
+            fd: 4,
+            access: None,
+            flags: None,
+        })
+        .unwrap();
+        assert_eq!(fd, serde_json::json!({ "type": "open_fd", "fd": 4 }));
+
+        let fd = serde_json::to_value(Reason::OpenFd {
+            fd: 4,
+            access: Some("wo"),
+            flags: Some(vec!["wo", "append"]),
+        })
+        .unwrap();
+        assert_eq!(
+            fd,
+            serde_json::json!({ "type": "open_fd", "fd": 4, "access": "wo", "flags": ["wo", "append"] })
+        );
+
+        let mmap = serde_json::to_value(Reason::Mmap {
+            perms: vec!["r-xp".to_string()],
+            process_rss_kb: None,
+            process_pss_kb: None,
+            deleted: false,
+        })
+        .unwrap();
+        assert_eq!(
+            mmap,
+            serde_json::json!({ "type": "mmap", "perms": ["r-xp"] })
+        );
+
+        let socket = serde_json::to_value(Reason::Socket {
+            proto: "tcp",
+            state: "listening".to_string(),
+            timer: None,
+        })
+        .unwrap();
+        assert_eq!(
+            socket,
+            serde_json::json!({ "type": "socket", "proto": "tcp", "state": "listening" })
+        );
+
+        let cwd = serde_json::to_value(Reason::Cwd).unwrap();
+        assert_eq!(cwd, serde_json::json!({ "type": "cwd" }));
+
+        let timed = serde_json::to_value(Reason::Socket {
+            proto: "tcp",
+            state: "established".to_string(),
+            timer: Some(SocketTimer {
+                name: "keepalive",
+                expires_secs: 7199,
+            }),
+        })
+        .unwrap();
+        assert_eq!(
+            timed["timer"],
+            serde_json::json!({ "name": "keepalive", "expires_secs": 7199 })
+        );
+    }
+
+    #[test]
+    fn reason_labels() {
+        assert_eq!(
+            Reason::OpenFd {
+                fd: 7,
+                access: None,
+                flags: None,
+            }
+            .label(),
+            "open fd 7"
+        );
+        assert_eq!(
+            Reason::OpenFd {
+                fd: 3,
+                access: Some("rw"),
+                flags: Some(vec!["rw", "append"]),
+            }
+            .label(),
+            "open fd 3 (rw, append)"
+        );
+        assert_eq!(
+            Reason::OpenFd {
+                fd: 3,
+                access: Some("ro"),
+                flags: None,
+            }
+            .label(),
+            "open fd 3 (ro)"
+        );
+        assert_eq!(
+            Reason::Mmap {
+                perms: vec!["r--p".to_string(), "r-xp".to_string()],
+                process_rss_kb: Some(2048),
+                process_pss_kb: Some(1024),
+                deleted: false,
+            }
+            .label(),
+            "memory mapped (r--p, r-xp; process rss 2.0 MiB, pss 1.0 MiB)"
+        );
+        let gone = Reason::Mmap {
+            perms: vec!["rw-s".to_string()],
+            process_rss_kb: None,
+            process_pss_kb: None,
+            deleted: true,
+        };
+        assert_eq!(gone.label(), "memory mapped (rw-s, deleted)");
+        let replaced = Reason::Executing { deleted: true };
+        assert_eq!(replaced.label(), "executing (deleted)");
+        assert_eq!(
+            serde_json::to_value(&replaced).unwrap()["type"],
+            "executing"
+        );
+        assert_eq!(Reason::Executing { deleted: false }.label(), "executing");
+        assert_eq!(serde_json::to_value(&gone).unwrap()["deleted"], true);
+        assert_eq!(
+            Reason::Socket {
+                proto: "udp",
+                state: "connected".to_string(),
+                timer: None,
+            }
+            .label(),
+            "socket udp connected"
+        );
+        assert_eq!(
+            Reason::Socket {
+                proto: "tcp",
+                state: "established".to_string(),
+                timer: Some(SocketTimer {
+                    name: "time_wait",
+                    expires_secs: 42,
+                }),
+            }
+            .label(),
+            "socket tcp established, time wait timer (42s left)"
+        );
+    }
+
+    #[test]
+    fn format_kb_as_mib_rounds() {
+        assert_eq!(format_kb_as_mib(12288), "12.0 MiB");
+        assert_eq!(format_kb_as_mib(1536), "1.5 MiB");
+        assert_eq!(format_kb_as_mib(0), "0.0 MiB");
+    }
+
+    #[test]
+    fn mmap_reason_without_mem_dedups_perms() {
+        let perms = vec!["r-xp".to_string(), "r--p".to_string(), "r--p".to_string()];
+        assert_eq!(
+            mmap_reason(1, perms, false, false),
+            Reason::Mmap {
+                perms: vec!["r--p".to_string(), "r-xp".to_string()],
+                process_rss_kb: None,
+                process_pss_kb: None,
+                deleted: false,
+            }
+        );
+    }
+
+    #[test]
+    fn scopes_limit_path_scan() {
+        let pid = std::process::id() as i32;
+        let path = std::env::temp_dir().join(format!("zenlixem_scopes_{pid}"));
+        let file = std::fs::File::create(&path).unwrap();
+        let target = FileMatch::Path(path.display().to_string());
+        let proc = ProcSnapshot {
+            pid,
+            comm: Comm::known("test"),
+        };
+        let opts = |scopes| Options {
+            json: false,
+            envelope: JsonEnvelope::default(),
+            mem: false,
+            fd_flags: false,
+            socket_timers: false,
+            scopes,
+            threads: false,
+            format: OutputFormat::Human,
+            stream: false,
+            exclusions: Exclusions::new(&[], &[]),
+        };
+        let mut stats = ScanStats::default();
+
+        let found = scan_pid_path(&proc, &target, &opts(ScanScopes::default()), &mut stats);
+        assert!(found.is_some_and(|r| matches!(r.reasons[0], Reason::OpenFd { .. })));
+        let mmap_only = ScanScopes::new(true, false);
+        assert!(scan_pid_path(&proc, &target, &opts(mmap_only), &mut stats).is_none());
+
+        drop(file);
+        let _ = std::fs::remove_file(&path);
+
+        let cwd = std::env::current_dir().unwrap();
+        let here = file_match_for_path(&cwd).unwrap();
+        let found = scan_pid_path(&proc, &here, &opts(ScanScopes::default()), &mut stats).unwrap();
+        assert!(found.reasons.contains(&Reason::Cwd));
+        assert_eq!(Reason::Cwd.label(), "working directory (cwd)");
+    }
+
+    #[test]
+    fn format_threads_counts_and_lists() {
+        let threads = vec![
+            ThreadRow {
+                tid: 10,
+                command: "app".to_string(),
+            },
+            ThreadRow {
+                tid: 13,
+                command: "worker-3".to_string(),
+            },
+        ];
+        assert_eq!(format_threads(&threads), "threads (2): 10 app, 13 worker-3");
+        assert_eq!(format_threads(&[]), "threads: <unreadable>");
+    }
+
     #[test]
     fn reasons_dedup_sort() {
-        let mut reasons = vec!["memory mapped".to_string(), "open fd".to_string()];
-        reasons.sort();
+        let mmap = Reason::Mmap {
+            perms: vec!["r--p".to_string()],
+            process_rss_kb: None,
+            process_pss_kb: None,
+            deleted: false,
+        };
+        let mut reasons = vec![
+            mmap.clone(),
+            Reason::OpenFd {
+                fd: 9,
+                access: None,
+                flags: None,
+            },
+            Reason::OpenFd {
+                fd: 3,
+                access: None,
+                flags: None,
+            },
+            Reason::OpenFd {
+                fd: 9,
+                access: None,
+                flags: None,
+            },
+        ];
+        sort_reasons(&mut reasons, Reason::kind);
         reasons.dedup();
-        assert_eq!(reasons, vec!["memory mapped", "open fd"]);
+        assert_eq!(
+            reasons,
+            vec![
+                Reason::OpenFd {
+                    fd: 3,
+                    access: None,
+                    flags: None,
+                },
+                Reason::OpenFd {
+                    fd: 9,
+                    access: None,
+                    flags: None,
+                },
+                mmap
+            ]
+        );
     }
 }
@@ -0,0 +1,234 @@
+//! JSON contract checks: run the binary against a synthetic proc tree
+//! (`--proc-root`) and assert the documented envelope fields and types.
+
+use serde_json::Value;
+use std::fs;
+use std::os::unix::fs::symlink;
+use std::path::{Path, PathBuf};
+use testkit::{assert_scan_envelope, proc_tree, run_proc, run_proc_json};
+
+const BIN: &str = env!("CARGO_BIN_EXE_whyopen");
+
+/// A proc tree with one process holding `/srv/data.db` (fd 3 and a
+/// mapping) and an established connection on port 8080 (fd 4) with a
+/// keepalive timer armed, running a binary that has since been replaced.
+fn fixture(name: &str) -> PathBuf {
+    let root = proc_tree(
+        name,
+        "   0: 0100007F:1F90 0100007F:D431 01 00000000:00000000 02:000AFC80 00000000  1000        0 999 1 0000000000000000 20 4 30 10 -1\n",
+    );
+    let pid = root.join("4242");
+    symlink("/usr/bin/fixture (deleted)", pid.join("exe")).unwrap();
+    fs::create_dir_all(pid.join("fdinfo")).unwrap();
+    fs::write(
+        pid.join("fdinfo/3"),
+        "pos:\t0\nflags:\t0100002\nmnt_id:\t29\n",
+    )
+    .unwrap();
+    root
+}
+
+fn run_json(root: &Path, args: &[&str]) -> Value {
+    run_proc_json(BIN, root, args)
+}
+
+/// The fields every whyopen envelope carries, with their types.
+fn assert_envelope(v: &Value, target_mode: &str) {
+    assert_scan_envelope(v, "whyopen");
+    assert_eq!(v["target_mode"], target_mode, "{v}");
+}
+
+#[test]
+fn path_target_json_shape() {
+    let root = fixture("whyopen_path");
+    let v = run_json(&root, &["/srv/data.db"]);
+    assert_envelope(&v, "path");
+    assert_eq!(v["target"], "/srv/data.db");
+    assert_eq!(v["scopes"], serde_json::json!(["fd", "mmap"]));
+
+    let results = v["results"].as_array().unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0]["pid"], 4242);
+    assert_eq!(results[0]["command"], "fixture");
+    assert_eq!(results[0]["exe"], "/usr/bin/fixture (deleted)");
+    let reasons = results[0]["reasons"].as_array().unwrap();
+    assert_eq!(reasons[0]["type"], "open_fd");
+    assert_eq!(reasons[0]["fd"], 3);
+    assert_eq!(reasons[0]["access"], "rw");
+    assert_eq!(reasons[1]["type"], "mmap");
+    assert!(reasons[1]["perms"].is_array());
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn port_and_multi_target_json_shape() {
+    let root = fixture("whyopen_port");
+    let v = run_json(&root, &["--socket-timers", "8080"]);
+    assert_envelope(&v, "port");
+    let reason = &v["results"][0]["reasons"][0];
+    assert_eq!(reason["type"], "socket");
+    assert_eq!(reason["proto"], "tcp");
+    assert!(reason["state"].is_string());
+    assert_eq!(reason["timer"]["name"], "keepalive");
+    assert_eq!(reason["timer"]["expires_secs"], 7200);
+
+    let v = run_json(&root, &["8080", "/srv/data.db"]);
+    assert_envelope(&v, "multi");
+    assert!(v["results"][0]["reasons"].is_null());
+    let targets = v["targets"].as_array().unwrap();
+    assert_eq!(targets.len(), 2);
+    for t in targets {
+        assert!(t["target_mode"].is_string());
+        assert!(t["partial"].is_boolean());
+        assert!(t["results"].is_array());
+    }
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn each_fd_is_its_own_reason_in_fd_order() {
+    let root = fixture("whyopen_fds");
+    symlink("/srv/data.db", root.join("4242/fd/12")).unwrap();
+    symlink("/srv/data.db", root.join("4242/fd/7")).unwrap();
+    let v = run_json(&root, &["--no-mmap", "/srv/data.db"]);
+    let fds: Vec<&Value> = v["results"][0]["reasons"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| &r["fd"])
+        .collect();
+    assert_eq!(fds, [3, 7, 12]);
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn fds_mode_lists_every_fd_by_kind() {
+    let root = fixture("whyopen_fds_mode");
+    symlink("pipe:[55]", root.join("4242/fd/5")).unwrap();
+    symlink("anon_inode:[eventfd]", root.join("4242/fd/6")).unwrap();
+    symlink("/tmp/scratch (deleted)", root.join("4242/fd/7")).unwrap();
+    let v = run_json(&root, &["--pid", "4242", "--fds"]);
+    assert_eq!(v["mode"], "whyopen");
+    assert_eq!(v["target_mode"], "fds");
+    assert_eq!(v["pid"], 4242);
+    assert_eq!(v["command"], "fixture");
+    let kinds: Vec<(&Value, &Value)> = v["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| (&r["fd"], &r["type"]))
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            (&3.into(), &"file".into()),
+            (&4.into(), &"socket".into()),
+            (&5.into(), &"pipe".into()),
+            (&6.into(), &"anon".into()),
+            (&7.into(), &"file".into()),
+        ]
+    );
+    assert_eq!(v["results"][1]["socket"]["local"], "127.0.0.1:8080");
+    assert_eq!(v["results"][1]["socket"]["state"], "established");
+    assert_eq!(v["results"][4]["target"], "/tmp/scratch");
+    assert_eq!(v["results"][4]["deleted"], true);
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn pid_mode_groups_what_the_process_holds() {
+    let root = fixture("whyopen_pid_mode");
+    symlink("/tmp/scratch (deleted)", root.join("4242/fd/7")).unwrap();
+    let v = run_json(&root, &["--pid", "4242"]);
+    assert_eq!(v["mode"], "whyopen");
+    assert_eq!(v["target_mode"], "pid");
+    assert_eq!(v["command"], "fixture");
+    let results = v["results"].as_array().unwrap();
+    let targets: Vec<&Value> = results.iter().map(|r| &r["target"]).collect();
+    assert_eq!(targets, ["/srv/data.db", "/tmp/scratch", "127.0.0.1:8080"]);
+
+    let types: Vec<&Value> = results[0]["reasons"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| &r["type"])
+        .collect();
+    assert_eq!(types, ["open_fd", "mmap"]);
+    assert_eq!(results[1]["deleted"], true);
+    assert_eq!(results[2]["reasons"][0]["type"], "socket");
+    assert_eq!(results[2]["reasons"][0]["proto"], "tcp");
+    assert_eq!(results[2]["remote"], "127.0.0.1:54321");
+
+    let out = run_proc(BIN, &root, &["--pid", "4243"]);
+    assert_eq!(out.status.code(), Some(1), "{out:?}");
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn pid_mode_keeps_connections_on_one_local_address_apart() {
+    let root = proc_tree(
+        "whyopen_pid_conns",
+        "   0: 0100007F:1F90 0100007F:D431 01 00000000:00000000 00:00000000 00000000  1000        0 999 1 0000000000000000 20 4 30 10 -1\n\
+         1: 0100007F:1F90 0100007F:D432 08 00000000:00000000 00:00000000 00000000  1000        0 1000 1 0000000000000000 20 4 30 10 -1\n",
+    );
+    symlink("socket:[1000]", root.join("4242/fd/8")).unwrap();
+    let v = run_json(&root, &["--pid", "4242", "--no-mmap"]);
+    let sockets: Vec<(&Value, &Value, &Value)> = v["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|r| r["target"] == "127.0.0.1:8080")
+        .map(|r| (&r["remote"], &r["reasons"][0]["state"], &r["reasons"][1]))
+        .collect();
+    assert_eq!(
+        sockets,
+        [
+            (
+                &"127.0.0.1:54321".into(),
+                &"established".into(),
+                &Value::Null
+            ),
+            (
+                &"127.0.0.1:54322".into(),
+                &"close_wait".into(),
+                &Value::Null
+            ),
+        ]
+    );
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn running_binary_is_executing_before_mapped() {
+    let root = fixture("whyopen_exe");
+    fs::write(
+        root.join("4242/maps"),
+        "55d000000000-55d000001000 r-xp 00000000 08:01 77 /usr/bin/fixture (deleted)\n",
+    )
+    .unwrap();
+    let v = run_json(&root, &["/usr/bin/fixture"]);
+    let reasons = v["results"][0]["reasons"].as_array().unwrap();
+    assert_eq!(reasons.len(), 2, "{v}");
+    assert_eq!(reasons[0]["type"], "executing");
+    assert_eq!(reasons[0]["deleted"], true);
+    assert_eq!(reasons[1]["type"], "mmap");
+    assert_eq!(reasons[1]["deleted"], true);
+
+    let v = run_json(&root, &["--no-mmap", "/usr/bin/fixture"]);
+    assert_eq!(v["results"], serde_json::json!([]));
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn unreadable_maps_keep_fd_evidence() {
+    let root = fixture("whyopen_maps_error");
+    fs::remove_file(root.join("4242/maps")).unwrap();
+    fs::create_dir(root.join("4242/maps")).unwrap();
+    let v = run_json(&root, &["/srv/data.db"]);
+    assert_eq!(v["errored"], 1, "{v}");
+    assert_eq!(v["partial"], true);
+    let reasons = v["results"][0]["reasons"].as_array().unwrap();
+    assert_eq!(reasons.len(), 1, "{v}");
+    assert_eq!(reasons[0]["type"], "open_fd");
+    let _ = fs::remove_dir_all(&root);
+}
@@ -6,14 +6,24 @@ use serde::Serialize;
 use serde_json::json;
 use std::fs;
 use std::io;
-use std::path::Path;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use cliutil::{
-    build_target, error, git_sha, print_header, print_info, print_json_error, print_json_payload,
-    print_version, privilege_mode, privilege_mode_message, short_sha, AppError,
+    build_target, exit_with_error, git_sha, load_passwd, print_header, print_info,
+    print_json_envelope, print_json_error, print_version, privilege_insufficient, privilege_mode,
+    privilege_mode_message, set_color_choice, short_sha, validate_fields, warn, AppError,
+    ColorChoice, ExitCode, JsonEnvelope, PrivilegeMode, Table,
+};
+use procscan::{
+    fd_rows, list_pids, mask_has_capability, proc_owner_uid, read_comm_access,
+    read_comm_best_effort, read_fd_links_access, read_fd_targets_access, read_proc_maps_access,
+    read_proc_net_sockets, read_proc_status_access, set_command_source, set_unknown_label, Comm,
+    CommandSource, ProcAccess, ProcNetSockets, SocketTable, CAP_DAC_READ_SEARCH, CAP_SYS_PTRACE,
+    FD_ROW_FIELDS, PROC_NET_TABLES,
 };
-use procscan::{list_pids, read_proc_net_sockets, ProcAccess};
 
 #[derive(Parser, Debug)]
 #[command(
@@ -24,6 +34,8 @@ use procscan::{list_pids, read_proc_net_sockets, ProcAccess};
     after_help = r#"EXAMPLES:
   zenlixem doctor
   zenlixem doctor --json
+  zenlixem files 1234
+  zenlixem probe 1234
   zenlixem completions bash > zenlixem.bash
 "#
 )]
@@ -38,6 +50,16 @@ struct Args {
     )]
     info: bool,
 
+    #[arg(
+        long = "color",
+        global = true,
+        value_enum,
+        value_name = "WHEN",
+        default_value = "auto",
+        help = "Color warnings, errors and headers: auto (terminal, unless NO_COLOR is set), always, or never"
+    )]
+    color: ColorChoice,
+
     #[command(subcommand)]
     command: Option<Cmd>,
 }
@@ -45,13 +67,15 @@ struct Args {
 #[derive(Subcommand, Debug)]
 enum Cmd {
     Doctor(DoctorArgs),
+    Files(FilesArgs),
+    Probe(ProbeArgs),
     Completions(CompletionsArgs),
 }
 
 #[derive(Parser, Debug)]
 #[command(
     about = "Run environment checks",
-    long_about = "Run environment checks (procfs visibility, /proc/net access, audit log, journalctl, build metadata).",
+    long_about = "Run environment checks (procfs visibility, /proc/net access, audit log, journalctl, passwd database, build metadata).",
     after_help = r#"EXAMPLES:
   zenlixem doctor
   zenlixem doctor --json
@@ -60,6 +84,104 @@ enum Cmd {
 struct DoctorArgs {
     #[arg(long = "json", help = "Output result as JSON")]
     json: bool,
+
+    #[arg(
+        long = "no-mode-message",
+        requires = "json",
+        help = "Omit the human-oriented mode_message field from JSON output"
+    )]
+    no_mode_message: bool,
+
+    #[arg(
+        long = "fields",
+        value_name = "LIST",
+        value_delimiter = ',',
+        requires = "json",
+        help = "Only include these comma-separated result fields in JSON output"
+    )]
+    fields: Vec<String>,
+}
+
+#[derive(Parser, Debug)]
+#[command(
+    about = "List a process's open files",
+    long_about = "List every open fd of a process (like lsof -p), classified as file, socket, pipe, anon or other.\n\nSockets are resolved against /proc/net/{tcp,tcp6,udp,udp6}; unlinked files are flagged as deleted.",
+    after_help = r#"EXAMPLES:
+  zenlixem files 1234
+  zenlixem files --json $(pidof nginx | cut -d' ' -f1)
+"#
+)]
+struct FilesArgs {
+    #[arg(value_name = "PID", help = "Process to inspect")]
+    pid: i32,
+
+    #[arg(long = "json", help = "Output result as JSON")]
+    json: bool,
+
+    #[arg(
+        long = "no-mode-message",
+        requires = "json",
+        help = "Omit the human-oriented mode_message field from JSON output"
+    )]
+    no_mode_message: bool,
+
+    #[arg(
+        long = "fields",
+        value_name = "LIST",
+        value_delimiter = ',',
+        requires = "json",
+        help = "Only include these comma-separated result fields in JSON output"
+    )]
+    fields: Vec<String>,
+
+    #[arg(
+        long = "unknown-label",
+        value_name = "LABEL",
+        help = "Text shown for a process name that cannot be read (default <unknown>)"
+    )]
+    unknown_label: Option<String>,
+
+    #[arg(
+        long = "command-source",
+        value_enum,
+        value_name = "SOURCE",
+        default_value = "cmdline",
+        help = "Where command names come from: cmdline (comm, completed from argv[0] when truncated), comm or argv"
+    )]
+    command_source: CommandSource,
+}
+
+#[derive(Parser, Debug)]
+#[command(
+    about = "Explain why a process cannot be inspected",
+    long_about = "Try each /proc read the scanners rely on (comm, status, fd, maps) for one process and report the outcome of each, plus whether re-running as root would help.",
+    after_help = r#"EXAMPLES:
+  zenlixem probe 1
+  zenlixem probe --json 1234
+"#
+)]
+struct ProbeArgs {
+    #[arg(value_name = "PID", help = "Process to probe")]
+    pid: i32,
+
+    #[arg(long = "json", help = "Output result as JSON")]
+    json: bool,
+
+    #[arg(
+        long = "no-mode-message",
+        requires = "json",
+        help = "Omit the human-oriented mode_message field from JSON output"
+    )]
+    no_mode_message: bool,
+
+    #[arg(
+        long = "fields",
+        value_name = "LIST",
+        value_delimiter = ',',
+        requires = "json",
+        help = "Only include these comma-separated result fields in JSON output"
+    )]
+    fields: Vec<String>,
 }
 
 #[derive(Parser, Debug)]
@@ -89,6 +211,9 @@ enum CheckStatus {
     Fail,
 }
 
+/// Result keys accepted by `zenlixem doctor --fields`.
+const CHECK_FIELDS: &[&str] = &["check", "status", "message"];
+
 #[derive(Clone, Debug, Serialize)]
 struct CheckResult {
     check: &'static str,
@@ -104,61 +229,239 @@ fn main() {
         Err(e) => {
             if matches!(e.kind(), ErrorKind::DisplayHelp | ErrorKind::DisplayVersion) {
                 let _ = e.print();
-                std::process::exit(0);
+                ExitCode::Success.exit();
             }
             if json_requested {
                 print_json_error(AppError::InvalidInput(e.to_string()));
             } else {
                 let _ = e.print();
             }
-            std::process::exit(1);
+            ExitCode::InvalidInput.exit();
         }
     };
 
     match run(args) {
-        Ok(code) => std::process::exit(code),
-        Err(AppError::InvalidInput(e)) => {
-            if json_requested {
-                print_json_error(AppError::InvalidInput(e));
-            } else {
-                error(&e);
-            }
-            std::process::exit(1);
-        }
-        Err(AppError::Fatal(e)) => {
-            if json_requested {
-                print_json_error(AppError::Fatal(e));
-            } else {
-                error(&e);
-            }
-            std::process::exit(2);
-        }
+        Ok(code) => code.exit(),
+        Err(e) => exit_with_error(e, json_requested),
     }
 }
 
-fn run(args: Args) -> Result<i32, AppError> {
+fn run(args: Args) -> Result<ExitCode, AppError> {
+    set_color_choice(args.color);
+
     if args.version {
         print_version();
-        return Ok(0);
+        return Ok(ExitCode::Success);
     }
 
     if args.info {
         print_info();
-        return Ok(0);
+        return Ok(ExitCode::Success);
     }
 
     let Some(cmd) = args.command else {
         return Err(AppError::InvalidInput(
-            "missing command (try: zenlixem doctor | zenlixem completions bash)".to_string(),
+            "missing command (try: zenlixem doctor | zenlixem files PID | zenlixem probe PID | zenlixem completions bash)"
+                .to_string(),
         ));
     };
 
     match cmd {
-        Cmd::Doctor(d) => Ok(run_doctor(d.json)),
+        Cmd::Doctor(d) => {
+            validate_fields(&d.fields, CHECK_FIELDS)?;
+            Ok(run_doctor(d.json, &envelope(d.no_mode_message, d.fields)))
+        }
+        Cmd::Files(f) => {
+            validate_fields(&f.fields, FD_ROW_FIELDS)?;
+            if let Some(label) = &f.unknown_label {
+                set_unknown_label(label);
+            }
+            set_command_source(f.command_source);
+            run_files(f.pid, f.json, &envelope(f.no_mode_message, f.fields))?;
+            Ok(ExitCode::Success)
+        }
+        Cmd::Probe(p) => {
+            validate_fields(&p.fields, PROBE_FIELDS)?;
+            run_probe(p.pid, p.json, &envelope(p.no_mode_message, p.fields))?;
+            Ok(ExitCode::Success)
+        }
         Cmd::Completions(c) => {
             run_completions(c.shell)?;
-            Ok(0)
+            Ok(ExitCode::Success)
+        }
+    }
+}
+
+fn run_files(pid: i32, json_out: bool, envelope: &JsonEnvelope) -> Result<(), AppError> {
+    let targets = match read_fd_targets_access(pid) {
+        ProcAccess::Ok(v) => v,
+        ProcAccess::PermissionDenied => {
+            return Err(AppError::Fatal(format!(
+                "permission denied reading /proc/{pid}/fd (re-run as root)"
+            )))
+        }
+        ProcAccess::Gone => return Err(AppError::InvalidInput(format!("no such process: {pid}"))),
+        ProcAccess::Fatal(e) => return Err(AppError::Fatal(e.to_string())),
+    };
+    let command = read_comm_best_effort(pid);
+    let rows = fd_rows(targets, SocketTable::default().load(warn));
+
+    if json_out {
+        let payload = json!({
+            "privilege": privilege_mode(),
+            "mode_message": privilege_mode_message(),
+            "mode": "files",
+            "pid": pid,
+            "command": command,
+            "results": rows,
+        });
+        print_json_envelope(payload, envelope);
+        return Ok(());
+    }
+
+    println!("{}", privilege_mode_message());
+    println!("Process: {pid} ({command})");
+    if rows.is_empty() {
+        println!("No open files.");
+        return Ok(());
+    }
+    let mut table = Table::new(["FD", "TYPE", "TARGET"]).fixed(0);
+    for r in &rows {
+        table.row([r.fd.to_string(), r.kind.to_string(), r.detail()]);
+    }
+    table.print();
+    Ok(())
+}
+
+/// Result keys accepted by `probe --fields`.
+const PROBE_FIELDS: &[&str] = &["file", "path", "result", "detail"];
+
+/// Outcome of one /proc read attempted by `probe`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+struct ProbeRow {
+    file: &'static str,
+    path: String,
+    result: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+}
+
+impl ProbeRow {
+    fn new<T>(
+        pid: i32,
+        file: &'static str,
+        access: &ProcAccess<T>,
+        ok: impl Fn(&T) -> String,
+    ) -> Self {
+        let detail = match access {
+            ProcAccess::Ok(v) => Some(ok(v)),
+            ProcAccess::Fatal(e) => Some(e.to_string()),
+            ProcAccess::PermissionDenied | ProcAccess::Gone => None,
+        };
+        ProbeRow {
+            file,
+            path: format!("/proc/{pid}/{file}"),
+            result: access.outcome(),
+            detail,
+        }
+    }
+}
+
+/// What to do about the probe results, for the human summary.
+fn probe_advice(rows: &[ProbeRow], privileged: bool, owner_uid: Option<u32>) -> String {
+    let denied = rows
+        .iter()
+        .filter(|r| r.result == "permission_denied")
+        .count();
+    if denied == 0 {
+        if rows.iter().any(|r| r.result == "gone") {
+            return "the process exited while being probed".to_string();
         }
+        return "every read succeeded; this process can be fully inspected".to_string();
+    }
+    if !privileged {
+        let owner = owner_uid.map_or_else(String::new, |uid| format!(" (or as uid {uid})"));
+        return format!(
+            "{denied} read(s) denied: fd and maps need the same uid or CAP_SYS_PTRACE; re-run as root{owner}"
+        );
+    }
+    format!(
+        "{denied} read(s) denied despite privileges: check LSM policy (SELinux/AppArmor), \
+         hidepid on /proc, or yama ptrace_scope"
+    )
+}
+
+fn run_probe(pid: i32, json_out: bool, envelope: &JsonEnvelope) -> Result<(), AppError> {
+    let comm = read_comm_access(pid);
+    let status = read_proc_status_access(pid);
+    let fds = read_fd_links_access(pid);
+    let maps = read_proc_maps_access(pid);
+
+    let rows = vec![
+        ProbeRow::new(pid, "comm", &comm, |c| c.clone()),
+        ProbeRow::new(pid, "status", &status, |s| {
+            format!("state {}, ppid {}", s.state, s.ppid)
+        }),
+        ProbeRow::new(pid, "fd", &fds, |v| format!("{} fds", v.len())),
+        ProbeRow::new(pid, "maps", &maps, |v| format!("{} mappings", v.len())),
+    ];
+    if rows.iter().all(|r| r.result == "gone") {
+        return Err(AppError::InvalidInput(format!("no such process: {pid}")));
+    }
+
+    let command = match comm {
+        ProcAccess::Ok(c) => Comm::known(c),
+        ProcAccess::PermissionDenied | ProcAccess::Gone | ProcAccess::Fatal(_) => Comm::unknown(),
+    };
+    let owner_uid = proc_owner_uid(pid);
+    let denied = rows
+        .iter()
+        .filter(|r| r.result == "permission_denied")
+        .count();
+
+    if json_out {
+        let payload = json!({
+            "privilege": privilege_mode(),
+            "mode_message": privilege_mode_message(),
+            "mode": "probe",
+            "pid": pid,
+            "command": command,
+            "owner_uid": owner_uid,
+            "root_would_help": privilege_insufficient(denied),
+            "results": rows,
+        });
+        print_json_envelope(payload, envelope);
+        return Ok(());
+    }
+
+    println!("{}", privilege_mode_message());
+    match owner_uid {
+        Some(uid) => println!("Process: {pid} ({command}), owned by uid {uid}"),
+        None => println!("Process: {pid} ({command})"),
+    }
+    print_header("FILE     RESULT              DETAIL");
+    for r in &rows {
+        let line = format!(
+            "{:<8} {:<19} {}",
+            r.file,
+            r.result,
+            r.detail.as_deref().unwrap_or("")
+        );
+        println!("{}", line.trim_end());
+    }
+    println!();
+    println!(
+        "{}",
+        probe_advice(&rows, privilege_mode() == PrivilegeMode::Root, owner_uid)
+    );
+    Ok(())
+}
+
+fn envelope(no_mode_message: bool, fields: Vec<String>) -> JsonEnvelope {
+    JsonEnvelope {
+        omit_mode_message: no_mode_message,
+        fields,
+        ..JsonEnvelope::default()
     }
 }
 
@@ -197,7 +500,7 @@ fn detect_shell_from_env() -> Result<Shell, AppError> {
     }
 }
 
-fn run_doctor(json_out: bool) -> i32 {
+fn run_doctor(json_out: bool, envelope: &JsonEnvelope) -> ExitCode {
     let checks = collect_checks();
 
     let mut ok = 0usize;
@@ -212,13 +515,8 @@ fn run_doctor(json_out: bool) -> i32 {
         }
     }
 
-    let exit_code = if fail > 0 {
-        2
-    } else if warn > 0 {
-        1
-    } else {
-        0
-    };
+    let status = doctor_status(warn, fail);
+    let exit_code = doctor_exit_code(status);
 
     if json_out {
         let payload = json!({
@@ -227,10 +525,12 @@ fn run_doctor(json_out: bool) -> i32 {
             "mode": "doctor",
             "build_target": build_target(),
             "git_sha": git_sha(),
+            "status": status,
+            "exit_code": exit_code.code(),
             "summary": { "ok": ok, "warn": warn, "fail": fail },
             "results": checks,
         });
-        print_json_payload(&payload);
+        print_json_envelope(payload, envelope);
         return exit_code;
     }
 
@@ -254,15 +554,40 @@ fn run_doctor(json_out: bool) -> i32 {
     exit_code
 }
 
+/// Overall doctor status: the worst status among all checks.
+fn doctor_status(warn: usize, fail: usize) -> CheckStatus {
+    if fail > 0 {
+        CheckStatus::Fail
+    } else if warn > 0 {
+        CheckStatus::Warn
+    } else {
+        CheckStatus::Ok
+    }
+}
+
+/// Doctor exit status: 2 if any check failed, else 1 if any warned, else 0.
+fn doctor_exit_code(status: CheckStatus) -> ExitCode {
+    match status {
+        CheckStatus::Ok => ExitCode::Success,
+        CheckStatus::Warn => ExitCode::Warnings,
+        CheckStatus::Fail => ExitCode::Fatal,
+    }
+}
+
 fn collect_checks() -> Vec<CheckResult> {
     vec![
         check_os(),
         check_procfs(),
         check_list_pids(),
         check_proc_access_smoke(),
+        check_hidepid(),
+        check_ptrace_scope(),
+        check_capabilities(),
         check_proc_net(),
         check_audit_log(),
         check_journalctl(),
+        check_passwd(),
+        check_clock(),
         check_build_metadata(),
     ]
 }
@@ -324,18 +649,52 @@ fn check_list_pids() -> CheckResult {
 }
 
 fn check_proc_net() -> CheckResult {
-    match read_proc_net_sockets() {
-        Ok(sockets) => CheckResult {
-            check: "proc_net",
-            status: CheckStatus::Ok,
-            message: format!("{} sockets parsed", sockets.len()),
-        },
-        Err(e) => CheckResult {
-            check: "proc_net",
-            status: CheckStatus::Fail,
-            message: format!("cannot read /proc/net/*: {e}"),
-        },
+    let (status, message) = summarize_proc_net(&read_proc_net_sockets());
+    CheckResult {
+        check: "proc_net",
+        status,
+        message,
+    }
+}
+
+/// Per-protocol socket counts, e.g. "tcp=40 tcp6=12 udp=8 udp6=3".
+///
+/// Tables that failed to read are left out of the counts and warned about
+/// with the same text the scanners print. An absent table (the protocol is
+/// disabled) is noted but is not a problem, as in the scanners.
+fn summarize_proc_net(sockets: &ProcNetSockets) -> (CheckStatus, String) {
+    let counts: Vec<String> = PROC_NET_TABLES
+        .iter()
+        .filter_map(|&proto| {
+            let n = sockets.count(proto)?;
+            Some(format!("{}={n}", proto.table_name()))
+        })
+        .collect();
+    let warnings = sockets.warnings();
+    let absent: Vec<&str> = sockets.absent().iter().map(|p| p.table_name()).collect();
+
+    if counts.is_empty() {
+        let reason = if warnings.is_empty() {
+            format!("not present: {}", absent.join(", "))
+        } else {
+            warnings.join("; ")
+        };
+        return (
+            CheckStatus::Fail,
+            format!("cannot read /proc/net/*: {reason}"),
+        );
     }
+    let mut message = counts.join(" ");
+    if !absent.is_empty() {
+        message.push_str(&format!(" (not present: {})", absent.join(", ")));
+    }
+    if warnings.is_empty() {
+        return (CheckStatus::Ok, message);
+    }
+    (
+        CheckStatus::Warn,
+        format!("{message}; {}", warnings.join("; ")),
+    )
 }
 
 fn check_audit_log() -> CheckResult {
@@ -397,6 +756,161 @@ fn check_journalctl() -> CheckResult {
     }
 }
 
+/// NSS sources that serve users the flat `/etc/passwd` file doesn't list.
+fn nss_passwd_network_sources(nsswitch: &str) -> Vec<String> {
+    for line in nsswitch.lines() {
+        let line = line.trim();
+        let Some(rest) = line.strip_prefix("passwd:") else {
+            continue;
+        };
+        return rest
+            .split_whitespace()
+            .filter(|s| !s.starts_with('['))
+            .filter(|s| !matches!(*s, "files" | "compat" | "systemd" | "db"))
+            .map(|s| s.to_string())
+            .collect();
+    }
+    Vec::new()
+}
+
+fn check_passwd() -> CheckResult {
+    let users = match load_passwd() {
+        Ok(m) => m,
+        Err(e) => {
+            return CheckResult {
+                check: "passwd",
+                status: CheckStatus::Warn,
+                message: format!("/etc/passwd not readable: {e} (users will show as UIDs)"),
+            };
+        }
+    };
+
+    if !users.contains_key(&0) {
+        return CheckResult {
+            check: "passwd",
+            status: CheckStatus::Warn,
+            message: format!(
+                "{} users parsed but no uid 0 entry (file may be malformed)",
+                users.len()
+            ),
+        };
+    }
+
+    let nss = fs::read_to_string("/etc/nsswitch.conf")
+        .map(|s| nss_passwd_network_sources(&s))
+        .unwrap_or_default();
+    if !nss.is_empty() {
+        return CheckResult {
+            check: "passwd",
+            status: CheckStatus::Warn,
+            message: format!(
+                "{} users parsed; NSS also uses {} (those users will show as UIDs)",
+                users.len(),
+                nss.join(", ")
+            ),
+        };
+    }
+
+    CheckResult {
+        check: "passwd",
+        status: CheckStatus::Ok,
+        message: format!("{} users parsed", users.len()),
+    }
+}
+
+/// Skew between the system clock and a fresh file's mtime beyond which
+/// lasttouch's "N ago" output stops making sense.
+const CLOCK_SKEW_WARN_SECS: i64 = 60;
+
+/// 2024-01-01T00:00:00Z. A clock reading earlier than this was never set
+/// (e.g. an RTC that reset to the epoch).
+const CLOCK_FLOOR_EPOCH_SECS: i64 = 1_704_067_200;
+
+/// Signed seconds from `a` to `b`.
+fn seconds_between(a: SystemTime, b: SystemTime) -> i64 {
+    match b.duration_since(a) {
+        Ok(d) => d.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs() as i64),
+    }
+}
+
+fn clock_verdict(now_epoch_secs: i64, mtime_skew_secs: Option<i64>) -> CheckResult {
+    if now_epoch_secs < CLOCK_FLOOR_EPOCH_SECS {
+        return CheckResult {
+            check: "clock",
+            status: CheckStatus::Warn,
+            message: format!(
+                "system clock reads {now_epoch_secs}s since the epoch, before 2024 (not set?); lasttouch ages will be wrong"
+            ),
+        };
+    }
+
+    match mtime_skew_secs {
+        Some(skew) if skew.abs() > CLOCK_SKEW_WARN_SECS => CheckResult {
+            check: "clock",
+            status: CheckStatus::Warn,
+            message: format!(
+                "new file mtime is {skew:+}s off the system clock; lasttouch ages will be skewed"
+            ),
+        },
+        Some(skew) => CheckResult {
+            check: "clock",
+            status: CheckStatus::Ok,
+            message: format!("file timestamps match the system clock (skew {skew:+}s)"),
+        },
+        None => CheckResult {
+            check: "clock",
+            status: CheckStatus::Ok,
+            message: "system clock plausible (temp file check unavailable)".to_string(),
+        },
+    }
+}
+
+/// Create an empty, private file in `dir` that did not exist before.
+/// `create_new` refuses to follow a symlink planted at the name, and the
+/// name mixes in the clock so it cannot be guessed ahead of time.
+fn create_probe_file(dir: &Path) -> io::Result<(PathBuf, fs::File)> {
+    let pid = std::process::id();
+    for attempt in 0u32..16 {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+        let path = dir.join(format!(".zenlixem_clock_{pid}_{nanos:x}_{attempt}"));
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&path)
+        {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        "no free probe file name",
+    ))
+}
+
+/// Compare the system clock against the mtime the kernel stamps on a file
+/// created right now, which can differ when the temp dir lives on a network
+/// filesystem with its own clock.
+fn check_clock() -> CheckResult {
+    let before = SystemTime::now();
+    let mtime = create_probe_file(&std::env::temp_dir()).and_then(|(path, file)| {
+        let modified = file.metadata().and_then(|md| md.modified());
+        let _ = fs::remove_file(&path);
+        modified
+    });
+    let now_epoch_secs = seconds_between(UNIX_EPOCH, before);
+    clock_verdict(
+        now_epoch_secs,
+        mtime.ok().map(|m| seconds_between(before, m)),
+    )
+}
+
 fn check_build_metadata() -> CheckResult {
     let target = build_target();
     let sha = git_sha();
@@ -458,10 +972,282 @@ fn check_proc_access_smoke() -> CheckResult {
     }
 }
 
+/// `hidepid` of the `/proc` mount in `/proc/mounts` text (the last mount
+/// wins), with the `gid=` group exempt from it.
+fn proc_hidepid(mounts: &str) -> Option<(String, Option<String>)> {
+    let options = mounts.lines().rev().find_map(|l| {
+        let mut f = l.split_whitespace();
+        let (_, dir, fstype, opts) = (f.next()?, f.next()?, f.next()?, f.next()?);
+        (dir == "/proc" && fstype == "proc").then_some(opts)
+    })?;
+    let option = |key: &str| {
+        options
+            .split(',')
+            .find_map(|o| o.strip_prefix(key))
+            .map(str::to_string)
+    };
+    Some((
+        option("hidepid=").unwrap_or_else(|| "0".to_string()),
+        option("gid="),
+    ))
+}
+
+fn hidepid_verdict(mounts: &str) -> CheckResult {
+    let Some((hidepid, gid)) = proc_hidepid(mounts) else {
+        return CheckResult {
+            check: "hidepid",
+            status: CheckStatus::Warn,
+            message: "no proc mount on /proc in /proc/mounts".to_string(),
+        };
+    };
+    let exempt = gid.map_or_else(String::new, |g| format!(" or in group {g}"));
+    let effect = match hidepid.as_str() {
+        "0" | "off" => {
+            return CheckResult {
+                check: "hidepid",
+                status: CheckStatus::Ok,
+                message: "/proc mounted without hidepid".to_string(),
+            };
+        }
+        "1" | "noaccess" => {
+            "other users' /proc/<pid> entries are unreadable, so their fds and maps are skipped"
+        }
+        "2" | "invisible" => {
+            "other users' processes are hidden from /proc, so they are missing from every scan"
+        }
+        "4" | "ptraceable" => "only processes this user may ptrace are visible in /proc",
+        _ => "unknown mode; other users' processes may be hidden",
+    };
+    CheckResult {
+        check: "hidepid",
+        status: CheckStatus::Warn,
+        message: format!(
+            "/proc mounted with hidepid={hidepid}: {effect} unless run as root{exempt}"
+        ),
+    }
+}
+
+/// `hidepid` hides or locks other users' processes, the usual cause of
+/// partial whoholds/whyopen results.
+fn check_hidepid() -> CheckResult {
+    match fs::read_to_string("/proc/mounts") {
+        Ok(mounts) => hidepid_verdict(&mounts),
+        Err(e) => CheckResult {
+            check: "hidepid",
+            status: CheckStatus::Warn,
+            message: format!("cannot read /proc/mounts: {e}"),
+        },
+    }
+}
+
+/// Yama only restricts ptrace *attach*; the fd, maps and environ reads the
+/// tools make are ptrace *read* checks it does not touch, so any scope is
+/// reported as OK.
+fn ptrace_scope_verdict(value: Option<&str>) -> CheckResult {
+    let Some(value) = value else {
+        return CheckResult {
+            check: "ptrace_scope",
+            status: CheckStatus::Ok,
+            message: "yama not enabled".to_string(),
+        };
+    };
+    let scope = match value.trim() {
+        "0" => "classic ptrace permissions",
+        "1" => "attach restricted to descendants",
+        "2" => "attach restricted to CAP_SYS_PTRACE",
+        "3" => "attach disabled",
+        other => {
+            return CheckResult {
+                check: "ptrace_scope",
+                status: CheckStatus::Warn,
+                message: format!("unrecognized yama ptrace_scope value {other:?}"),
+            };
+        }
+    };
+    CheckResult {
+        check: "ptrace_scope",
+        status: CheckStatus::Ok,
+        message: format!(
+            "yama ptrace_scope={} ({scope}); /proc fd and maps reads are not affected",
+            value.trim()
+        ),
+    }
+}
+
+fn check_ptrace_scope() -> CheckResult {
+    match fs::read_to_string("/proc/sys/kernel/yama/ptrace_scope") {
+        Ok(s) => ptrace_scope_verdict(Some(&s)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => ptrace_scope_verdict(None),
+        Err(e) => CheckResult {
+            check: "ptrace_scope",
+            status: CheckStatus::Warn,
+            message: format!("cannot read yama ptrace_scope: {e}"),
+        },
+    }
+}
+
+/// Capabilities that let a scan see other users' processes.
+const SCAN_CAPABILITIES: &[(u32, &str)] = &[
+    (CAP_SYS_PTRACE, "cap_sys_ptrace"),
+    (CAP_DAC_READ_SEARCH, "cap_dac_read_search"),
+];
+
+fn capabilities_verdict(cap_eff: u64, root: bool) -> CheckResult {
+    let held: Vec<&str> = SCAN_CAPABILITIES
+        .iter()
+        .filter(|(cap, _)| mask_has_capability(cap_eff, *cap))
+        .map(|(_, name)| *name)
+        .collect();
+    if !held.is_empty() {
+        return CheckResult {
+            check: "capabilities",
+            status: CheckStatus::Ok,
+            message: format!("effective: {}", held.join(", ")),
+        };
+    }
+    let message = if root {
+        "running as root but without cap_sys_ptrace or cap_dac_read_search \
+         (dropped by a container or systemd unit?): other users' fds and maps \
+         are unreadable, so results will be partial"
+    } else {
+        "neither cap_sys_ptrace nor cap_dac_read_search held and not root: \
+         only this user's processes can be fully scanned, so results will be partial"
+    };
+    CheckResult {
+        check: "capabilities",
+        status: CheckStatus::Warn,
+        message: message.to_string(),
+    }
+}
+
+/// Effective capabilities of this process that matter for scanning.
+fn check_capabilities() -> CheckResult {
+    match read_proc_status_access(std::process::id() as i32) {
+        ProcAccess::Ok(status) => {
+            capabilities_verdict(status.cap_eff, privilege_mode() == PrivilegeMode::Root)
+        }
+        ProcAccess::PermissionDenied | ProcAccess::Gone => CheckResult {
+            check: "capabilities",
+            status: CheckStatus::Warn,
+            message: "cannot read /proc/self/status".to_string(),
+        },
+        ProcAccess::Fatal(e) => CheckResult {
+            check: "capabilities",
+            status: CheckStatus::Warn,
+            message: format!("cannot read /proc/self/status: {e}"),
+        },
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn capabilities_verdict_needs_a_scan_capability() {
+        let ptrace = 1u64 << CAP_SYS_PTRACE;
+        let v = capabilities_verdict(ptrace, false);
+        assert_eq!(v.status, CheckStatus::Ok);
+        assert_eq!(v.message, "effective: cap_sys_ptrace");
+
+        let both = ptrace | (1 << CAP_DAC_READ_SEARCH);
+        assert_eq!(
+            capabilities_verdict(both, true).message,
+            "effective: cap_sys_ptrace, cap_dac_read_search"
+        );
+
+        let none = capabilities_verdict(1 << 21, false);
+        assert_eq!(none.status, CheckStatus::Warn);
+        assert_eq!(
+            none.message,
+            "neither cap_sys_ptrace nor cap_dac_read_search held and not root: only this \
+             user's processes can be fully scanned, so results will be partial"
+        );
+        let dropped = capabilities_verdict(0, true);
+        assert_eq!(dropped.status, CheckStatus::Warn);
+        assert_eq!(
+            dropped.message,
+            "running as root but without cap_sys_ptrace or cap_dac_read_search (dropped by \
+             a container or systemd unit?): other users' fds and maps are unreadable, so \
+             results will be partial"
+        );
+    }
+
+    #[test]
+    fn hidepid_verdict_reads_the_proc_mount() {
+        let plain = "sysfs /sys sysfs rw 0 0\nproc /proc proc rw,nosuid,relatime 0 0\n";
+        assert_eq!(hidepid_verdict(plain).status, CheckStatus::Ok);
+
+        let hidden = "proc /proc proc rw,nosuid,hidepid=2,gid=10 0 0\n";
+        let v = hidepid_verdict(hidden);
+        assert_eq!(v.status, CheckStatus::Warn);
+        assert!(v.message.contains("hidepid=2"), "{}", v.message);
+        assert!(v.message.contains("group 10"), "{}", v.message);
+
+        let named = "proc /proc proc rw,hidepid=invisible 0 0\n";
+        assert_eq!(proc_hidepid(named), Some(("invisible".to_string(), None)));
+        // A later mount over /proc is the one in effect.
+        let remount = format!("{hidden}{plain}");
+        assert_eq!(hidepid_verdict(&remount).status, CheckStatus::Ok);
+        assert_eq!(hidepid_verdict("").status, CheckStatus::Warn);
+    }
+
+    #[test]
+    fn ptrace_scope_verdict_values() {
+        assert_eq!(ptrace_scope_verdict(None).status, CheckStatus::Ok);
+        let v = ptrace_scope_verdict(Some("2\n"));
+        assert_eq!(v.status, CheckStatus::Ok);
+        assert!(v.message.contains("ptrace_scope=2"), "{}", v.message);
+        assert_eq!(ptrace_scope_verdict(Some("x")).status, CheckStatus::Warn);
+    }
+
+    #[test]
+    fn summarize_proc_net_counts_per_protocol() {
+        use procscan::ProcNetProto;
+        use std::io;
+
+        let sockets = |unreadable: Vec<(ProcNetProto, io::ErrorKind)>| ProcNetSockets {
+            entries: Vec::new(),
+            unreadable: unreadable
+                .into_iter()
+                .map(|(proto, kind)| (proto, io::Error::from(kind)))
+                .collect(),
+        };
+        let (status, message) = summarize_proc_net(&sockets(Vec::new()));
+        assert_eq!(status, CheckStatus::Ok);
+        assert_eq!(message, "tcp=0 tcp6=0 udp=0 udp6=0");
+
+        // A disabled protocol has no table: noted, not warned about.
+        let no_v6 = sockets(vec![
+            (ProcNetProto::Tcp6, io::ErrorKind::NotFound),
+            (ProcNetProto::Udp6, io::ErrorKind::NotFound),
+        ]);
+        let (status, message) = summarize_proc_net(&no_v6);
+        assert_eq!(status, CheckStatus::Ok);
+        assert_eq!(message, "tcp=0 udp=0 (not present: tcp6, udp6)");
+
+        let denied = sockets(vec![(ProcNetProto::Udp6, io::ErrorKind::PermissionDenied)]);
+        let (status, message) = summarize_proc_net(&denied);
+        assert_eq!(status, CheckStatus::Warn);
+        assert_eq!(
+            message,
+            "tcp=0 tcp6=0 udp=0; udp6 socket table unreadable: permission denied"
+        );
+
+        let none = sockets(
+            PROC_NET_TABLES
+                .iter()
+                .map(|&p| (p, io::ErrorKind::NotFound))
+                .collect(),
+        );
+        let (status, message) = summarize_proc_net(&none);
+        assert_eq!(status, CheckStatus::Fail);
+        assert_eq!(
+            message,
+            "cannot read /proc/net/*: not present: tcp, tcp6, udp, udp6"
+        );
+    }
+
     #[test]
     fn check_status_serializes_lowercase() {
         let ok_val = serde_json::to_value(CheckStatus::Ok).unwrap();
@@ -503,23 +1289,87 @@ mod tests {
         }
     }
 
+    #[test]
+    fn clock_verdict_flags_skew_and_unset_clock() {
+        let now = 1_760_000_000;
+        assert_eq!(clock_verdict(now, Some(0)).status, CheckStatus::Ok);
+        assert_eq!(clock_verdict(now, None).status, CheckStatus::Ok);
+        let edge = clock_verdict(now, Some(CLOCK_SKEW_WARN_SECS));
+        assert_eq!(edge.status, CheckStatus::Ok);
+        assert_eq!(
+            edge.message,
+            "file timestamps match the system clock (skew +60s)"
+        );
+        let past = clock_verdict(now, Some(CLOCK_SKEW_WARN_SECS + 1));
+        assert_eq!(past.status, CheckStatus::Warn);
+
+        let skewed = clock_verdict(now, Some(-3600));
+        assert_eq!(skewed.status, CheckStatus::Warn);
+        assert!(skewed.message.contains("-3600s"));
+
+        assert_eq!(clock_verdict(86_400, Some(0)).status, CheckStatus::Warn);
+        let floor = clock_verdict(CLOCK_FLOOR_EPOCH_SECS, None);
+        assert_eq!(floor.status, CheckStatus::Ok);
+        assert_eq!(
+            clock_verdict(CLOCK_FLOOR_EPOCH_SECS - 1, None).status,
+            CheckStatus::Warn
+        );
+    }
+
+    #[test]
+    fn probe_row_records_outcome_and_detail() {
+        let ok = ProbeRow::new(7, "fd", &ProcAccess::Ok(vec![1, 2, 3]), |v| {
+            format!("{} fds", v.len())
+        });
+        assert_eq!(ok.path, "/proc/7/fd");
+        assert_eq!(ok.result, "ok");
+        assert_eq!(ok.detail.as_deref(), Some("3 fds"));
+
+        let denied = ProbeRow::new(7, "maps", &ProcAccess::<()>::PermissionDenied, |_| {
+            String::new()
+        });
+        let json = serde_json::to_value(&denied).unwrap();
+        assert_eq!(json["result"], "permission_denied");
+        assert!(json.get("detail").is_none());
+    }
+
+    #[test]
+    fn probe_advice_distinguishes_privilege() {
+        let row = |file, result| ProbeRow {
+            file,
+            path: String::new(),
+            result,
+            detail: None,
+        };
+        let clean = [row("comm", "ok"), row("fd", "ok")];
+        assert!(probe_advice(&clean, false, None).starts_with("every read succeeded"));
+
+        let denied = [row("comm", "ok"), row("fd", "permission_denied")];
+        let advice = probe_advice(&denied, false, Some(1000));
+        assert!(advice.contains("re-run as root (or as uid 1000)"));
+        assert!(probe_advice(&denied, true, Some(1000)).contains("despite privileges"));
+    }
+
+    #[test]
+    fn nss_passwd_network_sources_filters_local() {
+        let conf =
+            "# comment\npasswd:         files systemd sss [NOTFOUND=return] ldap\ngroup: files\n";
+        assert_eq!(nss_passwd_network_sources(conf), vec!["sss", "ldap"]);
+        assert!(nss_passwd_network_sources("passwd: files systemd\n").is_empty());
+        assert!(nss_passwd_network_sources("").is_empty());
+    }
+
     #[test]
     fn doctor_exit_code_logic() {
         // No fails → 0
-        assert_eq!(doctor_exit_code(5, 0, 0), 0);
+        assert_eq!(doctor_status(0, 0), CheckStatus::Ok);
+        assert_eq!(doctor_exit_code(doctor_status(0, 0)), ExitCode::Success);
         // Has warns → 1
-        assert_eq!(doctor_exit_code(3, 2, 0), 1);
+        assert_eq!(doctor_status(2, 0), CheckStatus::Warn);
+        assert_eq!(doctor_exit_code(doctor_status(2, 0)), ExitCode::Warnings);
+        assert_eq!(ExitCode::Warnings.code(), 1);
         // Has fails → 2
-        assert_eq!(doctor_exit_code(0, 1, 1), 2);
-    }
-
-    fn doctor_exit_code(_ok: usize, warn: usize, fail: usize) -> i32 {
-        if fail > 0 {
-            2
-        } else if warn > 0 {
-            1
-        } else {
-            0
-        }
+        assert_eq!(doctor_status(1, 1), CheckStatus::Fail);
+        assert_eq!(doctor_exit_code(doctor_status(1, 1)), ExitCode::Fatal);
     }
 }
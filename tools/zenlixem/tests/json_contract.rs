@@ -0,0 +1,97 @@
+//! JSON contract checks: run the subcommands against the test process
+//! itself (or the host, for doctor) and assert the documented envelope
+//! fields and types.
+
+use serde_json::Value;
+use std::fs;
+use std::process::Command;
+use testkit::{assert_base_envelope, stdout_json};
+
+const BIN: &str = env!("CARGO_BIN_EXE_zenlixem");
+
+fn run_json(args: &[&str]) -> Value {
+    let out = Command::new(BIN).args(args).arg("--json").output().unwrap();
+    assert!(out.status.success(), "{args:?}: {out:?}");
+    stdout_json(&out)
+}
+
+#[test]
+fn files_json_shape() {
+    let path = std::env::temp_dir().join(format!("zenlixem_json_files_{}", std::process::id()));
+    let file = fs::File::create(&path).unwrap();
+    let pid = std::process::id().to_string();
+
+    let v = run_json(&["files", &pid]);
+    assert_base_envelope(&v, "files");
+    assert_eq!(v["pid"].to_string(), pid);
+    assert!(v["command"].is_string(), "{v}");
+    let results = v["results"].as_array().unwrap();
+    for row in results {
+        assert!(row["fd"].is_i64(), "{row}");
+        assert!(row["type"].is_string(), "{row}");
+        assert!(row["target"].is_string(), "{row}");
+        assert!(row["deleted"].is_boolean(), "{row}");
+    }
+    let target = path.display().to_string();
+    let held = results.iter().find(|r| r["target"] == target.as_str());
+    assert_eq!(held.map(|r| &r["type"]), Some(&"file".into()), "{v}");
+
+    drop(file);
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn probe_json_shape() {
+    let pid = std::process::id().to_string();
+    let v = run_json(&["probe", &pid]);
+    assert_base_envelope(&v, "probe");
+    assert_eq!(v["pid"].to_string(), pid);
+    assert!(v["owner_uid"].is_u64(), "{v}");
+    assert_eq!(v["root_would_help"], false);
+    let files: Vec<&Value> = v["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| &r["file"])
+        .collect();
+    assert_eq!(files, ["comm", "status", "fd", "maps"]);
+    for row in v["results"].as_array().unwrap() {
+        assert_eq!(row["result"], "ok", "{row}");
+        assert!(row["path"].is_string(), "{row}");
+    }
+}
+
+/// Which checks pass depends on the host, so only the shape is fixed; the
+/// exit code must agree with the reported status.
+#[test]
+fn doctor_json_shape() {
+    let out = Command::new(BIN)
+        .args(["doctor", "--json"])
+        .output()
+        .unwrap();
+    let v = stdout_json(&out);
+    assert_base_envelope(&v, "doctor");
+    assert!(v["build_target"].is_string(), "{v}");
+    assert!(v["git_sha"].is_string(), "{v}");
+    assert_eq!(
+        out.status.code().map(Value::from),
+        Some(v["exit_code"].clone())
+    );
+    let status = v["status"].as_str().unwrap();
+    assert!(["ok", "warn", "fail"].contains(&status), "{v}");
+
+    let results = v["results"].as_array().unwrap();
+    let mut counts = [0u64; 3];
+    for check in results {
+        assert!(check["check"].is_string(), "{check}");
+        assert!(check["message"].is_string(), "{check}");
+        match check["status"].as_str() {
+            Some("ok") => counts[0] += 1,
+            Some("warn") => counts[1] += 1,
+            Some("fail") => counts[2] += 1,
+            other => panic!("unexpected status {other:?}: {check}"),
+        }
+    }
+    let summary = &v["summary"];
+    assert_eq!([&summary["ok"], &summary["warn"], &summary["fail"]], counts);
+}